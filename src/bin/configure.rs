@@ -1,7 +1,11 @@
 use clap::Parser;
 use stembot_rust::{
     dao::kvstore::KVStore,
-    models::config::LogLevel,
+    models::{
+        config::{LogFormat, LogLevel},
+        control::Encoding,
+        routing::{format_labels, parse_labels, validate_name, Labels},
+    },
 };
 
 #[derive(Parser, Debug)]
@@ -46,6 +50,45 @@ struct Args {
     #[clap(long, help = "Seconds before a pending message is discarded")]
     message_timeout_secs: Option<u32>,
 
+    #[clap(long, help = "Maximum concurrent SyncProcess commands (0 = unlimited)")]
+    max_processes: Option<u32>,
+
+    #[clap(long, help = "CPU seconds allowed per process (0 = unlimited)")]
+    rlimit_cpu_secs: Option<u64>,
+
+    #[clap(long, help = "Address space allowed per process in bytes (0 = unlimited)")]
+    rlimit_as_bytes: Option<u64>,
+
+    #[clap(long, help = "Open files allowed per process (0 = unlimited)")]
+    rlimit_nofile: Option<u64>,
+
+    #[clap(long, help = "Bytes of stdout and of stderr kept per process (0 = unlimited)")]
+    max_output_bytes: Option<u64>,
+
+    #[clap(long, help = "Compression for request bodies sent to other agents (none/zlib/gzip/zstd)")]
+    body_encoding: Option<Encoding>,
+
+    #[clap(long, help = "Path of the agent log file (empty string = stderr only)")]
+    log_file: Option<String>,
+
+    #[clap(long, help = "Size in bytes at which the log file is rotated (0 = never)")]
+    log_file_max_bytes: Option<u64>,
+
+    #[clap(long, help = "Number of rotated log files kept")]
+    log_file_count: Option<u32>,
+
+    #[clap(long, help = "Log line format (text/json)")]
+    log_format: Option<LogFormat>,
+
+    #[clap(long, help = "Address serving /metrics, e.g. 127.0.0.1:9100 (empty string = disabled)")]
+    metrics_address: Option<String>,
+
+    #[clap(long, value_parser = parse_labels, help = "Agent labels, e.g. role=db,dc=east (empty string = none)")]
+    labels: Option<Labels>,
+
+    #[clap(long, help = "Display name other agents and agt-control may address this agent by (empty string = none)")]
+    name: Option<String>,
+
     #[clap(long, help = "Set client control URL to local host (http://127.0.0.1:<port>/control)")]
     client_local: bool,
 
@@ -128,6 +171,80 @@ fn load_from_environment(store: &KVStore) -> Result<(), Box<dyn std::error::Erro
             println!("✓ Loaded AGT_MESSAGE_TIMEOUT_SECS: {v}");
         }
     }
+    if let Ok(v) = std::env::var("AGT_MAX_PROCESSES") {
+        if let Ok(n) = v.parse::<u32>() {
+            store.commit("max_processes", n)?;
+            println!("✓ Loaded AGT_MAX_PROCESSES: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_RLIMIT_CPU_SECS") {
+        if let Ok(n) = v.parse::<u64>() {
+            store.commit("rlimit_cpu_secs", n)?;
+            println!("✓ Loaded AGT_RLIMIT_CPU_SECS: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_RLIMIT_AS_BYTES") {
+        if let Ok(n) = v.parse::<u64>() {
+            store.commit("rlimit_as_bytes", n)?;
+            println!("✓ Loaded AGT_RLIMIT_AS_BYTES: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_RLIMIT_NOFILE") {
+        if let Ok(n) = v.parse::<u64>() {
+            store.commit("rlimit_nofile", n)?;
+            println!("✓ Loaded AGT_RLIMIT_NOFILE: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_MAX_OUTPUT_BYTES") {
+        if let Ok(n) = v.parse::<u64>() {
+            store.commit("max_output_bytes", n)?;
+            println!("✓ Loaded AGT_MAX_OUTPUT_BYTES: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_BODY_ENCODING") {
+        if v.parse::<Encoding>().is_ok() {
+            store.commit("body_encoding", v.as_str())?;
+            println!("✓ Loaded AGT_BODY_ENCODING: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_LOG_FILE") {
+        store.commit("log_file", v.as_str())?;
+        println!("✓ Loaded AGT_LOG_FILE: {v}");
+    }
+    if let Ok(v) = std::env::var("AGT_LOG_FILE_MAX_BYTES") {
+        if let Ok(n) = v.parse::<u64>() {
+            store.commit("log_file_max_bytes", n)?;
+            println!("✓ Loaded AGT_LOG_FILE_MAX_BYTES: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_LOG_FILE_COUNT") {
+        if let Ok(n) = v.parse::<u32>() {
+            store.commit("log_file_count", n)?;
+            println!("✓ Loaded AGT_LOG_FILE_COUNT: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_LOG_FORMAT") {
+        if let Ok(f) = v.parse::<LogFormat>() {
+            store.commit("log_format", f.as_str())?;
+            println!("✓ Loaded AGT_LOG_FORMAT: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_METRICS_ADDRESS") {
+        store.commit("metrics_address", v.as_str())?;
+        println!("✓ Loaded AGT_METRICS_ADDRESS: {v}");
+    }
+    if let Ok(v) = std::env::var("AGT_LABELS") {
+        if let Ok(labels) = parse_labels(&v) {
+            store.commit("labels", format_labels(&labels).as_str())?;
+            println!("✓ Loaded AGT_LABELS: {v}");
+        }
+    }
+    if let Ok(v) = std::env::var("AGT_NAME") {
+        if v.trim().is_empty() || validate_name(&v).is_ok() {
+            store.commit("name", v.trim())?;
+            println!("✓ Loaded AGT_NAME: {v}");
+        }
+    }
     Ok(())
 }
 
@@ -154,6 +271,19 @@ fn display_config(store: &KVStore) {
         ("Max Weight",           v("max_weight")),
         ("Ticket Timeout Secs",  v("ticket_timeout_secs")),
         ("Message Timeout Secs", v("message_timeout_secs")),
+        ("Max Processes",        v("max_processes")),
+        ("RLimit CPU Secs",      v("rlimit_cpu_secs")),
+        ("RLimit AS Bytes",      v("rlimit_as_bytes")),
+        ("RLimit NOFILE",        v("rlimit_nofile")),
+        ("Max Output Bytes",     v("max_output_bytes")),
+        ("Body Encoding",        v("body_encoding")),
+        ("Log File",             v("log_file")),
+        ("Log File Max Bytes",   v("log_file_max_bytes")),
+        ("Log File Count",       v("log_file_count")),
+        ("Log Format",           v("log_format")),
+        ("Metrics Address",      v("metrics_address")),
+        ("Labels",               v("labels")),
+        ("Name",                 v("name")),
         ("Secret Digest",        v("secret_digest")),
     ];
     for (label, value) in &items {
@@ -258,6 +388,78 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("✓ Set Message Timeout Secs: {v}");
         modified = true;
     }
+    if let Some(v) = args.max_processes {
+        store.commit("max_processes", v)?;
+        println!("✓ Set Max Processes: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.rlimit_cpu_secs {
+        store.commit("rlimit_cpu_secs", v)?;
+        println!("✓ Set RLimit CPU Secs: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.rlimit_as_bytes {
+        store.commit("rlimit_as_bytes", v)?;
+        println!("✓ Set RLimit AS Bytes: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.rlimit_nofile {
+        store.commit("rlimit_nofile", v)?;
+        println!("✓ Set RLimit NOFILE: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.max_output_bytes {
+        store.commit("max_output_bytes", v)?;
+        println!("✓ Set Max Output Bytes: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.body_encoding {
+        store.commit("body_encoding", v.as_str())?;
+        println!("✓ Set Body Encoding: {}", v.as_str());
+        modified = true;
+    }
+    if let Some(v) = args.log_file {
+        store.commit("log_file", v.as_str())?;
+        println!("✓ Set Log File: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.log_file_max_bytes {
+        store.commit("log_file_max_bytes", v)?;
+        println!("✓ Set Log File Max Bytes: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.log_file_count {
+        store.commit("log_file_count", v)?;
+        println!("✓ Set Log File Count: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.log_format {
+        store.commit("log_format", v.as_str())?;
+        println!("✓ Set Log Format: {}", v.as_str());
+        modified = true;
+    }
+    if let Some(v) = args.metrics_address {
+        store.commit("metrics_address", v.as_str())?;
+        println!("✓ Set Metrics Address: {v}");
+        modified = true;
+    }
+    if let Some(labels) = args.labels {
+        let v = format_labels(&labels);
+        store.commit("labels", v.as_str())?;
+        println!("✓ Set Labels: {v}");
+        modified = true;
+    }
+    if let Some(v) = args.name {
+        let v = v.trim();
+        match validate_name(v) {
+            Err(e) if !v.is_empty() => eprintln!("Error: {e}"),
+            _ => {
+                store.commit("name", v)?;
+                println!("✓ Set Name: {v}");
+                modified = true;
+            }
+        }
+    }
     if args.client_local {
         let port = store.get("socket_port", None)
             .ok()
@@ -10,7 +10,7 @@ use clap::{Parser, Subcommand};
 use stembot_rust::{
     cli,
     executor::agent::AgentClient,
-    models::config::Config,
+    models::{config::Config, control::{Encoding, HashAlgorithm}},
 };
 
 // ── CLI definition ────────────────────────────────────────────────────────────
@@ -55,6 +55,57 @@ enum Commands {
         #[clap(short = 't', long, default_value = "15")]
         timeout: u64,
     },
+    /// Show an agent's configuration, or change keys with KEY=VALUE
+    Config {
+        /// UUID of the agent to configure
+        agtuuid: String,
+        /// Config keys to set (KEY=VALUE, e.g. peer_timeout_secs=120)
+        #[clap(value_parser = cli::config::parse_config_pair)]
+        assignments: Vec<(String, serde_json::Value)>,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Print the most recent log entries of an agent
+    Logs {
+        /// UUID of the agent to read logs from
+        agtuuid: String,
+        /// Number of entries to print (default: 100)
+        #[clap(short = 'n', long, default_value = "100")]
+        lines: u64,
+        /// Only entries at this level or more severe (DEBUG/INFO/WARNING/ERROR)
+        #[clap(short = 'l', long)]
+        level: Option<String>,
+        /// Only entries from this module and its submodules
+        #[clap(short = 'm', long)]
+        module: Option<String>,
+        /// Only entries after this age (e.g. 15m) or UTC timestamp
+        #[clap(long, value_parser = cli::logs::parse_log_time)]
+        since: Option<f64>,
+        /// Only entries before this age (e.g. 5m) or UTC timestamp
+        #[clap(long, value_parser = cli::logs::parse_log_time)]
+        until: Option<f64>,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Show or change an agent's log levels until its next restart
+    LogLevel {
+        /// UUID of the agent to act on
+        agtuuid: String,
+        /// Application log level
+        #[clap(long)]
+        app: Option<String>,
+        /// API/framework log level
+        #[clap(long)]
+        api: Option<String>,
+        /// Per-module levels (MODULE=LEVEL; LEVEL "default" drops the override)
+        #[clap(value_parser = cli::logs::parse_module_level)]
+        modules: Vec<(String, Option<String>)>,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
     /// Benchmark agent file I/O performance across multiple file sizes
     Bench {
         /// UUID of the agent to benchmark
@@ -63,7 +114,7 @@ enum Commands {
         #[clap(short = 't', long, default_value = "15")]
         timeout: u64,
     },
-    /// Transfer a file from source to destination
+    /// Transfer a file (or with -r a directory) from source to destination
     Put {
         /// Source file path
         src_path: String,
@@ -78,17 +129,250 @@ enum Commands {
         /// Destination agent UUID (writes to local filesystem if omitted)
         #[clap(short = 'd', long)]
         dst_agtuuid: Option<String>,
+        /// Copy a directory tree, keeping permissions and mtimes
+        #[clap(short = 'r', long)]
+        recursive: bool,
+        /// Compression for the file data: none, zlib, gzip or zstd
+        #[clap(short = 'z', long, default_value = "zlib", conflicts_with = "recursive")]
+        compression: Encoding,
+        /// Give the copy the source's mode, owner, group and mtime
+        #[clap(short = 'p', long, conflicts_with = "recursive")]
+        preserve: bool,
+        /// Digest the copy is checked with: md5, sha256 or blake3
+        #[clap(long, default_value = "md5")]
+        hash: HashAlgorithm,
     },
-    /// Execute a command on a remote agent
+    /// Execute a command on one or more remote agents
+    #[clap(allow_missing_positional = true)]
     Run {
-        /// UUID of the agent to run the command on
-        agtuuid: String,
+        /// UUIDs of the agents to run the command on, or label selectors (role=db,dc=east or '*')
+        #[clap(required_unless_present_any = ["all", "matching"])]
+        agtuuids: Vec<String>,
         /// Command to execute
         command: String,
+        /// Also run on every known agent whose UUID matches this regex
+        #[clap(short = 'm', long = "match", value_parser = cli::run::parse_regex)]
+        matching: Option<regex::Regex>,
+        /// Run on every known agent
+        #[clap(short = 'a', long)]
+        all: bool,
+        /// Most agents to have a run in flight at once (0 = all of them)
+        #[clap(short = 'p', long, default_value = "0")]
+        parallel: usize,
+        /// After the summary, print each distinct output with the agents that produced it
+        #[clap(short = 'o', long)]
+        output: bool,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+        /// Start the command in the background and print its process handle
+        #[clap(long, conflicts_with_all = ["follow", "all", "matching"])]
+        detach: bool,
+        /// Print output as the command produces it instead of when it exits
+        #[clap(short = 'f', long, conflicts_with_all = ["all", "matching"])]
+        follow: bool,
+        /// Working directory for the command on the remote agent
+        #[clap(long)]
+        cwd: Option<String>,
+        /// Set an environment variable for the command (KEY=VALUE, repeatable)
+        #[clap(short = 'e', long = "env", value_parser = cli::run::parse_env_pair)]
+        env: Vec<(String, String)>,
+        /// Start the command with an empty environment
+        #[clap(long)]
+        clear_env: bool,
+        /// Send the contents of a local file to the command's stdin ("-" for this terminal's stdin)
+        #[clap(long)]
+        stdin: Option<String>,
+        /// Run the command as this user on the remote agent
+        #[clap(short = 'u', long)]
+        user: Option<String>,
+    },
+    /// List a directory or show file metadata on a remote agent
+    Ls {
+        /// UUID of the agent to query
+        agtuuid: String,
+        /// Directory or file path to list
+        path: String,
+        /// Levels of the directory tree to list (1 = direct children)
+        #[clap(short = 'd', long, default_value = "1")]
+        depth: u32,
+        /// Only show entries whose name matches this glob (e.g. '*.conf')
+        #[clap(short = 'p', long)]
+        pattern: Option<String>,
+        /// Show the metadata of the path itself instead of listing it
+        #[clap(long, conflicts_with_all = ["depth", "pattern"])]
+        stat: bool,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Compare a file's digest across agents
+    Hash {
+        /// File path to hash on every agent
+        path: String,
+        /// UUIDs of the agents to compare, or label selectors
+        #[clap(required = true)]
+        agtuuids: Vec<String>,
+        /// Digest algorithm: md5, sha256 or blake3
+        #[clap(short = 'a', long, default_value = "sha256")]
+        algorithm: HashAlgorithm,
+        /// Compare against this digest instead of the majority
+        #[clap(short = 'e', long)]
+        expected: Option<String>,
+        /// Timeout in seconds (default: 60)
+        #[clap(short = 't', long, default_value = "60")]
+        timeout: u64,
+    },
+    /// Print the end of a file on a remote agent
+    Tail {
+        /// UUID of the agent to read from
+        agtuuid: String,
+        /// File path to read
+        path: String,
+        /// Number of lines to print (default: 10)
+        #[clap(short = 'n', long)]
+        lines: Option<u64>,
+        /// Number of bytes to print instead of lines
+        #[clap(short = 'c', long, conflicts_with = "lines")]
+        bytes: Option<u64>,
+        /// Keep printing text appended to the file until interrupted
+        #[clap(short = 'f', long)]
+        follow: bool,
         /// Timeout in seconds (default: 15)
         #[clap(short = 't', long, default_value = "15")]
         timeout: u64,
     },
+    /// Delete a file or directory on a remote agent
+    Rm {
+        /// UUID of the agent to act on
+        agtuuid: String,
+        /// Path to delete
+        path: String,
+        /// Delete a non-empty directory and everything in it
+        #[clap(short = 'r', long)]
+        recursive: bool,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Move or rename a path on a remote agent
+    Mv {
+        /// UUID of the agent to act on
+        agtuuid: String,
+        /// Path to move
+        src: String,
+        /// New path
+        dst: String,
+        /// Replace the destination if it already exists
+        #[clap(short = 'f', long)]
+        force: bool,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Create a directory on a remote agent
+    Mkdir {
+        /// UUID of the agent to act on
+        agtuuid: String,
+        /// Directory to create
+        path: String,
+        /// Create missing parents; an existing directory is not an error
+        #[clap(short = 'p', long)]
+        parents: bool,
+        /// Octal mode for the new directory (e.g. 750)
+        #[clap(short = 'm', long, value_parser = cli::path::parse_mode)]
+        mode: Option<u32>,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Change the mode of a path on a remote agent
+    Chmod {
+        /// UUID of the agent to act on
+        agtuuid: String,
+        /// Octal mode (e.g. 644)
+        #[clap(value_parser = cli::path::parse_mode)]
+        mode: u32,
+        /// Path to change
+        path: String,
+        /// Change a directory's contents too
+        #[clap(short = 'R', long)]
+        recursive: bool,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Change the owner and/or group of a path on a remote agent
+    Chown {
+        /// UUID of the agent to act on
+        agtuuid: String,
+        /// OWNER, OWNER:GROUP or :GROUP (names or numeric ids)
+        #[clap(value_parser = cli::path::parse_owner_spec)]
+        owner: (Option<String>, Option<String>),
+        /// Path to change
+        path: String,
+        /// Change a directory's contents too
+        #[clap(short = 'R', long)]
+        recursive: bool,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// List, inspect, or kill background processes on a remote agent
+    Ps {
+        /// UUID of the agent to query
+        agtuuid: String,
+        /// Show the status and output of a process handle
+        #[clap(long)]
+        status: Option<String>,
+        /// Terminate a process handle
+        #[clap(long)]
+        kill: Option<String>,
+        /// Timeout in seconds (default: 15)
+        #[clap(short = 't', long, default_value = "15")]
+        timeout: u64,
+    },
+    /// Walk the whole mesh and print its graph as JSON or Graphviz DOT
+    Topology {
+        /// UUID of the agent to walk from (default: this agent)
+        agtuuid: Option<String>,
+        /// Output format: json or dot
+        #[clap(short = 'f', long, default_value = "json")]
+        format: cli::topology::TopologyFormat,
+        /// Seconds the agent spends walking the mesh (default: 10)
+        #[clap(short = 'w', long, default_value = "10")]
+        walk: f64,
+        /// Timeout in seconds (default: 30)
+        #[clap(short = 't', long, default_value = "30")]
+        timeout: u64,
+    },
+}
+
+impl Commands {
+    /// Arguments naming the single agent a command acts on; each may also be
+    /// a display name or a label selector that picks exactly one known agent.
+    fn destinations_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Commands::Stat     { agtuuid, .. }
+            | Commands::Config   { agtuuid, .. }
+            | Commands::Logs     { agtuuid, .. }
+            | Commands::LogLevel { agtuuid, .. }
+            | Commands::Bench    { agtuuid, .. }
+            | Commands::Ls       { agtuuid, .. }
+            | Commands::Tail     { agtuuid, .. }
+            | Commands::Rm       { agtuuid, .. }
+            | Commands::Mv       { agtuuid, .. }
+            | Commands::Mkdir    { agtuuid, .. }
+            | Commands::Chmod    { agtuuid, .. }
+            | Commands::Chown    { agtuuid, .. }
+            | Commands::Ps       { agtuuid, .. } => vec![agtuuid],
+            Commands::Put { src_agtuuid, dst_agtuuid, .. } =>
+                src_agtuuid.iter_mut().chain(dst_agtuuid.iter_mut()).collect(),
+            Commands::Topology { agtuuid, .. } => agtuuid.iter_mut().collect(),
+            Commands::Discover { .. } | Commands::Delete { .. } | Commands::Run { .. } | Commands::Hash { .. } =>
+                vec![],
+        }
+    }
 }
 
 // ── Entry point ───────────────────────────────────────────────────────────────
@@ -102,7 +386,33 @@ async fn main() -> Result<()> {
     let config = Config::load();
     let client = Arc::new(AgentClient::new(config.client_control_url.clone()));
 
-    match cli.command {
+    let mut command = cli.command;
+    for dst in command.destinations_mut() {
+        match cli::resolve_agent(&client, dst).await {
+            Ok(agtuuid) => *dst = agtuuid,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Commands::Hash { ref mut agtuuids, .. } = command {
+        let mut resolved = Vec::new();
+        for dst in agtuuids.iter() {
+            for agtuuid in cli::resolve_agents(&client, dst).await? {
+                if !resolved.contains(&agtuuid) {
+                    resolved.push(agtuuid);
+                }
+            }
+        }
+        if resolved.is_empty() {
+            eprintln!("no known agents match");
+            std::process::exit(1);
+        }
+        *agtuuids = resolved;
+    }
+
+    match command {
         Commands::Discover { peer_url, polling, delay, ttl } =>
             cli::discover::cmd_discover(client, peer_url, polling, delay, ttl).await?,
 
@@ -112,14 +422,69 @@ async fn main() -> Result<()> {
         Commands::Stat { agtuuid, timeout } =>
             cli::stat::cmd_stat(client, agtuuid, timeout).await?,
 
+        Commands::Config { agtuuid, assignments, timeout } =>
+            cli::config::cmd_config(client, agtuuid, assignments, timeout).await?,
+
+        Commands::Logs { agtuuid, lines, level, module, since, until, timeout } =>
+            cli::logs::cmd_logs(client, agtuuid, lines, level, module, since, until, timeout).await?,
+
+        Commands::LogLevel { agtuuid, app, api, modules, timeout } =>
+            cli::logs::cmd_log_level(client, agtuuid, app, api, modules, timeout).await?,
+
         Commands::Bench { agtuuid, timeout } =>
             cli::bench::cmd_bench(client, agtuuid, timeout).await?,
 
-        Commands::Put { src_path, dst_path, timeout, src_agtuuid, dst_agtuuid } =>
-            cli::put::cmd_put(client, src_path, dst_path, timeout, src_agtuuid, dst_agtuuid).await?,
+        Commands::Put {
+            src_path, dst_path, timeout, src_agtuuid, dst_agtuuid, recursive, compression, preserve, hash,
+        } => {
+            if recursive {
+                cli::put::cmd_put_dir(client, src_path, dst_path, timeout, src_agtuuid, dst_agtuuid, hash).await?
+            } else {
+                cli::put::cmd_put(
+                    client, src_path, dst_path, timeout, src_agtuuid, dst_agtuuid, compression, hash, preserve,
+                )
+                .await?
+            }
+        }
+
+        Commands::Run {
+            agtuuids, command, matching, all, parallel, output, timeout, detach, follow,
+            cwd, env, clear_env, stdin, user,
+        } => {
+            let options = cli::run::RunOptions { cwd, env, clear_env, stdin, user };
+            let targets = cli::run::RunTargets { agtuuids, matching, all, parallel, output };
+            cli::run::cmd_run(client, targets, command, timeout, options, detach, follow).await?
+        }
+
+        Commands::Ls { agtuuid, path, depth, pattern, stat, timeout } =>
+            cli::ls::cmd_ls(client, agtuuid, path, depth, pattern, stat, timeout).await?,
+
+        Commands::Hash { path, agtuuids, algorithm, expected, timeout } =>
+            cli::hash::cmd_hash(client, path, agtuuids, algorithm, expected, timeout).await?,
+
+        Commands::Tail { agtuuid, path, lines, bytes, follow, timeout } =>
+            cli::tail::cmd_tail(client, agtuuid, path, lines, bytes, follow, timeout).await?,
+
+        Commands::Rm { agtuuid, path, recursive, timeout } =>
+            cli::path::cmd_rm(client, agtuuid, path, recursive, timeout).await?,
+
+        Commands::Mv { agtuuid, src, dst, force, timeout } =>
+            cli::path::cmd_mv(client, agtuuid, src, dst, force, timeout).await?,
+
+        Commands::Mkdir { agtuuid, path, parents, mode, timeout } =>
+            cli::path::cmd_mkdir(client, agtuuid, path, parents, mode, timeout).await?,
+
+        Commands::Chmod { agtuuid, mode, path, recursive, timeout } =>
+            cli::path::cmd_chmod(client, agtuuid, path, mode, recursive, timeout).await?,
+
+        Commands::Chown { agtuuid, owner: (owner, group), path, recursive, timeout } =>
+            cli::path::cmd_chown(client, agtuuid, path, owner, group, recursive, timeout).await?,
+
+        Commands::Ps { agtuuid, status, kill, timeout } =>
+            cli::ps::cmd_ps(client, agtuuid, status, kill, timeout).await?,
 
-        Commands::Run { agtuuid, command, timeout } =>
-            cli::run::cmd_run(client, agtuuid, command, timeout).await?,
+        Commands::Topology { agtuuid, format, walk, timeout } =>
+            cli::topology::cmd_topology(client, agtuuid, format, walk, timeout).await?,
     }
 
     Ok(())
@@ -2,48 +2,96 @@ use actix_web::{rt::spawn, web, App, HttpServer, Result};
 use clokwerk::{AsyncScheduler, Interval::Seconds};
 use tracing_actix_web::TracingLogger;
 
-use std::time::Duration;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 use stembot_rust::{
-    collections::vacuum_collections, config::config, logger::init_logger, messaging::expire_network_messages, processor::{advertizing, control_handler, mpi_handler, polling, replay}, ticketing::expire_tickets
+    collections::vacuum_collections, config::config, models::config::Config, executor::process::expire_processes, executor::transfer::expire_transfers, health::{health_handler, ready_handler, scheduler_ticked}, logger::init_logger, messaging::expire_network_messages, metrics::{mark_started, metrics_handler}, processor::{advertizing, control_handler, mpi_handler, polling, replay}, ticketing::expire_tickets
 };
 
+/// Spawn `job` unless the previous job flagged by `running` is still going.
+fn spawn_exclusive(running: &'static AtomicBool, job: impl Future<Output = ()> + 'static) {
+    if running.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    struct Finished(&'static AtomicBool);
+    impl Drop for Finished {
+        fn drop(&mut self) {
+            self.0.store(false, Ordering::Release);
+        }
+    }
+    spawn(async move {
+        let _finished = Finished(running);
+        job.await;
+    });
+}
+
 #[actix_web::main]
 async fn main() -> Result<(), std::io::Error> {
     if std::env::var("RUST_BACKTRACE").is_err() {
         std::env::set_var("RUST_BACKTRACE", "1");
     }
 
+    mark_started();
+
     let config = config();
 
 
-    init_logger(config.log_level_app.to_string(), config.log_level_api.to_string());
+    init_logger(&config);
 
     config.log();
 
 
     let mut scheduler = AsyncScheduler::new();
 
-    scheduler.every(Seconds(config.message_timeout_secs)).run({
-        move || async move {
-            expire_tickets().unwrap_or_else(|e| log::error!("Error expiring tickets: {e}"));
+    // Expiry runs once per timeout period, re-read each tick so a SetConfig
+    // change applies without a restart.
+    scheduler.every(Seconds(1)).run({
+        let mut last = Instant::now();
+        move || {
+            let due = last.elapsed().as_secs() >= stembot_rust::config::config().ticket_timeout_secs as u64;
+            if due { last = Instant::now(); }
+            async move {
+                if due {
+                    expire_tickets().unwrap_or_else(|e| log::error!("Error expiring tickets: {e}"));
+                }
+            }
         }
     });
 
-    scheduler.every(Seconds(config.ticket_timeout_secs)).run({
-        move || async move {
-            expire_network_messages().unwrap_or_else(|e| log::error!("Error expiring network messages: {e}"));
+    scheduler.every(Seconds(1)).run({
+        let mut last = Instant::now();
+        move || {
+            let due = last.elapsed().as_secs() >= stembot_rust::config::config().message_timeout_secs as u64;
+            if due { last = Instant::now(); }
+            async move {
+                if due {
+                    expire_network_messages().unwrap_or_else(|e| log::error!("Error expiring network messages: {e}"));
+                }
+            }
         }
     });
 
+    scheduler.every(Seconds(60)).run(|| async { expire_processes() });
+
+    scheduler.every(Seconds(60)).run(|| async {
+        expire_transfers().unwrap_or_else(|e| log::error!("Error expiring transfers: {e}"));
+    });
+
     scheduler.every(Seconds(60)).run(|| async {
         vacuum_collections().unwrap_or_else(|e| log::error!("Error vacuuming collections: {e}"));
     });
 
-    scheduler.every(Seconds(1)).run(|| async { replay().await });
-    scheduler.every(Seconds(1)).run(|| async { polling().await });
-    scheduler.every(Seconds(10)).run(|| async { advertizing().await });
+    // The network jobs run outside the scheduler loop so a slow peer cannot
+    // hold up its tick; each is skipped while its previous run is unfinished.
+    static REPLAYING:   AtomicBool = AtomicBool::new(false);
+    static POLLING:     AtomicBool = AtomicBool::new(false);
+    static ADVERTIZING: AtomicBool = AtomicBool::new(false);
+    scheduler.every(Seconds(1)).run(|| async { spawn_exclusive(&REPLAYING, replay()) });
+    scheduler.every(Seconds(1)).run(|| async { spawn_exclusive(&POLLING, polling()) });
+    scheduler.every(Seconds(10)).run(|| async { spawn_exclusive(&ADVERTIZING, advertizing()) });
 
 
     log::info!("Starting scheduler");
@@ -52,29 +100,48 @@ async fn main() -> Result<(), std::io::Error> {
         async move {
             loop {
                 scheduler.run_pending().await;
+                scheduler_ticked();
                 sleep(Duration::from_secs(1)).await;
             }
         }
     });
 
+    let server_config = Config::clone(&config);
     let server = HttpServer::new(
         move || {
             App::new()
                 .wrap(TracingLogger::default())
                 .app_data(web::PayloadConfig::new(usize::MAX))
-                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(server_config.clone()))
                 .route("/control", web::post().to(control_handler))
                 .route("/mpi",     web::post().to(mpi_handler))
+                .route("/health",  web::get().to(health_handler))
+                .route("/ready",   web::get().to(ready_handler))
         }
     )
     .workers(config.workers as usize);
 
     log::info!("Starting server");
 
-    server.bind(
+    let server = server.bind(
         (
             config.socket_host.clone(),
             config.socket_port,
         )
-    )?.run().await
+    )?.run();
+
+    if config.metrics_address.is_empty() {
+        return server.await;
+    }
+
+    let metrics = HttpServer::new(
+        || App::new().route("/metrics", web::get().to(metrics_handler))
+    )
+    .workers(1)
+    .bind(config.metrics_address.as_str())?
+    .run();
+
+    log::info!("Serving metrics on {}", config.metrics_address);
+
+    tokio::try_join!(server, metrics).map(|_| ())
 }
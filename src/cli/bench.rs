@@ -1,46 +1,13 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use tokio::time::{sleep, Duration};
 
 use crate::{
     executor::agent::AgentClient,
     models::control::{Benchmark, CheckTicket, CloseTicket, ControlForm, ControlFormTicket},
 };
 
-use super::{format_bandwidth, format_bytes, KB, MB};
-
-/// Poll a `Benchmark` ticket for timing data only (no content read).
-async fn bench_poll_timing(
-    client: Arc<AgentClient>,
-    ticket: ControlFormTicket,
-    timeout_secs: u64,
-) -> CheckTicket {
-    let start = std::time::Instant::now();
-    let mut check = CheckTicket {
-        tckuuid:     ticket.tckuuid.clone(),
-        create_time: Some(ticket.create_time),
-        ..Default::default()
-    };
-    while start.elapsed().as_secs() < timeout_secs && check.service_time.is_none() {
-        match client.send_control_form(ControlForm::CheckTicket(check.clone())).await {
-            Ok(ControlForm::CheckTicket(c)) => check = c,
-            Ok(_) => break,
-            Err(e) => { eprintln!("poll error: {e}"); break; }
-        }
-        if check.service_time.is_none() {
-            sleep(Duration::from_secs(1)).await;
-        }
-    }
-    let close = ControlForm::CloseTicket(CloseTicket {
-        tckuuid: ticket.tckuuid.clone(),
-        ..Default::default()
-    });
-    if let Err(e) = client.send_control_form(close).await {
-        eprintln!("close ticket error: {e}");
-    }
-    check
-}
+use super::{format_bandwidth, format_bytes, ticket_status, wait_for_tickets, KB, MB};
 
 /// Send and poll a batch of Benchmark tickets for one direction.
 ///
@@ -87,16 +54,16 @@ async fn bench_run_direction(
         }
     }
 
-    let mut join_set = tokio::task::JoinSet::new();
+    // Timing data only; the content is never read.
+    let seen = sent.iter().map(ticket_status).collect();
+    let checks = wait_for_tickets(&client, seen, timeout_secs, |_| true).await;
     for ticket in sent {
-        let c = Arc::clone(&client);
-        join_set.spawn(async move { bench_poll_timing(c, ticket, timeout_secs).await });
-    }
-    let mut checks: Vec<CheckTicket> = Vec::new();
-    while let Some(res) = join_set.join_next().await {
-        match res {
-            Ok(c)  => checks.push(c),
-            Err(e) => eprintln!("join error: {e}"),
+        let close = ControlForm::CloseTicket(CloseTicket {
+            tckuuid: ticket.tckuuid,
+            ..Default::default()
+        });
+        if let Err(e) = client.send_control_form(close).await {
+            eprintln!("close ticket error: {e}");
         }
     }
     checks
@@ -132,6 +99,7 @@ async fn bench_run(
     print_row("IN",  &in_checks);
 }
 
+#[allow(clippy::identity_op)]
 pub async fn cmd_bench(
     client: Arc<AgentClient>,
     agtuuid: String,
@@ -0,0 +1,100 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::{
+    executor::agent::AgentClient,
+    models::control::{ControlForm, ControlFormTicket, GetConfig, SetConfig},
+};
+
+use super::poll_ticket;
+
+/// Parse a `KEY=VALUE` config assignment.  Values that parse as JSON are
+/// sent as such (`workers=4`); anything else is sent as a string.
+pub fn parse_config_pair(s: &str) -> Result<(String, Value)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => {
+            let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+            Ok((key.to_string(), value))
+        }
+        _ => Err(anyhow!("expected KEY=VALUE, got '{s}'")),
+    }
+}
+
+/// Show an agent's configuration, or change the given keys.
+pub async fn cmd_config(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    assignments: Vec<(String, Value)>,
+    timeout: u64,
+) -> Result<()> {
+    let form = if assignments.is_empty() {
+        ControlForm::GetConfig(GetConfig::default())
+    } else {
+        ControlForm::SetConfig(SetConfig {
+            values: assignments.into_iter().collect(),
+            ..Default::default()
+        })
+    };
+    let name = form.form_type();
+
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid.clone(), form, ..ControlFormTicket::default() })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("{name} ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    match ticket.form {
+        ControlForm::GetConfig(f) => {
+            if let Some(e) = f.error {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+            let config = f.config.unwrap_or_default();
+            let mut entries: Vec<_> = config.as_object().into_iter().flatten().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in entries {
+                println!("{key:.<25} {}", display_value(value));
+            }
+        }
+        ControlForm::SetConfig(f) => {
+            if let Some(e) = f.error {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+            for (key, value) in &f.values {
+                println!("{key:.<25} {}", display_value(value));
+            }
+            if !f.restart_required.is_empty() {
+                println!();
+                println!(
+                    "Restart {agtuuid} for these to take effect: {}",
+                    f.restart_required.join(", ")
+                );
+            }
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
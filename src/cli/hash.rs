@@ -0,0 +1,123 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::{
+    executor::agent::AgentClient,
+    models::control::{ControlForm, ControlFormTicket, HashAlgorithm, HashFile},
+};
+
+use super::{format_bytes, poll_ticket};
+
+/// An agent's digest and size of the file, or why it could not be hashed.
+type AgentDigest = Result<(String, u64), String>;
+
+/// Digest one file on one agent.
+async fn hash_on_agent(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    hash_algorithm: HashAlgorithm,
+    timeout: u64,
+) -> AgentDigest {
+    let form = ControlForm::HashFile(HashFile { path, hash_algorithm, ..Default::default() });
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid, form, ..ControlFormTicket::default() })
+        .await
+        .map_err(|e| e.to_string())?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        return Err("hash ticket never serviced".to_string());
+    }
+    if let Some(e) = ticket.error {
+        return Err(e);
+    }
+    match ticket.form {
+        ControlForm::HashFile(HashFile { error: Some(e), .. }) => Err(e),
+        ControlForm::HashFile(HashFile { hash: Some(hash), size, .. }) => Ok((hash, size.unwrap_or(0))),
+        _ => Err("unexpected response form".to_string()),
+    }
+}
+
+/// Compare one file across agents.
+///
+/// Every agent is queried at once. Digests are compared against `expected`
+/// when given, otherwise against the digest most agents agree on; the exit
+/// status is non-zero if any agent differs or fails.
+pub async fn cmd_hash(
+    client: Arc<AgentClient>,
+    path: String,
+    agtuuids: Vec<String>,
+    hash_algorithm: HashAlgorithm,
+    expected: Option<String>,
+    timeout: u64,
+) -> Result<()> {
+    let tasks: Vec<_> = agtuuids
+        .iter()
+        .map(|agtuuid| {
+            tokio::spawn(hash_on_agent(
+                Arc::clone(&client),
+                agtuuid.clone(),
+                path.clone(),
+                hash_algorithm,
+                timeout,
+            ))
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(tasks.len());
+    for (agtuuid, task) in agtuuids.iter().zip(tasks) {
+        let result = task.await.unwrap_or_else(|e| Err(e.to_string()));
+        results.push((agtuuid, result));
+    }
+
+    let reference = expected.map(|e| e.to_lowercase()).or_else(|| majority(&results));
+
+    let width = agtuuids.iter().map(String::len).max().unwrap_or(0);
+    let (mut matching, mut differing, mut failed) = (0, 0, 0);
+    println!("{} {path}", hash_algorithm.as_str());
+    for (agtuuid, result) in &results {
+        match result {
+            Ok((hash, size)) if Some(hash) == reference.as_ref() => {
+                matching += 1;
+                println!("   ok      {agtuuid:<width$}  {hash}  {}", format_bytes(*size as f64));
+            }
+            Ok((hash, size)) => {
+                differing += 1;
+                println!("   DIFFERS {agtuuid:<width$}  {hash}  {}", format_bytes(*size as f64));
+            }
+            Err(e) => {
+                failed += 1;
+                println!("   ERROR   {agtuuid:<width$}  {e}");
+            }
+        }
+    }
+    println!("{matching} matching, {differing} differing, {failed} failed");
+
+    if differing > 0 || failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// The digest most agents report; ties go to the one reported first.
+fn majority(results: &[(&String, AgentDigest)]) -> Option<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for (_, result) in results {
+        if let Ok((hash, _)) = result {
+            match counts.iter_mut().find(|(h, _)| h == hash) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((hash, 1)),
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .fold(None, |best, (hash, count)| match best {
+            Some((_, best_count)) if best_count >= count => best,
+            _ => Some((hash, count)),
+        })
+        .map(|(hash, _)| hash.to_string())
+}
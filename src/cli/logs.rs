@@ -0,0 +1,147 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::{
+    executor::agent::AgentClient,
+    logger::format_entry,
+    models::control::{ControlForm, ControlFormTicket, GetLogs, SetLogLevel},
+};
+
+use super::poll_ticket;
+
+/// Parse a log time bound: either an age such as `90s`, `15m`, `2h` or `1d`
+/// (a bare number is seconds), or a UTC timestamp (`2024-05-01T12:00:00Z` or
+/// `2024-05-01 12:00:00`).  Returns Unix seconds.
+pub fn parse_log_time(s: &str) -> Result<f64> {
+    let (digits, unit) = s.split_at(s.trim_end_matches(char::is_alphabetic).len());
+    if let Ok(n) = digits.parse::<f64>() {
+        let scale = match unit {
+            "" | "s" => 1.0,
+            "m"      => 60.0,
+            "h"      => 3_600.0,
+            "d"      => 86_400.0,
+            _ => return Err(anyhow!("unknown time unit '{unit}' in '{s}'; expected s, m, h or d")),
+        };
+        return Ok(Utc::now().timestamp_millis() as f64 / 1000.0 - n * scale);
+    }
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.timestamp_millis() as f64 / 1000.0);
+    }
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .map(|t| t.and_utc().timestamp_millis() as f64 / 1000.0)
+        .map_err(|_| anyhow!("expected an age like 15m or a UTC timestamp, got '{s}'"))
+}
+
+/// Parse a `MODULE=LEVEL` override; `LEVEL` may be `default` to drop it.
+pub fn parse_module_level(s: &str) -> Result<(String, Option<String>)> {
+    match s.split_once('=') {
+        Some((module, level)) if !module.is_empty() && !level.is_empty() => {
+            let level = (!level.eq_ignore_ascii_case("default")).then(|| level.to_string());
+            Ok((module.to_string(), level))
+        }
+        _ => Err(anyhow!("expected MODULE=LEVEL, got '{s}'")),
+    }
+}
+
+async fn run_ticket(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    form: ControlForm,
+    timeout: u64,
+) -> Result<ControlForm> {
+    let name = form.form_type();
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid, form, ..ControlFormTicket::default() })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("{name} ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+    Ok(ticket.form)
+}
+
+/// Print the most recent log entries of an agent.
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_logs(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    lines: u64,
+    level: Option<String>,
+    module: Option<String>,
+    since: Option<f64>,
+    until: Option<f64>,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::GetLogs(GetLogs {
+        lines: Some(lines),
+        level,
+        module,
+        since,
+        until,
+        ..Default::default()
+    });
+
+    match run_ticket(client, agtuuid, form, timeout).await? {
+        ControlForm::GetLogs(f) => {
+            if let Some(e) = f.error {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+            for entry in &f.entries {
+                println!("{}", format_entry(entry));
+            }
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Show an agent's log levels, or change them.
+pub async fn cmd_log_level(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    app: Option<String>,
+    api: Option<String>,
+    modules: Vec<(String, Option<String>)>,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::SetLogLevel(SetLogLevel {
+        app,
+        api,
+        modules: modules.into_iter().collect(),
+        ..Default::default()
+    });
+
+    match run_ticket(client, agtuuid, form, timeout).await? {
+        ControlForm::SetLogLevel(f) => {
+            if let Some(e) = f.error {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+            println!("{:.<25} {}", "app", f.app.unwrap_or_default());
+            println!("{:.<25} {}", "api", f.api.unwrap_or_default());
+            for (module, level) in &f.modules {
+                println!("{module:.<25} {}", level.as_deref().unwrap_or_default());
+            }
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+
+use crate::{
+    executor::agent::AgentClient,
+    models::control::{ControlForm, ControlFormTicket, FileEntry, ListDirectory, StatFile},
+};
+
+use super::{format_bytes, poll_ticket};
+
+/// `ls -l` style mode string, e.g. `drwxr-xr-x`.
+fn mode_string(entry: &FileEntry) -> String {
+    let kind = match entry.kind.as_str() {
+        "directory" => 'd',
+        "symlink"   => 'l',
+        "file"      => '-',
+        _           => '?',
+    };
+    let Some(mode) = entry.mode else {
+        return format!("{kind}?????????");
+    };
+
+    let mut s = String::with_capacity(10);
+    s.push(kind);
+    for (shift, special, set, unset) in [(6, 0o4000, 's', 'S'), (3, 0o2000, 's', 'S'), (0, 0o1000, 't', 'T')] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true)   => set,
+            (false, true)  => unset,
+            (true, false)  => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+fn format_mtime(mtime: Option<f64>) -> String {
+    mtime
+        .and_then(|t| Utc.timestamp_opt(t as i64, 0).single())
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// List a directory (or show one path's metadata with `stat`) on a remote agent.
+pub async fn cmd_ls(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    depth: u32,
+    pattern: Option<String>,
+    stat: bool,
+    timeout: u64,
+) -> Result<()> {
+    let form = if stat {
+        ControlForm::StatFile(StatFile { path: path.clone(), ..Default::default() })
+    } else {
+        ControlForm::ListDirectory(ListDirectory { path: path.clone(), depth, pattern, ..Default::default() })
+    };
+
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid.clone(), form, ..ControlFormTicket::default() })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("Listing ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    match ticket.form {
+        ControlForm::ListDirectory(f) => {
+            if let Some(e) = f.error { eprintln!("{e}"); process::exit(1); }
+            let owner_width = f.entries.iter().map(|e| e.owner.as_deref().unwrap_or("-").len()).max().unwrap_or(0);
+            let group_width = f.entries.iter().map(|e| e.group.as_deref().unwrap_or("-").len()).max().unwrap_or(0);
+            let size_width  = f.entries.iter().map(|e| e.size.to_string().len()).max().unwrap_or(0);
+            for e in &f.entries {
+                let target = e.target.as_deref().map(|t| format!(" -> {t}")).unwrap_or_default();
+                println!(
+                    "{}  {:<ow$}  {:<gw$}  {:>sw$}  {}  {}{}",
+                    mode_string(e),
+                    e.owner.as_deref().unwrap_or("-"),
+                    e.group.as_deref().unwrap_or("-"),
+                    e.size,
+                    format_mtime(e.mtime),
+                    e.name,
+                    target,
+                    ow = owner_width,
+                    gw = group_width,
+                    sw = size_width,
+                );
+            }
+        }
+        ControlForm::StatFile(f) => {
+            if let Some(e) = f.error { eprintln!("{e}"); process::exit(1); }
+            let Some(e) = f.entry else {
+                eprintln!("no metadata returned");
+                process::exit(1);
+            };
+            println!();
+            println!("{}", "=".repeat(70));
+            println!("{agtuuid}:{path}");
+            println!("{}", "=".repeat(70));
+            println!();
+            println!("   {:.<25} {}", "Type", e.kind);
+            println!("   {:.<25} {} bytes ({})", "Size", e.size, format_bytes(e.size as f64));
+            match e.mode {
+                Some(mode) => println!("   {:.<25} {:04o} ({})", "Mode", mode, mode_string(&e)),
+                None       => println!("   {:.<25} N/A", "Mode"),
+            }
+            println!(
+                "   {:.<25} {}:{}",
+                "Owner",
+                e.owner.as_deref().unwrap_or("-"),
+                e.group.as_deref().unwrap_or("-"),
+            );
+            println!("   {:.<25} {}", "Modified", format_mtime(e.mtime));
+            if let Some(ref target) = e.target {
+                println!("   {:.<25} {target}", "Target");
+            }
+            println!();
+            println!("{}", "=".repeat(70));
+            println!();
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
@@ -1,16 +1,31 @@
 pub mod bench;
+pub mod config;
 pub mod delete;
 pub mod discover;
+pub mod hash;
+pub mod logs;
+pub mod ls;
+pub mod ps;
+pub mod path;
 pub mod put;
 pub mod run;
 pub mod stat;
+pub mod tail;
+pub mod topology;
 
 use std::sync::Arc;
-use tokio::time::{sleep, Duration};
+
+use anyhow::{anyhow, Result};
 
 use crate::{
     executor::agent::AgentClient,
-    models::control::{CheckTicket, CloseTicket, ControlForm, ControlFormTicket},
+    models::{
+        control::{
+            CheckTicket, CloseTicket, ControlForm, ControlFormTicket, GetNames, SelectAgents,
+            WaitTickets,
+        },
+        routing::{resolve_name, Selector},
+    },
 };
 
 pub const KB: usize = 1_024;
@@ -31,9 +46,130 @@ pub fn format_bandwidth(bps: f64) -> String {
     else                    { format!("{:.1} GB/s", bps / GB as f64) }
 }
 
-/// Poll a ticket until serviced or timeout, then read and close it.
+/// An agent UUID followed by its display name, if it has one.
+pub fn agent_label(agtuuid: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{agtuuid} ({name})"),
+        None       => agtuuid.to_string(),
+    }
+}
+
+/// Print the ticket's queue position to stderr when it changes.
+pub fn report_queue_position(position: Option<u64>, reported: &mut Option<u64>) {
+    if position == *reported {
+        return;
+    }
+    match position {
+        Some(0) if reported.is_some() => eprintln!("started"),
+        Some(p) if p > 0 => eprintln!("queued at position {p}"),
+        _ => {}
+    }
+    *reported = position;
+}
+
+/// Ask the agent which of the agents it knows the selector picks.
+pub async fn select_agents(client: &AgentClient, selector: &str) -> Result<Vec<String>> {
+    let form = SelectAgents { selector: selector.to_string(), ..SelectAgents::default() };
+    match client.send_control_form(ControlForm::SelectAgents(form)).await? {
+        ControlForm::SelectAgents(f) => match f.error {
+            Some(e) => Err(anyhow!(e)),
+            None => Ok(f.agents),
+        },
+        _ => Err(anyhow!("unexpected response form")),
+    }
+}
+
+/// Expand a destination argument to agent UUIDs.
+///
+/// A selector (`*` or `key=value[,key=value...]`) is resolved by the agent
+/// to every agent it knows whose labels match.  An agent UUID is used as
+/// given; anything else is a display name, and a name shared by several
+/// agents is an error.
+pub async fn resolve_agents(client: &AgentClient, dst: &str) -> Result<Vec<String>> {
+    if !Selector::is_selector(dst) {
+        if uuid::Uuid::try_parse(dst).is_ok() {
+            return Ok(vec![dst.to_string()]);
+        }
+        // An agent that predates GetNames (or a Python one) cannot resolve
+        // display names, so the argument is taken as given.
+        let names = match client.send_control_form(ControlForm::GetNames(GetNames::default())).await {
+            Ok(ControlForm::GetNames(f)) if f.error.is_none() => f.names,
+            _ => return Ok(vec![dst.to_string()]),
+        };
+        return Ok(vec![resolve_name(&names, dst).map_err(|e| anyhow!(e))?]);
+    }
+    select_agents(client, dst).await
+}
+
+/// Resolve a destination argument that must name exactly one agent.
+pub async fn resolve_agent(client: &AgentClient, dst: &str) -> Result<String> {
+    let agents = resolve_agents(client, dst).await?;
+    match agents.as_slice() {
+        [agtuuid] => Ok(agtuuid.clone()),
+        []        => Err(anyhow!("no known agent matches '{dst}'")),
+        _         => Err(anyhow!("'{dst}' matches {} agents: {}", agents.len(), agents.join(", "))),
+    }
+}
+
+/// Wait until every ticket is serviced or `timeout_secs` pass, returning the
+/// last status of each ticket the agent still holds.
+///
+/// Long-polls with `WaitTickets` from the statuses in `seen`, so the agent
+/// answers as soon as a ticket moves on from them.  `on_update` sees each
+/// answer, including those sent early because a queue position changed or
+/// new partial output arrived, and ends the wait by returning `false`.
+pub async fn wait_for_tickets(
+    client: &AgentClient,
+    mut seen: Vec<CheckTicket>,
+    timeout_secs: u64,
+    mut on_update: impl FnMut(&[CheckTicket]) -> bool,
+) -> Vec<CheckTicket> {
+    let tckuuids: Vec<String> = seen.iter().map(|t| t.tckuuid.clone()).collect();
+    let start = std::time::Instant::now();
+    let mut tickets = Vec::new();
+    loop {
+        let remaining = timeout_secs as f64 - start.elapsed().as_secs_f64();
+        if remaining <= 0.0 {
+            break;
+        }
+        let wait = WaitTickets {
+            tckuuids: tckuuids.clone(),
+            timeout:  Some(remaining),
+            tickets:  seen,
+            ..Default::default()
+        };
+        match client.send_control_form(ControlForm::WaitTickets(wait)).await {
+            Ok(ControlForm::WaitTickets(w)) => match w.error {
+                Some(e) => { eprintln!("poll error: {e}"); break; }
+                None    => tickets = w.tickets,
+            },
+            Ok(_) => break,
+            Err(e) => { eprintln!("poll error: {e}"); break; }
+        }
+        if !on_update(&tickets) || tickets.iter().all(|t| t.service_time.is_some()) {
+            break;
+        }
+        seen = tickets.clone();
+    }
+    tickets
+}
+
+/// The status of `ticket` as last read, to wait on for what comes next.
+pub fn ticket_status(ticket: &ControlFormTicket) -> CheckTicket {
+    CheckTicket {
+        tckuuid:        ticket.tckuuid.clone(),
+        create_time:    Some(ticket.create_time),
+        service_time:   ticket.service_time,
+        queue_position: ticket.queue_position,
+        last_seq:       ticket.progress.iter().map(|p| p.seq).max(),
+        ..Default::default()
+    }
+}
+
+/// Wait for a ticket to be serviced or time out, then read and close it.
 ///
-/// 1. Uses `CheckTicket` (lightweight) to poll for service completion.
+/// 1. Waits with `wait_for_tickets`, reporting the queue position while the
+///    servicing agent has it queued.
 /// 2. Once serviced, reads the full ticket via `read_ticket`.
 /// 3. Closes the ticket via `CloseTicket`.
 pub async fn poll_ticket(
@@ -41,24 +177,14 @@ pub async fn poll_ticket(
     ticket: ControlFormTicket,
     timeout_secs: u64,
 ) -> ControlFormTicket {
-    let start = std::time::Instant::now();
-
-    // Poll with CheckTicket until the ticket is serviced
-    let mut check = CheckTicket {
-        tckuuid:     ticket.tckuuid.clone(),
-        create_time: Some(ticket.create_time),
-        ..Default::default()
-    };
-    while start.elapsed().as_secs() < timeout_secs && check.service_time.is_none() {
-        match client.send_control_form(ControlForm::CheckTicket(check.clone())).await {
-            Ok(ControlForm::CheckTicket(c)) => check = c,
-            Ok(_) => break,
-            Err(e) => { eprintln!("poll error: {e}"); break; }
+    let mut reported = None;
+    wait_for_tickets(&client, vec![ticket_status(&ticket)], timeout_secs, |tickets| {
+        if let Some(check) = tickets.first() {
+            report_queue_position(check.queue_position, &mut reported);
         }
-        if check.service_time.is_none() {
-            sleep(Duration::from_secs(1)).await;
-        }
-    }
+        true
+    })
+    .await;
 
     // Read the full ticket result
     let mut read = ticket.clone();
@@ -0,0 +1,152 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    executor::agent::AgentClient,
+    models::control::{
+        ControlForm, ControlFormTicket, DeletePath, MakeDirectory, MovePath, SetPermissions,
+    },
+};
+
+use super::poll_ticket;
+
+/// Parse an octal mode such as `755` or `0640`.
+pub fn parse_mode(s: &str) -> Result<u32> {
+    match u32::from_str_radix(s, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(anyhow!("expected an octal mode such as 755, got '{s}'")),
+    }
+}
+
+/// Split a `chown` spec (`owner`, `owner:group` or `:group`) into its parts.
+pub fn parse_owner_spec(s: &str) -> Result<(Option<String>, Option<String>)> {
+    let (owner, group) = match s.split_once(':') {
+        Some((owner, group)) => (owner, Some(group)),
+        None => (s, None),
+    };
+    let owner = (!owner.is_empty()).then(|| owner.to_string());
+    let group = group.filter(|g| !g.is_empty()).map(str::to_string);
+    if owner.is_none() && group.is_none() {
+        return Err(anyhow!("expected OWNER, OWNER:GROUP or :GROUP, got '{s}'"));
+    }
+    Ok((owner, group))
+}
+
+/// Send a file-management form to an agent and exit non-zero with its
+/// error (and `error_kind`) if the agent could not carry it out.
+async fn run_path_form(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    form: ControlForm,
+    timeout: u64,
+) -> Result<()> {
+    let name = form.form_type();
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid, form, ..ControlFormTicket::default() })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("{name} ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    let (error_kind, error) = match ticket.form {
+        ControlForm::DeletePath(f)     => (f.error_kind, f.error),
+        ControlForm::MovePath(f)       => (f.error_kind, f.error),
+        ControlForm::MakeDirectory(f)  => (f.error_kind, f.error),
+        ControlForm::SetPermissions(f) => (f.error_kind, f.error),
+        _ => (None, Some("unexpected response form".to_string())),
+    };
+    if let Some(e) = error {
+        match error_kind {
+            Some(kind) => eprintln!("{e} [{kind}]"),
+            None       => eprintln!("{e}"),
+        }
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Delete a path on a remote agent.
+pub async fn cmd_rm(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    recursive: bool,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::DeletePath(DeletePath { path, recursive, ..Default::default() });
+    run_path_form(client, agtuuid, form, timeout).await
+}
+
+/// Move or rename a path on a remote agent.
+pub async fn cmd_mv(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    src: String,
+    dst: String,
+    overwrite: bool,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::MovePath(MovePath { src, dst, overwrite, ..Default::default() });
+    run_path_form(client, agtuuid, form, timeout).await
+}
+
+/// Create a directory on a remote agent.
+pub async fn cmd_mkdir(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    parents: bool,
+    mode: Option<u32>,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::MakeDirectory(MakeDirectory { path, parents, mode, ..Default::default() });
+    run_path_form(client, agtuuid, form, timeout).await
+}
+
+/// Change the mode of a path on a remote agent.
+pub async fn cmd_chmod(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    mode: u32,
+    recursive: bool,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::SetPermissions(SetPermissions {
+        path,
+        mode: Some(mode),
+        recursive,
+        ..Default::default()
+    });
+    run_path_form(client, agtuuid, form, timeout).await
+}
+
+/// Change the owner and/or group of a path on a remote agent.
+pub async fn cmd_chown(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    owner: Option<String>,
+    group: Option<String>,
+    recursive: bool,
+    timeout: u64,
+) -> Result<()> {
+    let form = ControlForm::SetPermissions(SetPermissions {
+        path,
+        owner,
+        group,
+        recursive,
+        ..Default::default()
+    });
+    run_path_form(client, agtuuid, form, timeout).await
+}
@@ -0,0 +1,102 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::{
+    executor::agent::AgentClient,
+    models::control::{
+        CommandArg, ControlForm, ControlFormTicket, KillProcess, ListProcesses, ProcessStatus,
+    },
+};
+
+use super::poll_ticket;
+
+fn command_string(command: &CommandArg) -> String {
+    match command {
+        CommandArg::Single(s) => s.clone(),
+        CommandArg::Multi(v)  => v.join(" "),
+    }
+}
+
+/// List, inspect, or kill background processes on a remote agent.
+///
+/// With neither `status` nor `kill` set, lists every process the agent knows about.
+pub async fn cmd_ps(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    status: Option<String>,
+    kill: Option<String>,
+    timeout: u64,
+) -> Result<()> {
+    let form = if let Some(prcuuid) = kill {
+        ControlForm::KillProcess(KillProcess { prcuuid, ..Default::default() })
+    } else if let Some(prcuuid) = status {
+        ControlForm::ProcessStatus(ProcessStatus { prcuuid, ..Default::default() })
+    } else {
+        ControlForm::ListProcesses(ListProcesses::default())
+    };
+
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid.clone(), form, ..ControlFormTicket::default() })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("Process ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    match ticket.form {
+        ControlForm::ListProcesses(f) => {
+            if let Some(e) = f.error { eprintln!("{e}"); process::exit(1); }
+            println!();
+            println!("{}", "=".repeat(70));
+            println!("Processes on {agtuuid}");
+            println!("{}", "=".repeat(70));
+            println!();
+            for p in &f.processes {
+                let state = match (p.running, p.status) {
+                    (true, _)        => "running".to_string(),
+                    (false, Some(s)) => format!("exit {s}"),
+                    (false, None)    => "killed".to_string(),
+                };
+                println!(
+                    "   {:<36} {:<10} {:>9.1}s  {}",
+                    p.prcuuid, state, p.elapsed_time, command_string(&p.command),
+                );
+            }
+            if f.processes.is_empty() {
+                println!("   (No processes)");
+            }
+            println!();
+            println!("{}", "=".repeat(70));
+            println!();
+        }
+        ControlForm::ProcessStatus(f) => {
+            if let Some(e) = f.error { eprintln!("{e}"); process::exit(1); }
+            if let Some(ref out) = f.stdout { print!("{}", out.trim_end_matches('\n')); }
+            if let Some(ref err) = f.stderr { eprint!("{}", err.trim_end_matches('\n')); }
+            if f.running {
+                eprintln!();
+                eprintln!("(still running after {:.1}s)", f.elapsed_time.unwrap_or(0.0));
+            } else if let Some(status) = f.status {
+                if status != 0 { process::exit(status as i32); }
+            }
+        }
+        ControlForm::KillProcess(f) => {
+            if let Some(e) = f.error { eprintln!("{e}"); process::exit(1); }
+            println!("Killed {}", f.prcuuid);
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
@@ -1,18 +1,43 @@
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
 
 use crate::{
+    dao::Document,
     executor::{
         agent::AgentClient,
-        file::{load_file_to_form, write_file_from_form},
+        archive::{discard_archive, pack_archive, sibling_archive_path, temp_archive_path, unpack_archive},
+        compression::decompress,
+        file::{
+            apply_metadata, check_digest, hash_file, load_file_to_form, write_file_from_form,
+            FileMetadata, Hasher,
+        },
+        transfer::{chunk_digest, part_path, read_chunk, TRANSFER_CHUNK_SIZE},
+    },
+    models::control::{
+        ArchiveEntry, CommitTransfer, ControlForm, ControlFormTicket, DiscardArchive, Encoding,
+        HashAlgorithm, HashFile, LoadFile, PackArchive, ReadChunk, TransferStatus, UnpackArchive,
+        WriteChunk, WriteFile,
     },
-    models::control::{ControlForm, ControlFormTicket, LoadFile, WriteFile},
 };
 
-use super::poll_ticket;
+use super::{format_bytes, poll_ticket, MB};
+
+/// Files larger than this are sent with the chunked, resumable transfer forms.
+const CHUNKED_THRESHOLD: u64 = 16 * MB as u64;
+
+/// Attempts at a single chunk before a chunked transfer gives up.
+const CHUNK_ATTEMPTS: u32 = 3;
 
+/// Copy a file, giving the copy the source's mode, ownership and mtime when
+/// `preserve` is set and the destination's defaults otherwise.  The copy is
+/// checked against the source's digest under `hash_algorithm`.
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_put(
     client: Arc<AgentClient>,
     src_path: String,
@@ -20,14 +45,33 @@ pub async fn cmd_put(
     timeout: u64,
     src_agtuuid: Option<String>,
     dst_agtuuid: Option<String>,
+    encoding: Encoding,
+    hash_algorithm: HashAlgorithm,
+    preserve: bool,
 ) -> Result<()> {
+    if let Some(info) = source_info(&client, &src_path, src_agtuuid.as_deref(), timeout).await {
+        let size = info.size.unwrap_or(0);
+        if size > CHUNKED_THRESHOLD {
+            let metadata = if preserve {
+                FileMetadata { mode: info.mode, uid: info.uid, gid: info.gid, mtime: info.mtime }
+            } else {
+                FileMetadata::default()
+            };
+            return cmd_put_chunked(
+                client, src_path, dst_path, timeout, src_agtuuid, dst_agtuuid, size, metadata,
+                encoding, hash_algorithm,
+            )
+            .await;
+        }
+    }
+
     let read_elapsed;
     let mut write_elapsed = 0.0f64;
     let mut read_error:  Option<String> = None;
     let mut write_error: Option<String> = None;
 
     // Load file from source
-    let mut load_form = LoadFile { path: src_path.clone(), ..Default::default() };
+    let mut load_form = LoadFile { path: src_path.clone(), encoding, hash_algorithm, ..Default::default() };
 
     if let Some(ref src_id) = src_agtuuid {
         println!("Reading from {src_id}:{src_path}...");
@@ -72,12 +116,26 @@ pub async fn cmd_put(
     // Write file to destination
     if read_error.is_none() {
         let wf = WriteFile {
-            b64zlib: load_form.b64zlib.clone().unwrap_or_default(),
-            md5sum:  load_form.md5sum.clone(),
-            size:    load_form.size,
-            path:    dst_path.clone(),
+            b64zlib:        load_form.b64zlib.clone().unwrap_or_default(),
+            encoding:       load_form.encoding,
+            md5sum:         load_form.md5sum.clone(),
+            hash_algorithm: load_form.hash_algorithm,
+            hash:           load_form.hash.clone(),
+            size:           load_form.size,
+            path:           dst_path.clone(),
             ..Default::default()
         };
+        let wf = if preserve {
+            WriteFile {
+                mode:  load_form.mode,
+                uid:   load_form.uid,
+                gid:   load_form.gid,
+                mtime: load_form.mtime,
+                ..wf
+            }
+        } else {
+            wf
+        };
 
         if let Some(ref dst_id) = dst_agtuuid {
             println!("Writing to {dst_id}:{dst_path}...");
@@ -119,46 +177,736 @@ pub async fn cmd_put(
         }
     }
 
-    // Pretty print results
-    println!();
-    println!("{}", "=".repeat(70));
-    println!("File Transfer Result");
-    println!("{}", "=".repeat(70));
+    TransferReport {
+        src_loc: Endpoint { agtuuid: src_agtuuid.as_deref(), path: &src_path }.location(),
+        dst_loc: Endpoint { agtuuid: dst_agtuuid.as_deref(), path: &dst_path }.location(),
+        size:    load_form.size.unwrap_or(0) as u64,
+        hash_algorithm,
+        hash:    load_form.hash,
+        read_elapsed,
+        write_elapsed,
+        read_error,
+        write_error,
+    }
+    .print();
+    Ok(())
+}
 
-    println!();
-    println!("Transfer Details");
-    let src_loc = src_agtuuid.as_deref()
-        .map(|id| format!("{id}:{src_path}"))
-        .unwrap_or_else(|| format!("local:{src_path}"));
-    let dst_loc = dst_agtuuid.as_deref()
-        .map(|id| format!("{id}:{dst_path}"))
-        .unwrap_or_else(|| format!("local:{dst_path}"));
-    println!("   Source................... {src_loc}");
-    println!("   Destination.............. {dst_loc}");
+/// Transfer a large file chunk by chunk, resuming whatever an earlier attempt
+/// with the same source, destination and source contents already staged.
+#[allow(clippy::too_many_arguments)]
+async fn cmd_put_chunked(
+    client: Arc<AgentClient>,
+    src_path: String,
+    dst_path: String,
+    timeout: u64,
+    src_agtuuid: Option<String>,
+    dst_agtuuid: Option<String>,
+    size: u64,
+    metadata: FileMetadata,
+    encoding: Encoding,
+    hash_algorithm: HashAlgorithm,
+) -> Result<()> {
+    let src = Endpoint { agtuuid: src_agtuuid.as_deref(), path: &src_path };
+    let dst = Endpoint { agtuuid: dst_agtuuid.as_deref(), path: &dst_path };
+    let mut report = TransferReport {
+        src_loc: src.location(),
+        dst_loc: dst.location(),
+        size,
+        hash_algorithm,
+        ..Default::default()
+    };
+    transfer_chunked(&client, src, dst, size, metadata, encoding, timeout, &mut report).await?;
 
-    println!();
-    println!("File Information");
-    println!("   Size..................... {} bytes", load_form.size.unwrap_or(0));
-    println!("   MD5 Checksum............. {}", load_form.md5sum.as_deref().unwrap_or("N/A"));
+    report.print();
+    if report.read_error.is_some() || report.write_error.is_some() {
+        println!("Run the same put again to resume the transfer.");
+        println!();
+    }
+    Ok(())
+}
 
-    println!();
-    println!("Timing Information");
-    println!("   Read Elapsed Time........ {read_elapsed:.3} seconds");
-    println!("   Write Elapsed Time....... {write_elapsed:.3} seconds");
-    println!("   Total Elapsed Time....... {:.3} seconds", read_elapsed + write_elapsed);
+/// Copy a directory tree as one stream: pack it into an archive at the
+/// source, move the archive with the chunked transfer forms and unpack it at
+/// the destination.  The archive is checked under `hash_algorithm`.
+pub async fn cmd_put_dir(
+    client: Arc<AgentClient>,
+    src_path: String,
+    dst_path: String,
+    timeout: u64,
+    src_agtuuid: Option<String>,
+    dst_agtuuid: Option<String>,
+    hash_algorithm: HashAlgorithm,
+) -> Result<()> {
+    let src = Endpoint { agtuuid: src_agtuuid.as_deref(), path: &src_path };
+    let dst = Endpoint { agtuuid: dst_agtuuid.as_deref(), path: &dst_path };
+    let mut report = DirectoryReport {
+        src_loc: src.location(),
+        dst_loc: dst.location(),
+        ..Default::default()
+    };
 
-    if read_error.is_some() || write_error.is_some() {
-        println!();
-        println!("Errors Occurred");
-        if let Some(ref e) = read_error  { println!("   Read Error............... {e}"); }
-        if let Some(ref e) = write_error { println!("   Write Error.............. {e}"); }
+    println!("Packing {}...", src.location());
+    let pack_start = Instant::now();
+    let packed = pack_source(&client, src, timeout).await;
+    report.pack_elapsed = pack_start.elapsed().as_secs_f64();
+    let packed = match packed {
+        Ok(packed) => packed,
+        Err(e) => {
+            report.error = Some(e.to_string());
+            report.print();
+            return Ok(());
+        }
+    };
+    report.archive_size = packed.size.unwrap_or(0);
+    report.packed       = packed.entries;
+    let src_archive     = packed.archive.unwrap_or_default();
+
+    // A local-to-local copy unpacks the packed archive where it is.
+    let dst_archive = if src.agtuuid.is_none() && dst.agtuuid.is_none() {
+        src_archive.clone()
     } else {
-        println!();
-        println!("✓ Transfer Complete");
+        let transfer_start = Instant::now();
+        let moved = move_archive(
+            &client, src, &src_archive, dst, report.archive_size, hash_algorithm, timeout,
+        )
+        .await;
+        report.transfer_elapsed = transfer_start.elapsed().as_secs_f64();
+        discard_source(&client, src.agtuuid, &src_archive, timeout).await;
+        match moved {
+            Ok(dst_archive) => dst_archive,
+            Err(e) => {
+                report.error = Some(e.to_string());
+                report.print();
+                return Ok(());
+            }
+        }
+    };
+
+    println!("Unpacking into {}...", dst.location());
+    let unpack_start = Instant::now();
+    let form = UnpackArchive {
+        archive: dst_archive,
+        path:    dst.path.to_string(),
+        ..Default::default()
+    };
+    let unpacked = match dst.agtuuid {
+        Some(id) => match send_form(&client, id, ControlForm::UnpackArchive(form), timeout).await {
+            Ok(ControlForm::UnpackArchive(f)) => Ok(f),
+            Ok(_) => Err(anyhow!("unexpected response form")),
+            Err(e) => Err(e),
+        },
+        None => Ok(unpack_archive(form)),
+    };
+    report.unpack_elapsed = unpack_start.elapsed().as_secs_f64();
+    match unpacked {
+        Ok(UnpackArchive { error: Some(e), .. }) => report.error = Some(e),
+        Ok(f) => report.unpacked = f.entries,
+        Err(e) => report.error = Some(e.to_string()),
     }
 
+    report.print();
+    Ok(())
+}
+
+/// Pack the source directory, locally or on the source agent.
+async fn pack_source(client: &Arc<AgentClient>, src: Endpoint<'_>, timeout: u64) -> Result<PackArchive> {
+    let form = PackArchive { path: src.path.to_string(), ..Default::default() };
+    let packed = match src.agtuuid {
+        Some(id) => match send_form(client, id, ControlForm::PackArchive(form), timeout).await? {
+            ControlForm::PackArchive(f) => f,
+            _ => return Err(anyhow!("unexpected response form")),
+        },
+        None => pack_archive(form),
+    };
+    match packed.error {
+        Some(e) => Err(anyhow!(e)),
+        None => Ok(packed),
+    }
+}
+
+/// Transfer a packed archive to a hidden file beside the destination
+/// directory (or to the local temp directory), returning its path there.
+async fn move_archive(
+    client: &Arc<AgentClient>,
+    src: Endpoint<'_>,
+    src_archive: &str,
+    dst: Endpoint<'_>,
+    size: u64,
+    hash_algorithm: HashAlgorithm,
+    timeout: u64,
+) -> Result<String> {
+    let dst_archive = match dst.agtuuid {
+        Some(_) => sibling_archive_path(Path::new(dst.path))?,
+        None => temp_archive_path(),
+    };
+    let dst_archive = dst_archive.to_string_lossy().into_owned();
+
+    // The archive is already gzipped, so its chunks are sent as they are.
+    let mut transfer = TransferReport { hash_algorithm, ..Default::default() };
+    transfer_chunked(
+        client,
+        Endpoint { agtuuid: src.agtuuid, path: src_archive },
+        Endpoint { agtuuid: dst.agtuuid, path: &dst_archive },
+        size,
+        FileMetadata::default(),
+        Encoding::None,
+        timeout,
+        &mut transfer,
+    )
+    .await?;
+    match transfer.read_error.or(transfer.write_error) {
+        Some(e) => Err(anyhow!(e)),
+        None => Ok(dst_archive),
+    }
+}
+
+/// Remove the packed archive from the source once it has been transferred.
+async fn discard_source(client: &Arc<AgentClient>, agtuuid: Option<&str>, archive: &str, timeout: u64) {
+    let form = DiscardArchive { archive: archive.to_string(), ..Default::default() };
+    let error = match agtuuid {
+        Some(id) => match send_form(client, id, ControlForm::DiscardArchive(form), timeout).await {
+            Ok(ControlForm::DiscardArchive(f)) => f.error,
+            Ok(_) => Some("unexpected response form".to_string()),
+            Err(e) => Some(e.to_string()),
+        },
+        None => discard_archive(form).error,
+    };
+    if let Some(e) = error {
+        eprintln!("failed to remove {archive}: {e}");
+    }
+}
+
+/// Move `src` to `dst` with the chunked transfer forms, giving it `metadata`,
+/// and record timings, the committed digest and any error in `report`.
+/// Chunks are compressed under `encoding`, and they and the assembled file
+/// are checked under `report.hash_algorithm`.
+///
+/// The source's digest is taken first: it names the transfer, so a changed
+/// source never resumes onto chunks staged from its old contents, and the
+/// destination checks the assembled file against it before committing.
+#[allow(clippy::too_many_arguments)]
+async fn transfer_chunked(
+    client: &Arc<AgentClient>,
+    src: Endpoint<'_>,
+    dst: Endpoint<'_>,
+    size: u64,
+    metadata: FileMetadata,
+    encoding: Encoding,
+    timeout: u64,
+    report: &mut TransferReport,
+) -> Result<()> {
+    println!("Hashing {}...", src.location());
+    let hash_start = Instant::now();
+    let hash_algorithm = report.hash_algorithm;
+    let digest = source_digest(client, src, size, hash_algorithm, timeout).await;
+    report.read_elapsed += hash_start.elapsed().as_secs_f64();
+    let digest = match digest {
+        Ok(digest) => digest,
+        Err(e) => {
+            report.read_error = Some(e.to_string());
+            return Ok(());
+        }
+    };
+
+    let trnuuid = Document::get_uuid_from_str(&format!(
+        "{}>{}:{size}:{digest}", src.location(), dst.location()
+    ));
+    let destination = match dst.agtuuid {
+        Some(id) => Destination::Remote(id),
+        None => Destination::Local(part_path(Path::new(dst.path), &trnuuid)?),
+    };
+
+    println!(
+        "Transferring {} to {} in {} chunks...",
+        src.location(), dst.location(), format_bytes(TRANSFER_CHUNK_SIZE as f64)
+    );
+
+    let mut offset = match destination.received(client, &trnuuid, timeout).await {
+        Ok(received) if received <= size => received,
+        Ok(_) => 0,
+        Err(e) => {
+            report.write_error = Some(e.to_string());
+            return Ok(());
+        }
+    };
+    if offset > 0 {
+        println!("Resuming at {} of {}", format_bytes(offset as f64), format_bytes(size as f64));
+    }
+
+    let mut attempts = 0;
+    while offset < size && report.read_error.is_none() && report.write_error.is_none() {
+        show_progress(offset, size);
+
+        let read_start = Instant::now();
+        let read = ReadChunk {
+            path:   src.path.to_string(),
+            offset,
+            length: TRANSFER_CHUNK_SIZE,
+            encoding,
+            hash_algorithm,
+            ..Default::default()
+        };
+        let chunk = read_source_chunk(client, src.agtuuid, read, timeout).await;
+        report.read_elapsed += read_start.elapsed().as_secs_f64();
+        let chunk = match chunk {
+            Ok(chunk) if chunk.size == Some(size) => chunk,
+            Ok(_) => {
+                report.read_error = Some("source file changed size during the transfer".to_string());
+                break;
+            }
+            Err(e) => {
+                retry_or_fail(&mut attempts, offset, e.to_string(), &mut report.read_error);
+                continue;
+            }
+        };
+
+        let write_start = Instant::now();
+        let write = WriteChunk {
+            trnuuid:  trnuuid.clone(),
+            offset,
+            b64zlib:  chunk.b64zlib.unwrap_or_default(),
+            encoding: chunk.encoding,
+            md5sum:   chunk.md5sum.unwrap_or_default(),
+            hash_algorithm,
+            hash:     chunk.hash,
+            ..Default::default()
+        };
+        let written = destination.write(client, write, timeout).await;
+        report.write_elapsed += write_start.elapsed().as_secs_f64();
+        match written {
+            Ok(WriteChunk { error: None, received: Some(received), .. }) => {
+                offset = received;
+                attempts = 0;
+            }
+            Ok(rejected) => {
+                // Pick up from wherever the destination says it is.
+                if let Some(received) = rejected.received {
+                    offset = received;
+                }
+                let e = rejected.error.unwrap_or_else(|| "chunk not acknowledged".to_string());
+                retry_or_fail(&mut attempts, offset, e, &mut report.write_error);
+            }
+            Err(e) => retry_or_fail(&mut attempts, offset, e.to_string(), &mut report.write_error),
+        }
+    }
+    show_progress(offset, size);
     println!();
-    println!("{}", "=".repeat(70));
-    println!();
+
+    if report.read_error.is_none() && report.write_error.is_none() {
+        println!("Committing {}...", dst.location());
+        let write_start = Instant::now();
+        let commit = CommitTransfer {
+            trnuuid: trnuuid.clone(),
+            path:    dst.path.to_string(),
+            size,
+            md5sum:  (hash_algorithm == HashAlgorithm::Md5).then(|| digest.clone()),
+            hash_algorithm,
+            hash:    Some(digest),
+            mode:    metadata.mode,
+            uid:     metadata.uid,
+            gid:     metadata.gid,
+            mtime:   metadata.mtime,
+            ..Default::default()
+        };
+        match destination.commit(client, commit, timeout).await {
+            Ok(CommitTransfer { error: Some(e), .. }) => report.write_error = Some(e),
+            Ok(committed) => report.hash = committed.hash.or(committed.md5sum),
+            Err(e) => report.write_error = Some(e.to_string()),
+        }
+        report.write_elapsed += write_start.elapsed().as_secs_f64();
+    }
+
     Ok(())
 }
+
+/// Count a failed chunk attempt, recording `error` once the attempts run out.
+fn retry_or_fail(attempts: &mut u32, offset: u64, error: String, failure: &mut Option<String>) {
+    *attempts += 1;
+    if *attempts >= CHUNK_ATTEMPTS {
+        *failure = Some(error);
+    } else {
+        eprintln!();
+        eprintln!("chunk at offset {offset} failed, retrying: {error}");
+    }
+}
+
+fn show_progress(done: u64, size: u64) {
+    let percent = (done * 100).checked_div(size).unwrap_or(100);
+    print!(
+        "\r   Progress................. {percent:>3}% ({} of {})",
+        format_bytes(done as f64),
+        format_bytes(size as f64)
+    );
+    let _ = std::io::stdout().flush();
+}
+
+/// Send `form` to `agtuuid` in a ticket and return the serviced form.
+async fn send_form(
+    client: &Arc<AgentClient>,
+    agtuuid: &str,
+    form: ControlForm,
+    timeout: u64,
+) -> Result<ControlForm> {
+    let form_type = form.form_type();
+    let ticket = client
+        .send_ticket(ControlFormTicket {
+            dst: agtuuid.to_string(),
+            form,
+            ..ControlFormTicket::default()
+        })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(client), ticket, timeout * 2).await;
+
+    if ticket.service_time.is_none() {
+        return Err(anyhow!("{form_type} ticket never serviced!"));
+    }
+    if let Some(e) = ticket.error {
+        return Err(anyhow!(e));
+    }
+    Ok(ticket.form)
+}
+
+/// Read a chunk from the local filesystem or from the source agent.
+async fn read_source_chunk(
+    client: &Arc<AgentClient>,
+    src_agtuuid: Option<&str>,
+    form: ReadChunk,
+    timeout: u64,
+) -> Result<ReadChunk> {
+    let form = match src_agtuuid {
+        Some(id) => match send_form(client, id, ControlForm::ReadChunk(form), timeout).await? {
+            ControlForm::ReadChunk(f) => f,
+            _ => return Err(anyhow!("unexpected response form")),
+        },
+        None => read_chunk(form),
+    };
+    match form.error {
+        Some(e) => Err(anyhow!(e)),
+        None => Ok(form),
+    }
+}
+
+/// Digest of the source file under `hash_algorithm`, taken locally or on the
+/// source agent.
+async fn source_digest(
+    client: &Arc<AgentClient>,
+    src: Endpoint<'_>,
+    size: u64,
+    hash_algorithm: HashAlgorithm,
+    timeout: u64,
+) -> Result<String> {
+    let form = HashFile { path: src.path.to_string(), hash_algorithm, ..Default::default() };
+    let form = match src.agtuuid {
+        Some(id) => {
+            // Allow the agent a second per 16 MB to read the file.
+            let timeout = timeout + size / (16 * MB as u64);
+            match send_form(client, id, ControlForm::HashFile(form), timeout).await? {
+                ControlForm::HashFile(f) => f,
+                _ => return Err(anyhow!("unexpected response form")),
+            }
+        }
+        None => hash_file(form),
+    };
+    match (form.error, form.hash) {
+        (Some(e), _) => Err(anyhow!(e)),
+        (None, Some(hash)) => Ok(hash),
+        (None, None) => Err(anyhow!("no digest returned for {}", src.location())),
+    }
+}
+
+/// Size and metadata of the source file, or `None` if it cannot be read.
+async fn source_info(
+    client: &Arc<AgentClient>,
+    src_path: &str,
+    src_agtuuid: Option<&str>,
+    timeout: u64,
+) -> Option<ReadChunk> {
+    let form = ReadChunk { path: src_path.to_string(), ..Default::default() };
+    read_source_chunk(client, src_agtuuid, form, timeout).await.ok()
+}
+
+/// Where a chunked transfer is staged: the `transfers` datastore of a remote
+/// agent, or a `.part` file next to a local destination.
+enum Destination<'a> {
+    Remote(&'a str),
+    Local(PathBuf),
+}
+
+impl Destination<'_> {
+    async fn received(&self, client: &Arc<AgentClient>, trnuuid: &str, timeout: u64) -> Result<u64> {
+        match self {
+            Self::Remote(id) => {
+                let form = TransferStatus { trnuuid: trnuuid.to_string(), ..Default::default() };
+                match send_form(client, id, ControlForm::TransferStatus(form), timeout).await? {
+                    ControlForm::TransferStatus(TransferStatus { error: Some(e), .. }) => Err(anyhow!(e)),
+                    ControlForm::TransferStatus(f) => Ok(f.received.unwrap_or(0)),
+                    _ => Err(anyhow!("unexpected response form")),
+                }
+            }
+            Self::Local(part) => Ok(std::fs::metadata(part).map(|m| m.len()).unwrap_or(0)),
+        }
+    }
+
+    async fn write(&self, client: &Arc<AgentClient>, mut form: WriteChunk, timeout: u64) -> Result<WriteChunk> {
+        match self {
+            Self::Remote(id) => match send_form(client, id, ControlForm::WriteChunk(form), timeout).await? {
+                ControlForm::WriteChunk(f) => Ok(f),
+                _ => Err(anyhow!("unexpected response form")),
+            },
+            Self::Local(part) => {
+                match append_part(part, &form) {
+                    Ok(received) => form.received = Some(received),
+                    Err(e) => {
+                        form.received = std::fs::metadata(part).map(|m| m.len()).ok();
+                        form.error    = Some(e.to_string());
+                    }
+                }
+                Ok(form)
+            }
+        }
+    }
+
+    async fn commit(
+        &self,
+        client: &Arc<AgentClient>,
+        mut form: CommitTransfer,
+        timeout: u64,
+    ) -> Result<CommitTransfer> {
+        match self {
+            Self::Remote(id) => {
+                // Allow the agent a second per 16 MB to assemble the file.
+                let timeout = timeout + form.size / (16 * MB as u64);
+                match send_form(client, id, ControlForm::CommitTransfer(form), timeout).await? {
+                    ControlForm::CommitTransfer(f) => Ok(f),
+                    _ => Err(anyhow!("unexpected response form")),
+                }
+            }
+            Self::Local(part) => {
+                let metadata = FileMetadata { mode: form.mode, uid: form.uid, gid: form.gid, mtime: form.mtime };
+                let hash = commit_part(
+                    part, Path::new(&form.path), form.size, form.hash_algorithm, form.hash.as_deref(), &metadata,
+                )?;
+                form.hash = Some(hash);
+                Ok(form)
+            }
+        }
+    }
+}
+
+/// Append a chunk to a local `.part` file; offset `0` starts it over.
+fn append_part(part: &Path, form: &WriteChunk) -> Result<u64> {
+    let data = decompress(form.encoding, &B64.decode(&form.b64zlib)?)?;
+    check_digest(Some(chunk_digest(form)), &data, "")?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(form.offset > 0)
+        .write(true)
+        .truncate(form.offset == 0)
+        .open(part)?;
+    let received = file.metadata()?.len();
+    if received != form.offset {
+        return Err(anyhow!("chunk offset {} does not match the {} bytes received", form.offset, received));
+    }
+    file.write_all(&data)?;
+    Ok(received + data.len() as u64)
+}
+
+/// Check a local `.part` file is complete and its digest under `algorithm`
+/// matches `expected`, if given, give it `metadata` and rename it to `path`.
+fn commit_part(
+    part: &Path,
+    path: &Path,
+    size: u64,
+    algorithm: HashAlgorithm,
+    expected: Option<&str>,
+    metadata: &FileMetadata,
+) -> Result<String> {
+    let mut file = std::fs::File::open(part)?;
+    let received = file.metadata()?.len();
+    if received != size {
+        return Err(anyhow!("transfer incomplete: {received} of {size} bytes received"));
+    }
+
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; TRANSFER_CHUNK_SIZE as usize];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    let hash = hasher.finalize();
+    if let Some(expected) = expected {
+        if hash != expected {
+            let _ = std::fs::remove_file(part);
+            return Err(anyhow!(
+                "{} mismatch: expected {expected}, got {hash}", algorithm.as_str().to_uppercase()
+            ));
+        }
+    }
+    apply_metadata(&file, path, metadata)?;
+    file.sync_all()?;
+    std::fs::rename(part, path)?;
+    Ok(hash)
+}
+
+/// A path on an agent, or on the local filesystem when `agtuuid` is `None`.
+#[derive(Clone, Copy)]
+struct Endpoint<'a> {
+    agtuuid: Option<&'a str>,
+    path:    &'a str,
+}
+
+impl Endpoint<'_> {
+    fn location(&self) -> String {
+        match self.agtuuid {
+            Some(id) => format!("{id}:{}", self.path),
+            None     => format!("local:{}", self.path),
+        }
+    }
+}
+
+/// Outcome of a `put`, printed as the results banner.
+#[derive(Default)]
+struct TransferReport {
+    src_loc:        String,
+    dst_loc:        String,
+    size:           u64,
+    hash_algorithm: HashAlgorithm,
+    hash:           Option<String>,
+    read_elapsed:   f64,
+    write_elapsed:  f64,
+    read_error:     Option<String>,
+    write_error:    Option<String>,
+}
+
+impl TransferReport {
+    fn print(&self) {
+        println!();
+        println!("{}", "=".repeat(70));
+        println!("File Transfer Result");
+        println!("{}", "=".repeat(70));
+
+        println!();
+        println!("Transfer Details");
+        println!("   Source................... {}", self.src_loc);
+        println!("   Destination.............. {}", self.dst_loc);
+
+        println!();
+        println!("File Information");
+        println!("   Size..................... {} bytes", self.size);
+        let label = format!("{} Checksum", self.hash_algorithm.as_str().to_uppercase());
+        println!("   {label:.<25} {}", self.hash.as_deref().unwrap_or("N/A"));
+
+        println!();
+        println!("Timing Information");
+        println!("   Read Elapsed Time........ {:.3} seconds", self.read_elapsed);
+        println!("   Write Elapsed Time....... {:.3} seconds", self.write_elapsed);
+        println!("   Total Elapsed Time....... {:.3} seconds", self.read_elapsed + self.write_elapsed);
+
+        if self.read_error.is_some() || self.write_error.is_some() {
+            println!();
+            println!("Errors Occurred");
+            if let Some(ref e) = self.read_error  { println!("   Read Error............... {e}"); }
+            if let Some(ref e) = self.write_error { println!("   Write Error.............. {e}"); }
+        } else {
+            println!();
+            println!("✓ Transfer Complete");
+        }
+
+        println!();
+        println!("{}", "=".repeat(70));
+        println!();
+    }
+}
+
+/// Outcome of a `put -r`, printed as the results banner with one line per
+/// entry.
+#[derive(Default)]
+struct DirectoryReport {
+    src_loc:          String,
+    dst_loc:          String,
+    archive_size:     u64,
+    /// Entries as packed at the source; ones with an error were left out.
+    packed:           Vec<ArchiveEntry>,
+    /// Entries as unpacked at the destination.
+    unpacked:         Vec<ArchiveEntry>,
+    pack_elapsed:     f64,
+    transfer_elapsed: f64,
+    unpack_elapsed:   f64,
+    error:            Option<String>,
+}
+
+impl DirectoryReport {
+    fn print(&self) {
+        let skipped: Vec<_> = self.packed.iter().filter(|e| e.error.is_some()).collect();
+        let failed = skipped.len() + self.unpacked.iter().filter(|e| e.error.is_some()).count();
+        let count = |kind: &str| {
+            self.unpacked.iter().filter(|e| e.error.is_none() && e.kind == kind).count()
+        };
+        let file_bytes: u64 = self.unpacked.iter()
+            .filter(|e| e.error.is_none() && e.kind == "file")
+            .map(|e| e.size)
+            .sum();
+
+        println!();
+        println!("{}", "=".repeat(70));
+        println!("Directory Transfer Result");
+        println!("{}", "=".repeat(70));
+
+        println!();
+        println!("Transfer Details");
+        println!("   Source................... {}", self.src_loc);
+        println!("   Destination.............. {}", self.dst_loc);
+
+        if !self.unpacked.is_empty() || !skipped.is_empty() {
+            println!();
+            println!("Entries");
+            for e in &self.unpacked {
+                let suffix = if e.kind == "directory" { "/" } else { "" };
+                match e.error {
+                    Some(ref err) => println!("   FAILED  {}{suffix}: {err}", e.name),
+                    None          => println!("   ok      {}{suffix}", e.name),
+                }
+            }
+            for e in &skipped {
+                println!("   SKIPPED {}: {}", e.name, e.error.as_deref().unwrap_or_default());
+            }
+        }
+
+        println!();
+        println!("Summary");
+        println!("   Files.................... {} ({})", count("file"), format_bytes(file_bytes as f64));
+        println!("   Directories.............. {}", count("directory"));
+        println!("   Symlinks................. {}", count("symlink"));
+        println!("   Failed................... {failed}");
+        println!("   Archive Size............. {}", format_bytes(self.archive_size as f64));
+
+        println!();
+        println!("Timing Information");
+        println!("   Pack Elapsed Time........ {:.3} seconds", self.pack_elapsed);
+        println!("   Transfer Elapsed Time.... {:.3} seconds", self.transfer_elapsed);
+        println!("   Unpack Elapsed Time...... {:.3} seconds", self.unpack_elapsed);
+        println!(
+            "   Total Elapsed Time....... {:.3} seconds",
+            self.pack_elapsed + self.transfer_elapsed + self.unpack_elapsed
+        );
+
+        println!();
+        if let Some(ref e) = self.error {
+            println!("Errors Occurred");
+            println!("   Error.................... {e}");
+        } else if failed > 0 {
+            println!("Transfer Complete with {failed} failed entries");
+        } else {
+            println!("✓ Transfer Complete");
+        }
+
+        println!();
+        println!("{}", "=".repeat(70));
+        println!();
+    }
+}
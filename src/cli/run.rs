@@ -1,31 +1,137 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Write};
 use std::process;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use regex::Regex;
 
 use crate::{
     executor::agent::AgentClient,
-    models::control::{CommandArg, ControlForm, ControlFormTicket, SyncProcess},
+    models::{
+        control::{CloseTicket, CommandArg, ControlForm, ControlFormTicket, StartProcess, SyncProcess},
+        routing::Selector,
+    },
 };
 
-use super::poll_ticket;
+use super::{
+    poll_ticket, report_queue_position, resolve_agent, resolve_agents, select_agents, ticket_status,
+    wait_for_tickets,
+};
+
+/// Process settings for `run` beyond the command itself.
+#[derive(Debug, Default)]
+pub struct RunOptions {
+    pub cwd:       Option<String>,
+    pub env:       Vec<(String, String)>,
+    pub clear_env: bool,
+    /// Local file whose contents are sent as stdin; `-` reads our own stdin.
+    pub stdin:     Option<String>,
+    pub user:      Option<String>,
+}
+
+/// The agents `run` targets and how their results are reported.
+#[derive(Debug, Default)]
+pub struct RunTargets {
+    /// Agent UUIDs, or selectors picking agents by label.
+    pub agtuuids: Vec<String>,
+    /// Also every known agent whose UUID matches.
+    pub matching: Option<Regex>,
+    /// Every known agent.
+    pub all:      bool,
+    /// Agents to run on at once; `0` runs on all of them together.
+    pub parallel: usize,
+    /// Print each distinct output after the summary table.
+    pub output:   bool,
+}
+
+impl RunTargets {
+    /// The agent to run on when exactly one was named and nothing else.
+    fn single(&self) -> Option<&str> {
+        match self.agtuuids.as_slice() {
+            [agtuuid] if self.matching.is_none() && !self.all && !Selector::is_selector(agtuuid) => {
+                Some(agtuuid)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a regular expression over agent UUIDs.
+pub fn parse_regex(s: &str) -> Result<Regex> {
+    Regex::new(s).map_err(|e| anyhow!("invalid regex '{s}': {e}"))
+}
+
+/// Parse a `KEY=VALUE` environment assignment.
+pub fn parse_env_pair(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(anyhow!("expected KEY=VALUE, got '{s}'")),
+    }
+}
+
+/// Build the `SyncProcess` form for a command and its options.
+fn sync_process_form(command: String, timeout: u64, options: RunOptions) -> Result<SyncProcess> {
+    let b64stdin = match options.stdin.as_deref() {
+        Some("-") => {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data)?;
+            Some(B64.encode(data))
+        }
+        Some(path) => Some(B64.encode(std::fs::read(path)?)),
+        None => None,
+    };
+
+    Ok(SyncProcess {
+        command: CommandArg::Single(command),
+        timeout: timeout as i64,
+        cwd: options.cwd,
+        env: options.env.into_iter().collect::<BTreeMap<_, _>>(),
+        clear_env: options.clear_env,
+        b64stdin,
+        run_as_user: options.user,
+        ..Default::default()
+    })
+}
 
 pub async fn cmd_run(
     client: Arc<AgentClient>,
-    agtuuid: String,
+    targets: RunTargets,
     command: String,
     timeout: u64,
+    options: RunOptions,
+    detach: bool,
+    follow: bool,
 ) -> Result<()> {
+    let Some(agtuuid) = targets.single().map(str::to_string) else {
+        if detach || follow {
+            eprintln!("--detach and --follow run on a single agent");
+            process::exit(1);
+        }
+        let form = sync_process_form(command, timeout, options)?;
+        return cmd_run_many(client, targets, form, timeout).await;
+    };
+    let agtuuid = match resolve_agent(&client, &agtuuid).await {
+        Ok(agtuuid) => agtuuid,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    };
+
+    let form = sync_process_form(command, timeout, options)?;
+    if detach {
+        return cmd_run_detached(client, agtuuid, form, timeout).await;
+    }
+    if follow {
+        return cmd_run_follow(client, agtuuid, form, timeout).await;
+    }
+
     let ticket = client
         .send_ticket(ControlFormTicket {
             dst: agtuuid,
-            form: ControlForm::SyncProcess(SyncProcess {
-                command: CommandArg::Single(command),
-                timeout: timeout as i64,
-                stdout: None, stderr: None, status: None,
-                start_time: None, elapsed_time: None,
-                error: None, objuuid: None, coluuid: None,
-            }),
+            form: ControlForm::SyncProcess(form),
             ..ControlFormTicket::default()
         })
         .await?;
@@ -35,15 +141,369 @@ pub async fn cmd_run(
     if let ControlForm::SyncProcess(ref f) = ticket.form {
         if let Some(ref out) = f.stdout { print!("{}", out.trim_end_matches('\n')); }
         if let Some(ref err) = f.stderr { eprint!("{}", err.trim_end_matches('\n')); }
-        if let Some(status) = f.status {
-            if status != 0 { process::exit(status as i32); }
+        exit_with_process_status(f, timeout);
+    }
+
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Report how the remote process ended and exit with a matching status.
+///
+/// A process ended by a signal exits with `128 + signal`, as a shell would.
+fn exit_with_process_status(f: &SyncProcess, timeout: u64) {
+    if f.timed_out {
+        eprintln!("command timed out after {timeout}s");
+    }
+    if let Some(signal) = f.signal {
+        process::exit(128 + signal as i32);
+    }
+    if let Some(status) = f.status {
+        if status != 0 { process::exit(status as i32); }
+    }
+}
+
+/// Run the command with streamed output, printing it as it arrives.
+///
+/// Progress chunks are printed in `seq` order; once the ticket is serviced,
+/// whatever the chunks did not cover is printed from the final form.
+async fn cmd_run_follow(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    form: SyncProcess,
+    timeout: u64,
+) -> Result<()> {
+    let ticket = client
+        .send_ticket(ControlFormTicket {
+            dst: agtuuid,
+            form: ControlForm::SyncProcess(SyncProcess { stream: true, ..form }),
+            ..ControlFormTicket::default()
+        })
+        .await?;
+
+    let start = std::time::Instant::now();
+    let mut read = ticket.clone();
+    read.form_type = "read_ticket".to_string();
+
+    let mut result = ticket.clone();
+    let mut next_seq = 0;
+    let mut printed_out = 0;
+    let mut printed_err = 0;
+    let mut reported = None;
+    while start.elapsed().as_secs() < timeout * 2 {
+        result = match client.send_ticket(read.clone()).await {
+            Ok(t)  => t,
+            Err(e) => { eprintln!("read ticket error: {e}"); break; }
+        };
+        report_queue_position(result.queue_position, &mut reported);
+
+        result.progress.sort_by_key(|p| p.seq);
+        for p in &result.progress {
+            if p.seq < next_seq { continue; }
+            if p.seq > next_seq { break; }
+            // Agents report where each chunk starts; one that does not is
+            // counted from what was printed so far.
+            if let Some(ref out) = p.stdout {
+                print!("{out}");
+                printed_out = p.stdout_offset.map_or(printed_out, |o| o as usize) + out.len();
+            }
+            if let Some(ref err) = p.stderr {
+                eprint!("{err}");
+                printed_err = p.stderr_offset.map_or(printed_err, |o| o as usize) + err.len();
+            }
+            next_seq += 1;
+        }
+        let _ = std::io::stdout().flush();
+
+        if result.service_time.is_some() { break; }
+        let remaining = (timeout * 2).saturating_sub(start.elapsed().as_secs());
+        wait_for_tickets(&client, vec![ticket_status(&result)], remaining, |_| false).await;
+    }
+
+    let close = ControlForm::CloseTicket(CloseTicket {
+        tckuuid: ticket.tckuuid.clone(),
+        ..Default::default()
+    });
+    if let Err(e) = client.send_control_form(close).await {
+        eprintln!("close ticket error: {e}");
+    }
+
+    if let ControlForm::SyncProcess(ref f) = result.form {
+        if let Some(out) = f.stdout.as_deref().and_then(|s| s.get(printed_out..)) { print!("{out}"); }
+        if let Some(err) = f.stderr.as_deref().and_then(|s| s.get(printed_err..)) { eprint!("{err}"); }
+        if let Some(ref e) = f.error {
+            eprintln!("{e}");
+            process::exit(1);
         }
+        exit_with_process_status(f, timeout);
+    }
+
+    if let Some(ref e) = result.error {
+        eprintln!("{e}");
+        process::exit(1);
     }
 
+    Ok(())
+}
+
+/// How the command went on one agent.
+struct AgentRun {
+    agtuuid: String,
+    status:  &'static str,
+    exit:    String,
+    elapsed: Option<f64>,
+    stdout:  String,
+    stderr:  String,
+}
+
+impl AgentRun {
+    fn new(agtuuid: &str, ticket: Option<&ControlFormTicket>) -> Self {
+        let mut run = AgentRun {
+            agtuuid: agtuuid.to_string(),
+            status:  "no response",
+            exit:    "-".to_string(),
+            elapsed: None,
+            stdout:  String::new(),
+            stderr:  String::new(),
+        };
+        let Some(ticket) = ticket.filter(|t| t.service_time.is_some()) else {
+            return run;
+        };
+        run.elapsed = ticket.service_time.map(|s| s - ticket.create_time);
+        if let Some(ref e) = ticket.error {
+            run.status = "error";
+            run.stderr = e.clone();
+            return run;
+        }
+        let ControlForm::SyncProcess(ref f) = ticket.form else {
+            run.status = "error";
+            run.stderr = "unexpected response form".to_string();
+            return run;
+        };
+        run.elapsed = f.elapsed_time.or(run.elapsed);
+        run.stdout  = f.stdout.clone().unwrap_or_default();
+        run.stderr  = f.stderr.clone().unwrap_or_default();
+        if let Some(status) = f.status { run.exit = status.to_string(); }
+        if let Some(signal) = f.signal { run.exit = format!("sig{signal}"); }
+        run.status = match (&f.error, f.timed_out, f.signal, f.status) {
+            (Some(e), _, _, _) => {
+                run.stderr = e.clone();
+                "error"
+            }
+            (_, true, _, _)     => "timeout",
+            (_, _, Some(_), _)  => "killed",
+            (_, _, _, Some(0))  => "ok",
+            _                   => "failed",
+        };
+        run
+    }
+
+    /// The first non-empty line of output, or of the error.
+    fn first_line(&self) -> &str {
+        self.stdout
+            .lines()
+            .chain(self.stderr.lines())
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or_default()
+    }
+}
+
+/// Run the command on several agents and print a summary table.
+///
+/// With `parallel` unlimited, one multicast ticket runs it everywhere at
+/// once.  Otherwise each agent gets a ticket of its own and at most
+/// `parallel` are in flight, the next starting as soon as any finishes.
+///
+/// Exits non-zero unless the command succeeded on every agent.
+async fn cmd_run_many(
+    client: Arc<AgentClient>,
+    targets: RunTargets,
+    form: SyncProcess,
+    timeout: u64,
+) -> Result<()> {
+    let mut agents = Vec::new();
+    for dst in &targets.agtuuids {
+        agents.extend(resolve_agents(&client, dst).await?);
+    }
+    if targets.all || targets.matching.is_some() {
+        agents.extend(select_agents(&client, "*").await?.into_iter().filter(|a| {
+            targets.all || targets.matching.as_ref().is_some_and(|re| re.is_match(a))
+        }));
+    }
+    let mut seen = BTreeSet::new();
+    agents.retain(|a| seen.insert(a.clone()));
+    if agents.is_empty() {
+        eprintln!("no known agents match");
+        process::exit(1);
+    }
+
+    let runs = if targets.parallel == 0 {
+        let ticket = client
+            .send_ticket(ControlFormTicket {
+                dsts: agents.clone(),
+                form: ControlForm::SyncProcess(form.clone()),
+                ..ControlFormTicket::default()
+            })
+            .await?;
+        let ticket = poll_ticket(Arc::clone(&client), ticket, timeout * 2).await;
+        if let Some(ref e) = ticket.error {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        agents.iter().map(|dst| AgentRun::new(dst, ticket.results.iter().find(|t| t.dst == *dst))).collect()
+    } else {
+        run_windowed(&client, &agents, &form, targets.parallel, timeout).await?
+    };
+
+    print_run_summary(&runs);
+    if targets.output {
+        print_run_outputs(&runs);
+    }
+
+    if runs.iter().any(|r| r.status != "ok") {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Run the form on each agent in a ticket of its own, keeping at most
+/// `window` tickets in flight, and return the runs in `agents` order.
+async fn run_windowed(
+    client: &Arc<AgentClient>,
+    agents: &[String],
+    form: &SyncProcess,
+    window: usize,
+    timeout: u64,
+) -> Result<Vec<AgentRun>> {
+    let mut pending = agents.iter();
+    let mut in_flight = tokio::task::JoinSet::new();
+    let mut tickets = BTreeMap::new();
+    loop {
+        while in_flight.len() < window {
+            let Some(dst) = pending.next() else { break };
+            let client = Arc::clone(client);
+            let ticket = ControlFormTicket {
+                dst:  dst.clone(),
+                form: ControlForm::SyncProcess(form.clone()),
+                ..ControlFormTicket::default()
+            };
+            in_flight.spawn(async move {
+                let ticket = client.send_ticket(ticket).await?;
+                anyhow::Ok(poll_ticket(client, ticket, timeout * 2).await)
+            });
+        }
+        match in_flight.join_next().await {
+            Some(done) => {
+                let ticket = done??;
+                tickets.insert(ticket.dst.clone(), ticket);
+            }
+            None => break,
+        }
+    }
+    Ok(agents.iter().map(|dst| AgentRun::new(dst, tickets.get(dst))).collect())
+}
+
+fn print_run_summary(runs: &[AgentRun]) {
+    let width = runs.iter().map(|r| r.agtuuid.len()).max().unwrap_or_default().max(5);
+    println!(
+        "{:.<width$} {:.<11} {:.<5} {:.<9} Output",
+        "Agent", "Status", "Exit", "Elapsed"
+    );
+    for run in runs {
+        let elapsed = run.elapsed.map(|e| format!("{e:.3}s")).unwrap_or_else(|| "-".to_string());
+        let line: String = run.first_line().chars().take(60).collect();
+        println!(
+            "{:<width$} {:<11} {:<5} {:<9} {}",
+            run.agtuuid, run.status, run.exit, elapsed, line
+        );
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for run in runs {
+        *counts.entry(run.status).or_default() += 1;
+    }
+    let counts: Vec<String> = counts.iter().map(|(status, n)| format!("{n} {status}")).collect();
+    let plural = if runs.len() == 1 { "" } else { "s" };
+    println!("{} agent{plural}: {}", runs.len(), counts.join(", "));
+}
+
+/// Print each distinct result once, headed by the agents that produced it.
+fn print_run_outputs(runs: &[AgentRun]) {
+    let mut groups: Vec<(&AgentRun, Vec<&str>)> = Vec::new();
+    for run in runs {
+        let same = |r: &&AgentRun| {
+            r.status == run.status && r.exit == run.exit && r.stdout == run.stdout && r.stderr == run.stderr
+        };
+        match groups.iter_mut().find(|(r, _)| same(r)) {
+            Some((_, agents)) => agents.push(&run.agtuuid),
+            None => groups.push((run, vec![&run.agtuuid])),
+        }
+    }
+    for (run, agents) in groups {
+        println!();
+        println!("== {} ({}, exit {})", agents.join(", "), run.status, run.exit);
+        if !run.stdout.is_empty() { println!("{}", run.stdout.trim_end_matches('\n')); }
+        if !run.stderr.is_empty() {
+            println!("-- stderr");
+            println!("{}", run.stderr.trim_end_matches('\n'));
+        }
+    }
+}
+
+/// Start the command as a background process and print its handle.
+async fn cmd_run_detached(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    form: SyncProcess,
+    timeout: u64,
+) -> Result<()> {
+    let ticket = client
+        .send_ticket(ControlFormTicket {
+            dst: agtuuid,
+            form: ControlForm::StartProcess(StartProcess {
+                command: form.command,
+                cwd: form.cwd,
+                env: form.env,
+                clear_env: form.clear_env,
+                b64stdin: form.b64stdin,
+                run_as_user: form.run_as_user,
+                ..Default::default()
+            }),
+            ..ControlFormTicket::default()
+        })
+        .await?;
+
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
     if let Some(ref e) = ticket.error {
         eprintln!("{e}");
         process::exit(1);
     }
 
+    match ticket.form {
+        ControlForm::StartProcess(f) => {
+            if let Some(e) = f.error {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+            match f.prcuuid {
+                Some(prcuuid) => println!("{prcuuid}"),
+                None => {
+                    eprintln!("Start ticket never serviced!");
+                    process::exit(1);
+                }
+            }
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
     Ok(())
 }
@@ -6,10 +6,24 @@ use chrono::{SecondsFormat, TimeZone, Utc};
 
 use crate::{
     executor::agent::AgentClient,
-    models::control::{ControlForm, ControlFormTicket, GetConfig, GetPeers, GetRoutes},
+    models::control::{ControlForm, ControlFormTicket, GetConfig, GetPeers, GetRoutes, GetStats},
 };
 
-use super::poll_ticket;
+use super::{agent_label, poll_ticket};
+
+fn format_time(time: f64) -> String {
+    let secs  = time as i64;
+    let nanos = ((time - secs as f64) * 1_000_000_000.0) as u32;
+    Utc.timestamp_opt(secs, nanos)
+        .single()
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_else(|| time.to_string())
+}
+
+fn format_uptime(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}d {:02}h {:02}m {:02}s", secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60)
+}
 
 pub async fn cmd_stat(
     client: Arc<AgentClient>,
@@ -32,18 +46,25 @@ pub async fn cmd_stat(
         form: ControlForm::GetRoutes(GetRoutes::default()),
         ..ControlFormTicket::default()
     };
+    let stats_ticket = ControlFormTicket {
+        dst: agtuuid.clone(),
+        form: ControlForm::GetStats(GetStats::default()),
+        ..ControlFormTicket::default()
+    };
 
-    // Send all three initial tickets concurrently
-    let (config_res, peers_res, routes_res) = tokio::join!(
+    // Send all four initial tickets concurrently
+    let (config_res, peers_res, routes_res, stats_res) = tokio::join!(
         client.send_ticket(config_ticket),
         client.send_ticket(peers_ticket),
         client.send_ticket(routes_ticket),
+        client.send_ticket(stats_ticket),
     );
     let it = Instant::now();
 
     let config_ticket  = config_res?;
     let peers_ticket   = peers_res?;
     let routes_ticket  = routes_res?;
+    let stats_ticket   = stats_res?;
 
     let config_ticket  = poll_ticket(Arc::clone(&client), config_ticket, timeout).await;
     let et = config_ticket
@@ -53,8 +74,9 @@ pub async fn cmd_stat(
 
     let peers_ticket   = poll_ticket(Arc::clone(&client), peers_ticket, timeout).await;
     let routes_ticket  = poll_ticket(Arc::clone(&client), routes_ticket, timeout).await;
+    let stats_ticket   = poll_ticket(Arc::clone(&client), stats_ticket, timeout).await;
 
-    for ticket in [&config_ticket, &peers_ticket, &routes_ticket] {
+    for ticket in [&config_ticket, &peers_ticket, &routes_ticket, &stats_ticket] {
         if let Some(ref e) = ticket.error { eprintln!("{e}"); }
     }
 
@@ -71,6 +93,11 @@ pub async fn cmd_stat(
         _ => vec![],
     };
     routes.sort_by(|a, b| a.agtuuid.cmp(&b.agtuuid));
+    // Agents without GetStats (such as Python agents) answer with an error.
+    let stats = match &stats_ticket.form {
+        ControlForm::GetStats(f) if f.error.is_none() && stats_ticket.service_time.is_some() => Some(f.clone()),
+        _ => None,
+    };
 
     let mut hops = config_ticket.hops.clone();
     hops.sort_by(|a, b| a.hop_time.partial_cmp(&b.hop_time).unwrap_or(std::cmp::Ordering::Equal));
@@ -84,6 +111,40 @@ pub async fn cmd_stat(
     println!("Elapsed Time");
     println!("   {et:.3} seconds");
 
+    println!();
+    println!("Runtime");
+    match &stats {
+        Some(stats) => {
+            let time = |t: Option<f64>| t.map(format_time).unwrap_or_else(|| "(never)".to_string());
+            println!("   {:.<36} {}", "Version", stats.version.as_deref().unwrap_or("(unknown)"));
+            println!("   {:.<36} {}", "Uptime", stats.uptime_secs.map(format_uptime).unwrap_or_default());
+            println!("   {:.<36} {}", "Tickets Open", stats.tickets_open.unwrap_or_default());
+            println!("   {:.<36} {}", "Tickets Serviced", stats.tickets_serviced.unwrap_or_default());
+            println!("   {:.<36} {}", "Traces Stored", stats.traces.unwrap_or_default());
+            println!("   {:.<36} {}", "Last Advertisement Sent", time(stats.last_advertisement_sent));
+            println!("   {:.<36} {}", "Last Advertisement Received", time(stats.last_advertisement_received));
+        }
+        None => println!("   (No runtime statistics received)"),
+    }
+
+    if let Some(stats) = &stats {
+        println!();
+        println!("Message Queue");
+        if stats.queued_messages.is_empty() {
+            println!("   (empty)");
+        }
+        for (dest, count) in &stats.queued_messages {
+            let dest = if dest.is_empty() { "(no destination)" } else { dest };
+            println!("   {dest:.<36} {count}");
+        }
+
+        println!();
+        println!("Collections");
+        for (name, count) in &stats.collections {
+            println!("   {name:.<36} {count}");
+        }
+    }
+
     println!();
     println!("Configuration");
     if let Some(cfg) = config {
@@ -103,10 +164,23 @@ pub async fn cmd_stat(
     for peer in &peers {
         println!(
             "   {:.<36} Polling: {:<5} URL: {}",
-            peer.agtuuid.as_deref().unwrap_or("(none)"),
+            agent_label(peer.agtuuid.as_deref().unwrap_or("(none)"), peer.name.as_deref()),
             peer.polling,
             peer.url.as_deref().unwrap_or("(none)"),
         );
+        let peer_stats = stats
+            .iter()
+            .flat_map(|s| &s.peers)
+            .find(|p| peer.agtuuid.as_deref() == Some(p.agtuuid.as_str()));
+        if let Some(p) = peer_stats {
+            println!(
+                "   {:<36} Last Seen: {}  Forwarded: {}  Failed: {}",
+                "",
+                p.last_seen.map(format_time).unwrap_or_else(|| "(never)".to_string()),
+                p.forward_successes,
+                p.forward_failures,
+            );
+        }
     }
 
     println!();
@@ -114,20 +188,14 @@ pub async fn cmd_stat(
     for route in &routes {
         println!(
             "   {:.<36} -> {:.<36} (weight: {})",
-            route.agtuuid, route.gtwuuid, route.weight,
+            agent_label(&route.agtuuid, route.name.as_deref()), route.gtwuuid, route.weight,
         );
     }
 
     println!();
     println!("Network Hops");
     for (idx, hop) in hops.iter().enumerate() {
-        let secs  = hop.hop_time as i64;
-        let nanos = ((hop.hop_time - secs as f64) * 1_000_000_000.0) as u32;
-        let time_str = Utc
-            .timestamp_opt(secs, nanos)
-            .single()
-            .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Millis, true))
-            .unwrap_or_else(|| hop.hop_time.to_string());
+        let time_str = format_time(hop.hop_time);
         println!(
             "   [{: <2}] {:.<36} {:.<20} @ {}",
             idx + 1,
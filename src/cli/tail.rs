@@ -0,0 +1,178 @@
+use std::io::Write;
+use std::process;
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::time::{Duration, Instant};
+
+use crate::{
+    executor::agent::AgentClient,
+    models::control::{CancelTicket, CloseTicket, ControlForm, ControlFormTicket, TailFile},
+};
+
+use super::{poll_ticket, ticket_status, wait_for_tickets};
+
+/// How long each followed `TailFile` ticket streams before it is reissued.
+const FOLLOW_WINDOW: u64 = 30;
+
+pub async fn cmd_tail(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    path: String,
+    lines: Option<u64>,
+    bytes: Option<u64>,
+    follow: bool,
+    timeout: u64,
+) -> Result<()> {
+    let form = TailFile { path, lines, bytes, ..Default::default() };
+    if follow {
+        return cmd_tail_follow(client, agtuuid, form, timeout).await;
+    }
+
+    let ticket = client
+        .send_ticket(ControlFormTicket {
+            dst: agtuuid,
+            form: ControlForm::TailFile(form),
+            ..ControlFormTicket::default()
+        })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("Tail ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    match ticket.form {
+        ControlForm::TailFile(f) => {
+            if let Some(e) = f.error {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+            print!("{}", f.content.unwrap_or_default());
+        }
+        _ => {
+            eprintln!("unexpected response form");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Stream text appended to the file until interrupted.
+///
+/// Each ticket follows the file for [`FOLLOW_WINDOW`] seconds; the next one
+/// picks up at the offset the last one reached.  Ctrl-C cancels the ticket
+/// in flight and exits once its final output has been printed.
+async fn cmd_tail_follow(
+    client: Arc<AgentClient>,
+    agtuuid: String,
+    mut form: TailFile,
+    timeout: u64,
+) -> Result<()> {
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut interrupted = false;
+
+    loop {
+        let ticket = client
+            .send_ticket(ControlFormTicket {
+                dst: agtuuid.clone(),
+                form: ControlForm::TailFile(TailFile {
+                    follow:  true,
+                    timeout: FOLLOW_WINDOW,
+                    ..form.clone()
+                }),
+                ..ControlFormTicket::default()
+            })
+            .await?;
+
+        let mut read = ticket.clone();
+        read.form_type = "read_ticket".to_string();
+
+        let mut deadline = Instant::now() + Duration::from_secs(FOLLOW_WINDOW + timeout);
+        let mut result = ticket.clone();
+        let mut next_seq = 0;
+        while Instant::now() < deadline {
+            result = match client.send_ticket(read.clone()).await {
+                Ok(t)  => t,
+                Err(e) => { eprintln!("read ticket error: {e}"); break; }
+            };
+
+            result.progress.sort_by_key(|p| p.seq);
+            for p in &result.progress {
+                if p.seq < next_seq { continue; }
+                if p.seq > next_seq { break; }
+                if let Some(ref out) = p.stdout { print!("{out}"); }
+                if let Some(ref err) = p.stderr { eprint!("{err}"); }
+                next_seq += 1;
+            }
+            let _ = std::io::stdout().flush();
+
+            if result.service_time.is_some() { break; }
+
+            let remaining = deadline.saturating_duration_since(Instant::now()).as_secs().max(1);
+            tokio::select! {
+                _ = wait_for_tickets(&client, vec![ticket_status(&result)], remaining, |_| false) => {}
+                _ = &mut ctrl_c, if !interrupted => {
+                    interrupted = true;
+                    deadline = Instant::now() + Duration::from_secs(timeout);
+                    let cancel = ControlForm::CancelTicket(CancelTicket {
+                        tckuuid: ticket.tckuuid.clone(),
+                        ..Default::default()
+                    });
+                    if let Err(e) = client.send_control_form(cancel).await {
+                        eprintln!("cancel ticket error: {e}");
+                    }
+                }
+            }
+        }
+
+        let close = ControlForm::CloseTicket(CloseTicket {
+            tckuuid: ticket.tckuuid.clone(),
+            ..Default::default()
+        });
+        if let Err(e) = client.send_control_form(close).await {
+            eprintln!("close ticket error: {e}");
+        }
+
+        if result.service_time.is_none() {
+            if interrupted {
+                return Ok(());
+            }
+            eprintln!("Tail ticket never serviced!");
+            process::exit(1);
+        }
+        if let Some(ref e) = result.error {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+
+        match result.form {
+            ControlForm::TailFile(f) => {
+                if let Some(e) = f.error {
+                    eprintln!("{e}");
+                    process::exit(1);
+                }
+                if interrupted {
+                    return Ok(());
+                }
+                form = TailFile {
+                    lines:  None,
+                    bytes:  None,
+                    offset: f.next_offset,
+                    ..form
+                };
+            }
+            _ => {
+                eprintln!("unexpected response form");
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::process;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::json;
+
+use crate::{
+    config::config,
+    executor::agent::AgentClient,
+    models::control::{ControlForm, ControlFormTicket, GetTopology},
+    topology::topology_dot,
+};
+
+use super::poll_ticket;
+
+/// How `topology` prints the graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TopologyFormat {
+    Json,
+    Dot,
+}
+
+impl std::str::FromStr for TopologyFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "dot"  => Ok(Self::Dot),
+            _ => Err(format!("unknown format '{s}' (expected json or dot)")),
+        }
+    }
+}
+
+/// Walk the mesh from an agent (this one by default) and print the graph.
+pub async fn cmd_topology(
+    client: Arc<AgentClient>,
+    agtuuid: Option<String>,
+    format: TopologyFormat,
+    walk: f64,
+    timeout: u64,
+) -> Result<()> {
+    let agtuuid = agtuuid.unwrap_or_else(|| config().agtuuid.clone());
+    let form = ControlForm::GetTopology(GetTopology { timeout: Some(walk), ..Default::default() });
+    let ticket = client
+        .send_ticket(ControlFormTicket { dst: agtuuid.clone(), form, ..ControlFormTicket::default() })
+        .await?;
+    let ticket = poll_ticket(Arc::clone(&client), ticket, timeout).await;
+
+    if ticket.service_time.is_none() {
+        eprintln!("Topology ticket never serviced!");
+        process::exit(1);
+    }
+    if let Some(ref e) = ticket.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+    let ControlForm::GetTopology(f) = ticket.form else {
+        eprintln!("unexpected response form");
+        process::exit(1);
+    };
+    if let Some(e) = f.error {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    match format {
+        TopologyFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "agtuuid": agtuuid, "agents": f.agents }))?
+        ),
+        TopologyFormat::Dot => print!("{}", topology_dot(&f.agents)),
+    }
+    Ok(())
+}
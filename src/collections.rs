@@ -7,14 +7,15 @@
 //!
 //! In-memory collections use a `file:?mode=memory` URI so that a single
 //! named connection is created once and reused for the lifetime of the
-//! process.  File-backed collections (peers, kvstore) open their usual
-//! `{name}.sqlite` path.
+//! process.  File-backed collections (peers, kvstore, transfers) open their
+//! usual `{name}.sqlite` path.
 
 use std::sync::OnceLock;
 
 use anyhow::Result;
 
 use crate::dao::collection::Collection;
+use crate::dao::datastore::Datastore;
 use crate::dao::db_path;
 use crate::dao::kvstore::KeyValuePair;
 use crate::models::control::ControlFormTicket;
@@ -36,6 +37,7 @@ static TRACES:   OnceLock<Collection<TicketTraceResponse>> = OnceLock::new();
 static PEERS:    OnceLock<Collection<Peer>>                = OnceLock::new();
 static ROUTES:   OnceLock<Collection<Route>>               = OnceLock::new();
 static KVSTORE:  OnceLock<Collection<KeyValuePair>>        = OnceLock::new();
+static TRANSFERS: OnceLock<Datastore>                      = OnceLock::new();
 
 // ── Collection openers ────────────────────────────────────────────────────────
 
@@ -57,6 +59,7 @@ pub fn open_tickets() -> Result<Collection<ControlFormTicket>> {
             .expect("failed to open tickets collection");
         c.create_attribute("tckuuid",     "/tckuuid").ok();
         c.create_attribute("create_time", "/create_time").ok();
+        c.create_attribute("parent",      "/parent").ok();
         c
     }).clone())
 }
@@ -107,6 +110,20 @@ pub fn open_kvstore() -> Result<Collection<KeyValuePair>> {
     }).clone())
 }
 
+/// Open (or return the cached singleton for) the `transfers` datastore.
+///
+/// Holds the staged data of chunked file transfers; it is file-backed so an
+/// interrupted transfer can resume after an agent restart.
+pub fn open_transfers() -> Result<Datastore> {
+    Ok(TRANSFERS.get_or_init(|| {
+        let c = Collection::new("transfers", Some(db_path("transfers").as_ref()))
+            .expect("failed to open transfers datastore");
+        c.create_attribute("type", "/type").ok();
+        c.create_attribute("update_time", "/update_time").ok();
+        c
+    }).clone())
+}
+
 /// Vacuum all collections to reclaim space from deleted records.
 pub fn vacuum_collections() -> Result<()> {
     open_messages()?.document.vacuum()?;
@@ -115,5 +132,6 @@ pub fn vacuum_collections() -> Result<()> {
     open_peers()?.document.vacuum()?;
     open_routes()?.document.vacuum()?;
     open_kvstore()?.document.vacuum()?;
+    open_transfers()?.document.vacuum()?;
     Ok(())
 }
\ No newline at end of file
@@ -1,13 +1,79 @@
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock, RwLock};
 
-use crate::models::config::Config;
+use anyhow::{anyhow, Result};
 
-static CONFIG: OnceLock<Config> = OnceLock::new();
+use crate::dao::kvstore::KVStore;
+use crate::logger::{set_log_format, set_log_levels};
+use crate::models::config::{validate_config_value, Config, RESTART_REQUIRED_KEYS};
+use crate::models::control::SetConfig;
 
-/// Return a reference to the process-wide [`Config`] singleton.
+static CONFIG: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
+
+fn holder() -> &'static RwLock<Arc<Config>> {
+    CONFIG.get_or_init(|| RwLock::new(Arc::new(Config::load())))
+}
+
+/// Return the process-wide [`Config`].
+///
+/// The first call loads the config from the kvstore; later calls return the
+/// cached value without touching the database until [`reload_config`]
+/// replaces it.  Keep the returned `Arc` only as long as one consistent
+/// snapshot is needed.
+pub fn config() -> Arc<Config> {
+    holder().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Re-read the config from the kvstore and apply the log levels and format.
 ///
-/// The first call loads the config from the kvstore; every subsequent call
-/// returns the cached value without touching the database.
-pub fn config() -> &'static Config {
-    CONFIG.get_or_init(Config::load)
+/// Timeouts and intervals are read from [`config`] each time they are used,
+/// so they follow the new values from here on.  Keys in
+/// [`RESTART_REQUIRED_KEYS`] keep their running values: the server and the
+/// handlers were set up with them, so a stored change waits for a restart.
+pub fn reload_config() -> Arc<Config> {
+    let mut fresh = Config::load();
+    let mut current = holder().write().unwrap_or_else(|e| e.into_inner());
+    fresh.keep_restart_required(&current);
+    let fresh = Arc::new(fresh);
+    *current = Arc::clone(&fresh);
+    drop(current);
+    set_log_levels(&fresh.log_level_app, &fresh.log_level_api);
+    set_log_format(fresh.log_format);
+    fresh
+}
+
+/// Validate and store the values in a [`SetConfig`] form, then reload.
+pub fn set_config(mut form: SetConfig) -> SetConfig {
+    log::debug!("{:?}", form.values.keys().collect::<Vec<_>>());
+    match set_config_inner(&mut form) {
+        Ok(()) => form.error = None,
+        Err(e) => {
+            form.restart_required.clear();
+            form.error = Some(e.to_string());
+        }
+    }
+    form
+}
+
+fn set_config_inner(form: &mut SetConfig) -> Result<()> {
+    if form.values.is_empty() {
+        return Err(anyhow!("no config values given"));
+    }
+    for (key, value) in form.values.iter_mut() {
+        *value = validate_config_value(key, value).map_err(|e| anyhow!(e))?;
+    }
+
+    let store = KVStore::new(None)?;
+    for (key, value) in &form.values {
+        store.commit(key.as_str(), value.clone())?;
+        log::info!("config {key} set to {value}");
+    }
+    form.restart_required = form
+        .values
+        .keys()
+        .filter(|key| RESTART_REQUIRED_KEYS.contains(&key.as_str()))
+        .cloned()
+        .collect();
+
+    reload_config();
+    Ok(())
 }
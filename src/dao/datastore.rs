@@ -73,7 +73,8 @@ fn sequence_type() -> String {
 
 // ── Internal helpers ──────────────────────────────────────────────────────────
 
-type Datastore = Collection<Value>;
+/// Collection holding the chunks and sequences of datastore files.
+pub type Datastore = Collection<Value>;
 
 fn new_chunk(datastore: &Datastore) -> Result<(String, DatastoreChunk)> {
     let oid = Document::get_uuid();
@@ -124,6 +125,15 @@ pub fn delete_sequence(datastore: &Datastore, sequuid: &str) -> Result<()> {
     Ok(())
 }
 
+/// Size of an existing sequence, or `None` if there is no such sequence.
+pub fn sequence_size(datastore: &Datastore, sequuid: &str) -> Result<Option<usize>> {
+    let existing = datastore.find_objuuids(&[("type", "sequence")])?;
+    if !existing.iter().any(|id| id == sequuid) {
+        return Ok(None);
+    }
+    Ok(Some(get_sequence(datastore, sequuid)?.size))
+}
+
 // ── File ──────────────────────────────────────────────────────────────────────
 
 /// A seekable, readable, writable file backed by a datastore collection.
@@ -314,6 +324,16 @@ impl File {
         self.following_write = true;
         Ok(())
     }
+
+    /// Write bytes after the last byte of the file, leaving the position at
+    /// the last byte written.
+    pub fn append(&mut self, raw_buffer: &[u8]) -> Result<()> {
+        if self.sequence.size > 0 {
+            self.seek(self.sequence.size - 1)?;
+            self.following_write = true;
+        }
+        self.write(raw_buffer)
+    }
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -373,6 +393,25 @@ mod tests {
         assert_eq!(sha256::digest(data_in.as_slice()), sha256::digest(data_out.as_slice()));
     }
 
+    #[test]
+    fn test_file_append_reopened() {
+        let mut data_in = vec![0u8; CHUNK_SIZE + 100];
+        rand::thread_rng().fill_bytes(&mut data_in);
+        let ds = make_datastore();
+        let seq_id = Document::get_uuid();
+
+        let (head, tail) = data_in.split_at(CHUNK_SIZE - 10);
+        let mut file = File::new(Some(&seq_id), ds.clone()).unwrap();
+        file.append(head).unwrap();
+        file.close().unwrap();
+
+        let mut file = File::new(Some(&seq_id), ds).unwrap();
+        assert_eq!(file.size(), head.len());
+        file.append(tail).unwrap();
+        file.seek(0).unwrap();
+        assert_eq!(file.read(None).unwrap(), data_in);
+    }
+
     #[test]
     fn test_file_write_read_zero() {
         let data_in: &[u8] = b"";
@@ -131,6 +131,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_commit_and_get_float() {
         let store = make_store();
         store.commit("key", json!(3.14)).unwrap();
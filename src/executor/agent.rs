@@ -9,20 +9,28 @@
 //! Content-Length: <ciphertext byte length>
 //! Nonce:          hex(16-byte AES-EAX nonce)
 //! Tag:            hex(16-byte AES-EAX MAC tag)
+//! Encoding:       zlib | gzip | zstd       [optional]
 //! Body:           AES.encrypt(json_data)   [raw binary ciphertext]
 //! ```
+//! With an `Encoding` header the JSON is compressed before it is encrypted,
+//! and the response comes back compressed the same way.  Without one the
+//! body is plain JSON, as Python agents send and expect.
 //! All messages are AES-256-EAX encrypted using the 32-byte key derived from
 //! `Config::secret_digest`.  `send_network_message` sets `isrc` to the local
 //! agent UUID before encrypting.
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use anyhow::{anyhow, Result};
 use aes::Aes256;
 use eax::Eax;
 use eax::aead::{Aead, AeadCore, KeyInit};
 use rand::rngs::OsRng;
 
+use crate::executor::compression::{compress, decompress};
 use crate::models::config::Config;
-use crate::models::control::{ControlForm, ControlFormTicket};
+use crate::models::control::{ControlForm, ControlFormTicket, Encoding};
 use crate::models::network::NetworkMessage;
 
 type Aes256Eax = Eax<Aes256>;
@@ -85,14 +93,24 @@ pub struct AgentClient {
     pub url: String,
     key: [u8; 32],
     agtuuid: String,
+    /// Compression applied to request bodies; `none` unless set with [`Self::with_encoding`].
+    encoding: Encoding,
+    /// Set once the agent rejects a compressed body; later bodies are sent
+    /// uncompressed.
+    declined: Arc<AtomicBool>,
     client: reqwest::Client,
 }
 
 impl AgentClient {
     /// Create a client using the current node's configured key and agtuuid.
+    ///
+    /// Bodies are compressed with the configured `body_encoding` until the
+    /// agent rejects one, as a Python agent or an older build does, after
+    /// which this client sends them uncompressed.
     pub fn new(url: String) -> Self {
         let config = Config::load();
         Self::with_credentials(url, config.key(), config.agtuuid)
+            .with_encoding(config.body_encoding)
     }
 
     /// Create a client with explicit credentials (useful in tests).
@@ -101,10 +119,19 @@ impl AgentClient {
             url,
             key,
             agtuuid,
+            encoding: Encoding::None,
+            declined: Arc::new(AtomicBool::new(false)),
             client: reqwest::Client::new(),
         }
     }
 
+    /// Compress request bodies with `encoding`.  The receiving agent answers
+    /// in the same encoding.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
     /// Send a control form and receive a typed response.
     ///
     /// Request and response bodies are raw binary AES-256 EAX ciphertext
@@ -118,21 +145,7 @@ impl AgentClient {
         log::debug!("{}", form.form_type());
 
         let plaintext = serde_json::to_vec(&form)?;
-        let (nonce, tag, ct) = encrypt(&self.key, &plaintext)?;
-
-        let response = self
-            .client
-            .post(&self.url)
-            .header("Nonce", hex::encode(nonce))
-            .header("Tag", hex::encode(&tag))
-            .header("Content-Type", "application/binary")
-            .header("Content-Length", ct.len().to_string())
-            .body(ct)
-            .send()
-            .await?;
-
-        response.error_for_status_ref()?;
-        let plain = self.decrypt_response(response).await?;
+        let plain = self.post(&plaintext).await?;
         Ok(serde_json::from_slice(&plain)?)
     }
 
@@ -147,21 +160,7 @@ impl AgentClient {
         log::debug!("{} -> {}", ticket.form_type, ticket.dst);
 
         let plaintext = serde_json::to_vec(&ticket)?;
-        let (nonce, tag, ct) = encrypt(&self.key, &plaintext)?;
-
-        let response = self
-            .client
-            .post(&self.url)
-            .header("Nonce", hex::encode(nonce))
-            .header("Tag", hex::encode(&tag))
-            .header("Content-Type", "application/binary")
-            .header("Content-Length", ct.len().to_string())
-            .body(ct)
-            .send()
-            .await?;
-
-        response.error_for_status_ref()?;
-        let plain = self.decrypt_response(response).await?;
+        let plain = self.post(&plaintext).await?;
         Ok(serde_json::from_slice(&plain)?)
     }
 
@@ -179,26 +178,46 @@ impl AgentClient {
         set_isrc(&mut msg, self.agtuuid.clone());
 
         let plaintext = serde_json::to_vec(&msg)?;
-        let (nonce, tag, ct) = encrypt(&self.key, &plaintext)?;
+        let plain = self.post(&plaintext).await?;
+        Ok(serde_json::from_slice(&plain)?)
+    }
 
-        let response = self
+    // ── Private helpers ───────────────────────────────────────────────────────
+
+    /// Compress, encrypt and send a request body, returning the decrypted
+    /// (and decompressed) response body.
+    ///
+    /// A compressed body the agent rejects is sent again uncompressed.
+    async fn post(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let encoding = if self.declined.load(Ordering::Relaxed) { Encoding::None } else { self.encoding };
+        let mut response = self.send(plaintext, encoding).await?;
+        if encoding != Encoding::None && !response.status().is_success() {
+            log::debug!("{} rejected a {} body; sending uncompressed", self.url, encoding.as_str());
+            self.declined.store(true, Ordering::Relaxed);
+            response = self.send(plaintext, Encoding::None).await?;
+        }
+
+        response.error_for_status_ref()?;
+        self.decrypt_response(response).await
+    }
+
+    async fn send(&self, plaintext: &[u8], encoding: Encoding) -> Result<reqwest::Response> {
+        let body = compress(encoding, plaintext)?;
+        let (nonce, tag, ct) = encrypt(&self.key, &body)?;
+
+        let mut request = self
             .client
             .post(&self.url)
             .header("Nonce", hex::encode(nonce))
             .header("Tag", hex::encode(&tag))
             .header("Content-Type", "application/binary")
-            .header("Content-Length", ct.len().to_string())
-            .body(ct)
-            .send()
-            .await?;
-
-        response.error_for_status_ref()?;
-        let plain = self.decrypt_response(response).await?;
-        Ok(serde_json::from_slice(&plain)?)
+            .header("Content-Length", ct.len().to_string());
+        if encoding != Encoding::None {
+            request = request.header("Encoding", encoding.as_str());
+        }
+        Ok(request.body(ct).send().await?)
     }
 
-    // ── Private helpers ───────────────────────────────────────────────────────
-
     async fn decrypt_response(&self, resp: reqwest::Response) -> Result<Vec<u8>> {
         let nonce_hex = resp
             .headers()
@@ -213,11 +232,21 @@ impl AgentClient {
             .to_str()?
             .to_string();
 
+        let encoding = body_encoding(resp.headers().get("Encoding").map(|v| v.to_str()).transpose()?)?;
+
         let nonce = hex::decode(&nonce_hex)?;
         let tag   = hex::decode(&tag_hex)?;
         let body  = resp.bytes().await?.to_vec();
 
-        decrypt(&self.key, &nonce, &tag, &body)
+        decompress(encoding, &decrypt(&self.key, &nonce, &tag, &body)?)
+    }
+}
+
+/// The [`Encoding`] named by a body's `Encoding` header; no header means `none`.
+pub(crate) fn body_encoding(header: Option<&str>) -> Result<Encoding> {
+    match header {
+        Some(name) => name.parse().map_err(|e: String| anyhow!(e)),
+        None       => Ok(Encoding::None),
     }
 }
 
@@ -233,6 +262,8 @@ fn set_isrc(msg: &mut NetworkMessage, isrc: String) {
         TicketTraceResponse(m) => m.isrc = Some(isrc),
         TicketRequest(m)       => m.isrc = Some(isrc),
         TicketResponse(m)      => m.isrc = Some(isrc),
+        TicketProgress(m)      => m.isrc = Some(isrc),
+        TicketCancel(m)        => m.isrc = Some(isrc),
     }
 }
 
@@ -418,6 +449,113 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_send_control_form_without_encoding_header() {
+        let key = test_key();
+        let (r_nonce, r_tag, r_ct) =
+            make_encrypted_response(&key, EXPECTED_GET_CONFIG_JSON.as_bytes()).await;
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/control")
+            .match_header("Encoding", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("Nonce", &hex::encode(r_nonce))
+            .with_header("Tag", &hex::encode(&r_tag))
+            .with_body(&r_ct)
+            .create_async()
+            .await;
+
+        let client = AgentClient::with_credentials(
+            format!("{}/control", server.url()),
+            key,
+            TEST_AGTUUID.to_string(),
+        );
+
+        let form = ControlForm::GetConfig(GetConfig::default());
+        assert!(client.send_control_form(form).await.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_control_form_compressed() {
+        let key = test_key();
+        let body = compress(Encoding::Zstd, EXPECTED_GET_CONFIG_JSON.as_bytes()).unwrap();
+        let (r_nonce, r_tag, r_ct) = make_encrypted_response(&key, &body).await;
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/control")
+            .match_header("Encoding", "zstd")
+            .with_status(200)
+            .with_header("Nonce", &hex::encode(r_nonce))
+            .with_header("Tag", &hex::encode(&r_tag))
+            .with_header("Encoding", "zstd")
+            .with_body(&r_ct)
+            .create_async()
+            .await;
+
+        let client = AgentClient::with_credentials(
+            format!("{}/control", server.url()),
+            key,
+            TEST_AGTUUID.to_string(),
+        )
+        .with_encoding(Encoding::Zstd);
+
+        let form = ControlForm::GetConfig(GetConfig::default());
+        let result = client.send_control_form(form).await.unwrap();
+        assert!(matches!(result, ControlForm::GetConfig(_)));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_send_control_form_falls_back_to_uncompressed() {
+        let key = test_key();
+        let (r_nonce, r_tag, r_ct) =
+            make_encrypted_response(&key, EXPECTED_GET_CONFIG_JSON.as_bytes()).await;
+
+        let mut server = mockito::Server::new_async().await;
+        let rejected = server
+            .mock("POST", "/control")
+            .match_header("Encoding", "zstd")
+            .with_status(400)
+            .expect(1)
+            .create_async()
+            .await;
+        let plain = server
+            .mock("POST", "/control")
+            .match_header("Encoding", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("Nonce", &hex::encode(r_nonce))
+            .with_header("Tag", &hex::encode(&r_tag))
+            .with_body(&r_ct)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = AgentClient::with_credentials(
+            format!("{}/control", server.url()),
+            key,
+            TEST_AGTUUID.to_string(),
+        )
+        .with_encoding(Encoding::Zstd);
+
+        // The second form goes uncompressed without being rejected first.
+        for _ in 0..2 {
+            let result = client.send_control_form(ControlForm::GetConfig(GetConfig::default())).await;
+            assert!(matches!(result.unwrap(), ControlForm::GetConfig(_)));
+        }
+        rejected.assert_async().await;
+        plain.assert_async().await;
+    }
+
+    #[test]
+    fn test_body_encoding_header() {
+        assert_eq!(body_encoding(None).unwrap(), Encoding::None);
+        assert_eq!(body_encoding(Some("gzip")).unwrap(), Encoding::Gzip);
+        assert!(body_encoding(Some("brotli")).is_err());
+    }
+
     // ── send_network_message integration tests ────────────────────────────────
 
     #[tokio::test]
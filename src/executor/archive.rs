@@ -0,0 +1,399 @@
+//! Directory archives for recursive transfers.
+//!
+//! `put -r` moves a directory as one gzipped tar stream instead of a ticket
+//! per file: the source agent packs it with `PackArchive`, the archive travels
+//! with the chunked transfer forms, and the destination agent unpacks it with
+//! `UnpackArchive`.  Entries keep their relative paths, permission bits and
+//! mtimes; symlinks are stored as links rather than followed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use filetime::FileTime;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tar::{Archive, Builder, EntryType};
+
+use crate::dao::Document;
+use crate::models::control::{ArchiveEntry, DiscardArchive, PackArchive, UnpackArchive};
+
+const ARCHIVE_PREFIX: &str = "stembot-";
+const ARCHIVE_SUFFIX: &str = ".tar.gz";
+
+/// A new archive path in the temp directory, where `PackArchive` writes.
+pub fn temp_archive_path() -> PathBuf {
+    std::env::temp_dir().join(format!("{ARCHIVE_PREFIX}{}{ARCHIVE_SUFFIX}", Document::get_uuid()))
+}
+
+/// A new hidden archive path beside the directory `dst`, where a transferred
+/// archive waits to be unpacked into it.
+pub fn sibling_archive_path(dst: &Path) -> Result<PathBuf> {
+    if dst.file_name().is_none() {
+        return Err(anyhow!("{} is not a directory path", dst.display()));
+    }
+    Ok(dst.with_file_name(format!(".{ARCHIVE_PREFIX}{}{ARCHIVE_SUFFIX}", Document::get_uuid())))
+}
+
+/// Whether `archive` is a path this module hands out: one from
+/// [`temp_archive_path`], or, when unpacking into `dst`, one from
+/// [`sibling_archive_path`] for it.  Only these are ever deleted.
+fn is_staged_archive(archive: &Path, dst: Option<&Path>) -> bool {
+    let name = archive.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    if !name.ends_with(ARCHIVE_SUFFIX) {
+        return false;
+    }
+    let temp_dir = std::env::temp_dir();
+    if name.starts_with(ARCHIVE_PREFIX) && archive.parent() == Some(temp_dir.as_path()) {
+        return true;
+    }
+    let beside_dst = dst.is_some_and(|d| d.file_name().is_some() && archive.parent() == d.parent());
+    beside_dst && name.starts_with(&format!(".{ARCHIVE_PREFIX}"))
+}
+
+// ── PackArchive ───────────────────────────────────────────────────────────────
+
+/// Pack `form.path` into a new archive in the temp directory.
+///
+/// Entries that cannot be read are recorded with an error and left out.
+pub fn pack_archive(mut form: PackArchive) -> PackArchive {
+    log::debug!("{}", form.path);
+    let archive = temp_archive_path();
+    match pack_inner(Path::new(&form.path), &archive) {
+        Ok(entries) => {
+            form.size    = fs::metadata(&archive).ok().map(|m| m.len());
+            form.archive = Some(archive.to_string_lossy().into_owned());
+            form.entries = entries;
+            form.error   = None;
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&archive);
+            form.archive = None;
+            form.size    = None;
+            form.entries = Vec::new();
+            form.error   = Some(e.to_string());
+        }
+    }
+    form
+}
+
+fn pack_inner(root: &Path, archive: &Path) -> Result<Vec<ArchiveEntry>> {
+    if !fs::metadata(root)?.is_dir() {
+        return Err(anyhow!("{} is not a directory", root.display()));
+    }
+    let mut builder = Builder::new(GzEncoder::new(fs::File::create(archive)?, Compression::default()));
+    builder.follow_symlinks(false);
+
+    let mut entries = Vec::new();
+    pack_dir(&mut builder, root, Path::new(""), &mut entries)?;
+    builder.into_inner()?.finish()?.sync_all()?;
+    Ok(entries)
+}
+
+/// Append the contents of `dir` in name order under `prefix`.
+fn pack_dir(
+    builder: &mut Builder<GzEncoder<fs::File>>,
+    dir: &Path,
+    prefix: &Path,
+    entries: &mut Vec<ArchiveEntry>,
+) -> Result<()> {
+    let mut children: Vec<_> = fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+    children.sort_by_key(|e| e.file_name());
+
+    for child in children {
+        let name = prefix.join(child.file_name());
+        let mut entry = ArchiveEntry {
+            name: name.to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let meta = match fs::symlink_metadata(child.path()) {
+            Ok(meta) => meta,
+            Err(e) => {
+                entry.error = Some(e.to_string());
+                entries.push(entry);
+                continue;
+            }
+        };
+        let file_type = meta.file_type();
+        entry.kind = kind_name(file_type.is_symlink(), file_type.is_dir(), file_type.is_file());
+        if file_type.is_file() {
+            entry.size = meta.len();
+        }
+
+        entry.error = builder.append_path_with_name(child.path(), &name).err().map(|e| e.to_string());
+        let descend = file_type.is_dir() && entry.error.is_none();
+        entries.push(entry);
+
+        if descend {
+            if let Err(e) = pack_dir(builder, &child.path(), &name, entries) {
+                let index = entries.iter().rposition(|en| Path::new(&en.name) == name);
+                if let Some(index) = index {
+                    entries[index].error = Some(e.to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn kind_name(symlink: bool, dir: bool, file: bool) -> String {
+    if symlink {
+        "symlink"
+    } else if dir {
+        "directory"
+    } else if file {
+        "file"
+    } else {
+        "other"
+    }
+    .to_string()
+}
+
+// ── UnpackArchive ─────────────────────────────────────────────────────────────
+
+/// Unpack `form.archive` into `form.path`, then delete the archive.
+pub fn unpack_archive(mut form: UnpackArchive) -> UnpackArchive {
+    log::debug!("{} -> {}", form.archive, form.path);
+    let archive = Path::new(&form.archive);
+    match unpack_inner(archive, Path::new(&form.path)) {
+        Ok(entries) => {
+            form.entries = entries;
+            form.error   = None;
+            if is_staged_archive(archive, Some(Path::new(&form.path))) {
+                let _ = fs::remove_file(archive);
+            }
+        }
+        Err(e) => form.error = Some(e.to_string()),
+    }
+    form
+}
+
+fn unpack_inner(archive: &Path, dst: &Path) -> Result<Vec<ArchiveEntry>> {
+    fs::create_dir_all(dst)?;
+    let mut archive = Archive::new(GzDecoder::new(fs::File::open(archive)?));
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    archive.set_overwrite(true);
+
+    let mut entries = Vec::new();
+    let mut dirs = Vec::new();
+    for item in archive.entries()? {
+        let mut item = item?;
+        let header = item.header();
+        let entry_type = header.entry_type();
+        let name = item.path()?.to_string_lossy().trim_end_matches('/').to_string();
+        let mut entry = ArchiveEntry {
+            kind: kind_name(entry_type.is_symlink(), entry_type.is_dir(), entry_type.is_file()),
+            size: if entry_type == EntryType::Regular { header.size().unwrap_or(0) } else { 0 },
+            name,
+            error: None,
+        };
+
+        // Directory modes and mtimes are applied once their contents are in
+        // place; a read-only directory would otherwise refuse its own files.
+        if entry_type.is_dir() {
+            dirs.push((entries.len(), header.mode().ok(), header.mtime().ok()));
+            item.set_preserve_permissions(false);
+            item.set_preserve_mtime(false);
+        }
+
+        entry.error = match item.unpack_in(dst) {
+            Ok(true) => None,
+            Ok(false) => Some("path escapes the destination directory".to_string()),
+            Err(e) => Some(e.to_string()),
+        };
+        entries.push(entry);
+    }
+
+    for (index, mode, mtime) in dirs.into_iter().rev() {
+        if entries[index].error.is_some() {
+            continue;
+        }
+        let path: PathBuf = dst.join(&entries[index].name);
+        if let Err(e) = restore_dir(&path, mode, mtime) {
+            entries[index].error = Some(e.to_string());
+        }
+    }
+    Ok(entries)
+}
+
+fn restore_dir(path: &Path, mode: Option<u32>, mtime: Option<u64>) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    if let Some(mtime) = mtime {
+        filetime::set_file_mtime(path, FileTime::from_unix_time(mtime as i64, 0))?;
+    }
+    Ok(())
+}
+
+// ── DiscardArchive ────────────────────────────────────────────────────────────
+
+/// Delete an archive made by [`pack_archive`].
+///
+/// Anything that is not a `stembot-*.tar.gz` file directly in the temp
+/// directory is refused.
+pub fn discard_archive(mut form: DiscardArchive) -> DiscardArchive {
+    log::debug!("{}", form.archive);
+    let path = Path::new(&form.archive);
+    form.error = if !is_staged_archive(path, None) {
+        Some(format!("{} is not an archive made by pack_archive", form.archive))
+    } else {
+        fs::remove_file(path).err().map(|e| e.to_string())
+    };
+    form
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    fn make_tree(root: &Path) {
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("ro")).unwrap();
+        fs::write(root.join("bin/app"), b"#!/bin/sh\necho hi\n").unwrap();
+        fs::write(root.join("ro/data.txt"), b"data").unwrap();
+        fs::set_permissions(root.join("bin/app"), fs::Permissions::from_mode(0o750)).unwrap();
+        std::os::unix::fs::symlink("bin/app", root.join("app")).unwrap();
+        filetime::set_file_mtime(root.join("ro/data.txt"), FileTime::from_unix_time(1_000_000, 0)).unwrap();
+        fs::set_permissions(root.join("ro"), fs::Permissions::from_mode(0o555)).unwrap();
+        filetime::set_file_mtime(root.join("ro"), FileTime::from_unix_time(2_000_000, 0)).unwrap();
+    }
+
+    fn mtime(path: &Path) -> i64 {
+        FileTime::from_last_modification_time(&fs::symlink_metadata(path).unwrap()).unix_seconds()
+    }
+
+    fn mode(path: &Path) -> u32 {
+        fs::symlink_metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn test_pack_and_unpack_preserve_tree() {
+        let src = tempfile::tempdir().unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        make_tree(src.path());
+
+        let packed = pack_archive(PackArchive {
+            path: src.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(packed.error.is_none(), "{:?}", packed.error);
+        let names: Vec<_> = packed.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["app", "bin", "bin/app", "ro", "ro/data.txt"]);
+        assert_eq!(packed.entries[0].kind, "symlink");
+        assert_eq!(packed.entries[2].size, 18);
+
+        let archive = packed.archive.unwrap();
+        let target = dst.path().join("copy");
+        let unpacked = unpack_archive(UnpackArchive {
+            archive: archive.clone(),
+            path:    target.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(unpacked.error.is_none(), "{:?}", unpacked.error);
+        assert!(unpacked.entries.iter().all(|e| e.error.is_none()), "{:?}", unpacked.entries);
+        assert_eq!(unpacked.entries.len(), 5);
+        assert!(!Path::new(&archive).exists());
+
+        assert_eq!(fs::read(target.join("ro/data.txt")).unwrap(), b"data");
+        assert_eq!(fs::read_link(target.join("app")).unwrap(), Path::new("bin/app"));
+        assert_eq!(mode(&target.join("bin/app")), 0o750);
+        assert_eq!(mode(&target.join("ro")), 0o555);
+        assert_eq!(mtime(&target.join("ro/data.txt")), 1_000_000);
+        assert_eq!(mtime(&target.join("ro")), 2_000_000);
+
+        fs::set_permissions(src.path().join("ro"), fs::Permissions::from_mode(0o755)).unwrap();
+        fs::set_permissions(target.join("ro"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_pack_archive_rejects_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("plain");
+        fs::write(&file, b"x").unwrap();
+        let packed = pack_archive(PackArchive {
+            path: file.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(packed.error.unwrap().contains("not a directory"));
+        assert!(packed.archive.is_none());
+    }
+
+    #[test]
+    fn test_discard_archive_only_removes_packed_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let other = dir.path().join("stembot-x.tar.gz");
+        fs::write(&other, b"x").unwrap();
+        let form = discard_archive(DiscardArchive {
+            archive: other.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(form.error.is_some());
+        assert!(other.exists());
+
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("f"), b"x").unwrap();
+        let archive = pack_archive(PackArchive {
+            path: src.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        })
+        .archive
+        .unwrap();
+        let form = discard_archive(DiscardArchive { archive: archive.clone(), ..Default::default() });
+        assert!(form.error.is_none());
+        assert!(!Path::new(&archive).exists());
+    }
+
+    #[test]
+    fn test_unpack_archive_keeps_archive_on_failure_or_foreign_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("copy");
+
+        let broken = temp_archive_path();
+        fs::write(&broken, b"not a tarball").unwrap();
+        let form = unpack_archive(UnpackArchive {
+            archive: broken.to_string_lossy().into_owned(),
+            path:    target.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(form.error.is_some());
+        assert!(broken.exists());
+        fs::remove_file(&broken).unwrap();
+
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("f"), b"x").unwrap();
+        let packed = pack_archive(PackArchive {
+            path: src.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        })
+        .archive
+        .unwrap();
+        let foreign = dir.path().join("keep.tar.gz");
+        fs::rename(&packed, &foreign).unwrap();
+        let form = unpack_archive(UnpackArchive {
+            archive: foreign.to_string_lossy().into_owned(),
+            path:    target.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(form.error.is_none(), "{:?}", form.error);
+        assert!(foreign.exists());
+
+        let sibling = sibling_archive_path(&target).unwrap();
+        fs::rename(&foreign, &sibling).unwrap();
+        let form = unpack_archive(UnpackArchive {
+            archive: sibling.to_string_lossy().into_owned(),
+            path:    target.to_string_lossy().into_owned(),
+            ..Default::default()
+        });
+        assert!(form.error.is_none(), "{:?}", form.error);
+        assert!(!sibling.exists());
+    }
+}
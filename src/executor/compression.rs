@@ -0,0 +1,102 @@
+//! Payload compression.
+//!
+//! File forms carry their data compressed under an [`Encoding`] and then
+//! base64-encoded, and agents may compress whole request bodies before
+//! encrypting them.  `zlib` (via [`zlib_compress`]) is what Python peers
+//! expect; `gzip` and `zstd` are available between Rust agents, and `none`
+//! skips the work for data that is already compressed.
+
+use std::io::{Read, Write};
+
+use anyhow::Result;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use crate::executor::file::{zlib_compress, zlib_decompress};
+use crate::models::control::Encoding;
+
+/// zstd level used for payloads; the library default.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compress `data` under `encoding`.
+pub fn compress(encoding: Encoding, data: &[u8]) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::None => Ok(data.to_vec()),
+        Encoding::Zlib => zlib_compress(data),
+        Encoding::Gzip => {
+            let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+            enc.write_all(data)?;
+            Ok(enc.finish()?)
+        }
+        Encoding::Zstd => Ok(zstd::encode_all(data, ZSTD_LEVEL)?),
+    }
+}
+
+/// Reverse [`compress`].
+pub fn decompress(encoding: Encoding, data: &[u8]) -> Result<Vec<u8>> {
+    match encoding {
+        Encoding::None => Ok(data.to_vec()),
+        Encoding::Zlib => zlib_decompress(data),
+        Encoding::Gzip => {
+            let mut out = Vec::new();
+            GzDecoder::new(data).read_to_end(&mut out)?;
+            Ok(out)
+        }
+        Encoding::Zstd => Ok(zstd::decode_all(data)?),
+    }
+}
+
+/// Compress `data` for a file form, returning the encoding actually used.
+///
+/// Data that `encoding` would not shrink, such as an archive or an image, is
+/// sent as-is under `none`.  `zlib` is always honoured, since a peer asking
+/// for it may not understand anything else.
+pub fn compress_payload(encoding: Encoding, data: &[u8]) -> Result<(Encoding, Vec<u8>)> {
+    let compressed = compress(encoding, data)?;
+    if encoding != Encoding::Zlib && compressed.len() >= data.len() {
+        return Ok((Encoding::None, data.to_vec()));
+    }
+    Ok((encoding, compressed))
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"hello stembot, hello stembot, hello stembot, hello stembot\n";
+
+    #[test]
+    fn test_compress_decompress_roundtrip() {
+        for encoding in [Encoding::None, Encoding::Zlib, Encoding::Gzip, Encoding::Zstd] {
+            let compressed = compress(encoding, TEXT).unwrap();
+            assert_eq!(decompress(encoding, &compressed).unwrap(), TEXT, "{}", encoding.as_str());
+        }
+    }
+
+    #[test]
+    fn test_compress_zlib_matches_legacy() {
+        assert_eq!(compress(Encoding::Zlib, TEXT).unwrap(), zlib_compress(TEXT).unwrap());
+    }
+
+    #[test]
+    fn test_decompress_wrong_encoding() {
+        let compressed = compress(Encoding::Zstd, TEXT).unwrap();
+        assert!(decompress(Encoding::Gzip, &compressed).is_err());
+    }
+
+    #[test]
+    fn test_compress_payload_skips_incompressible() {
+        // Too short for any encoding's framing to pay off.
+        let (encoding, payload) = compress_payload(Encoding::Zstd, b"abc").unwrap();
+        assert_eq!(encoding, Encoding::None);
+        assert_eq!(payload, b"abc");
+
+        let (encoding, _) = compress_payload(Encoding::Zlib, b"abc").unwrap();
+        assert_eq!(encoding, Encoding::Zlib);
+
+        let (encoding, payload) = compress_payload(Encoding::Gzip, TEXT).unwrap();
+        assert_eq!(encoding, Encoding::Gzip);
+        assert!(payload.len() < TEXT.len());
+    }
+}
@@ -1,33 +1,113 @@
-//! File I/O operations with compression and integrity verification.
+//! File I/O operations with compression and integrity verification,
+//! directory listing and file metadata, and file management (delete, move,
+//! mkdir, chmod/chown).
 //!
 //! Mirrors Python's `stembot/executor/file.py`.
 //!
 //! ## Wire format
-//! - Compression: zlib (level 9, raw deflate via `flate2`)
+//! - Compression: zlib (level 9, raw deflate via `flate2`) by default, or
+//!   whichever `encoding` the form names (see [`crate::executor::compression`])
 //! - Encoding:    base64 standard
 //! - Checksum:    MD5 hex digest of the **original** (uncompressed) bytes
 
-use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
 
 use anyhow::{anyhow, Result};
 use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
 use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use regex::Regex;
 
-use crate::models::control::{LoadFile, WriteFile};
+use crate::executor::compression::{compress_payload, decompress};
+use crate::executor::transfer::part_path;
+use crate::models::control::{
+    DeletePath, FileEntry, HashAlgorithm, HashFile, ListDirectory, LoadFile, MakeDirectory,
+    MovePath, SetPermissions, StatFile, WriteFile,
+};
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
-fn md5hex(data: &[u8]) -> String {
+pub(crate) fn md5hex(data: &[u8]) -> String {
     format!("{:x}", md5::compute(data))
 }
 
-fn zlib_compress(data: &[u8]) -> Result<Vec<u8>> {
+/// Incremental digest under any [`HashAlgorithm`].
+pub(crate) enum Hasher {
+    Md5(md5::Context),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub(crate) fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5    => Self::Md5(md5::Context::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(<sha2::Sha256 as sha2::Digest>::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(c)    => c.consume(data),
+            Self::Sha256(h) => sha2::Digest::update(h, data),
+            Self::Blake3(h) => { h.update(data); }
+        }
+    }
+
+    /// Lowercase hex digest.
+    pub(crate) fn finalize(self) -> String {
+        match self {
+            Self::Md5(c)    => format!("{:x}", c.finalize()),
+            Self::Sha256(h) => hex::encode(sha2::Digest::finalize(h)),
+            Self::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+pub(crate) fn digest_hex(algorithm: HashAlgorithm, data: &[u8]) -> String {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// The digest data must match: `hash` under `algorithm` when set, otherwise
+/// the legacy `md5sum`.
+pub(crate) fn expected_digest<'a>(
+    algorithm: HashAlgorithm,
+    hash: &'a Option<String>,
+    md5sum: &'a Option<String>,
+) -> Option<(HashAlgorithm, &'a str)> {
+    match (hash, md5sum) {
+        (Some(hash), _)      => Some((algorithm, hash)),
+        (None, Some(md5sum)) => Some((HashAlgorithm::Md5, md5sum)),
+        (None, None)         => None,
+    }
+}
+
+/// Fail unless `data` matches the expected digest, if there is one.
+pub(crate) fn check_digest(expected: Option<(HashAlgorithm, &str)>, data: &[u8], stage: &str) -> Result<()> {
+    if let Some((algorithm, expected)) = expected {
+        let actual = digest_hex(algorithm, data);
+        if actual != expected {
+            return Err(anyhow!(
+                "{} mismatch{stage}: expected {}, got {}",
+                algorithm.as_str().to_uppercase(), expected, actual
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn zlib_compress(data: &[u8]) -> Result<Vec<u8>> {
     let mut enc = ZlibEncoder::new(Vec::new(), Compression::new(9));
     enc.write_all(data)?;
     Ok(enc.finish()?)
 }
 
-fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+pub(crate) fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
     let mut dec = ZlibDecoder::new(data);
     let mut out = Vec::new();
     dec.read_to_end(&mut out)?;
@@ -43,17 +123,22 @@ pub fn load_file_to_form(mut form: LoadFile) -> LoadFile {
     log::debug!("{}", form.path);
     match std::fs::read(&form.path) {
         Ok(data) => {
+            let hash = digest_hex(form.hash_algorithm, &data);
             form.size   = Some(data.len() as i64);
-            form.md5sum = Some(md5hex(&data));
-            match zlib_compress(&data) {
-                Ok(compressed) => {
-                    form.b64zlib = Some(B64.encode(&compressed));
-                    form.error   = None;
+            form.md5sum = (form.hash_algorithm == HashAlgorithm::Md5).then(|| hash.clone());
+            form.hash   = Some(hash);
+            load_metadata(&mut form);
+            match compress_payload(form.encoding, &data) {
+                Ok((encoding, compressed)) => {
+                    form.b64zlib  = Some(B64.encode(&compressed));
+                    form.encoding = encoding;
+                    form.error    = None;
                 }
                 Err(e) => {
                     form.b64zlib = None;
                     form.size    = None;
                     form.md5sum  = None;
+                    form.hash    = None;
                     form.error   = Some(e.to_string());
                 }
             }
@@ -62,18 +147,86 @@ pub fn load_file_to_form(mut form: LoadFile) -> LoadFile {
             form.b64zlib = None;
             form.size    = None;
             form.md5sum  = None;
+            form.hash    = None;
             form.error   = Some(e.to_string());
         }
     }
     form
 }
 
+/// Mode, ownership and mtime of a file, in the form `LoadFile`, `ReadChunk`,
+/// `WriteFile` and `CommitTransfer` carry them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct FileMetadata {
+    pub mode:  Option<u32>,
+    pub uid:   Option<u32>,
+    pub gid:   Option<u32>,
+    pub mtime: Option<f64>,
+}
+
+/// Read the mode, ownership (Unix only) and mtime of the file at `path`.
+pub(crate) fn file_metadata(path: &Path) -> FileMetadata {
+    let mut metadata = FileMetadata::default();
+    let Ok(meta) = std::fs::metadata(path) else {
+        return metadata;
+    };
+    metadata.mtime = meta.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs_f64());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.mode = Some(meta.mode() & 0o7777);
+        metadata.uid  = Some(meta.uid());
+        metadata.gid  = Some(meta.gid());
+    }
+    metadata
+}
+
+/// Fill in the mode, ownership and mtime of the file a [`LoadFile`] names.
+fn load_metadata(form: &mut LoadFile) {
+    let FileMetadata { mode, uid, gid, mtime } = file_metadata(Path::new(&form.path));
+    form.mode  = mode;
+    form.uid   = uid;
+    form.gid   = gid;
+    form.mtime = mtime;
+}
+
+/// Give `file` the requested metadata, falling back to the mode and owner of
+/// the file at `replaced` it is about to replace.
+pub(crate) fn apply_metadata(file: &std::fs::File, replaced: &Path, metadata: &FileMetadata) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let current = std::fs::metadata(replaced).ok();
+        if let Some(mode) = metadata.mode.or(current.as_ref().map(|m| m.mode() & 0o7777)) {
+            file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+        }
+        if metadata.uid.is_some() || metadata.gid.is_some() {
+            std::os::unix::fs::fchown(file, metadata.uid, metadata.gid)?;
+        } else if let Some(ref m) = current {
+            // Keeping the replaced file's owner needs privilege; without it the
+            // new file keeps ours.
+            let _ = std::os::unix::fs::fchown(file, Some(m.uid()), Some(m.gid()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = replaced;
+
+    if let Some(mtime) = metadata.mtime {
+        let mtime = filetime::FileTime::from_unix_time(mtime.trunc() as i64, (mtime.fract() * 1e9) as u32);
+        filetime::set_file_handle_times(file, None, Some(mtime))?;
+    }
+    Ok(())
+}
+
 /// Extract and decompress file data from a [`LoadFile`] form.
 ///
 /// Mirrors `load_bytes_from_form(form: LoadFile) -> bytes`.
 ///
 /// # Errors
-/// Returns an error if `form.error` is set, if MD5 verification fails, or if
+/// Returns an error if `form.error` is set, if digest verification fails, or if
 /// decompression fails.
 pub fn load_bytes_from_form(form: &LoadFile) -> Result<Vec<u8>> {
     if let Some(ref e) = form.error {
@@ -81,13 +234,8 @@ pub fn load_bytes_from_form(form: &LoadFile) -> Result<Vec<u8>> {
     }
     let b64 = form.b64zlib.as_deref().ok_or_else(|| anyhow!("b64zlib is None"))?;
     let compressed = B64.decode(b64)?;
-    let data = zlib_decompress(&compressed)?;
-    if let Some(ref expected) = form.md5sum {
-        let actual = md5hex(&data);
-        if &actual != expected {
-            return Err(anyhow!("MD5 mismatch: expected {}, got {}", expected, actual));
-        }
-    }
+    let data = decompress(form.encoding, &compressed)?;
+    check_digest(expected_digest(form.hash_algorithm, &form.hash, &form.md5sum), &data, "")?;
     Ok(data)
 }
 
@@ -104,6 +252,7 @@ pub fn load_form_from_bytes(data: &[u8]) -> WriteFile {
         error:   None,
         objuuid: None,
         coluuid: None,
+        ..Default::default()
     }
 }
 
@@ -113,9 +262,10 @@ pub fn load_form_from_bytes(data: &[u8]) -> WriteFile {
 pub fn write_file_from_form(mut form: WriteFile) -> WriteFile {
     log::debug!("{}", form.path);
     match write_inner(&form) {
-        Ok(()) => {
-            form.error   = None;
-            form.b64zlib = "".to_string(); // cleared after write, like Python sets None
+        Ok(backup_path) => {
+            form.backup_path = backup_path;
+            form.error       = None;
+            form.b64zlib     = "".to_string(); // cleared after write, like Python sets None
         }
         Err(e) => {
             form.error = Some(e.to_string());
@@ -124,29 +274,553 @@ pub fn write_file_from_form(mut form: WriteFile) -> WriteFile {
     form
 }
 
-fn write_inner(form: &WriteFile) -> Result<()> {
+/// Write the form's data next to its target, then rename it into place,
+/// returning the path of the backup if one was made.
+fn write_inner(form: &WriteFile) -> Result<Option<String>> {
     let compressed = B64.decode(&form.b64zlib)?;
-    let data = zlib_decompress(&compressed)?;
+    let data = decompress(form.encoding, &compressed)?;
 
     // Verify pre-write checksum
-    if let Some(ref expected) = form.md5sum {
-        let actual = md5hex(&data);
-        if &actual != expected {
-            return Err(anyhow!("MD5 mismatch before write: expected {}, got {}", expected, actual));
-        }
+    let expected = expected_digest(form.hash_algorithm, &form.hash, &form.md5sum);
+    check_digest(expected, &data, " before write")?;
+
+    let path = Path::new(&form.path);
+    let part = part_path(path, &uuid::Uuid::new_v4().to_string())?;
+    let result = write_part(form, path, &part, &data).and_then(|()| {
+        let backup_path = if form.backup && path.exists() {
+            Some(backup(path)?)
+        } else {
+            None
+        };
+        std::fs::rename(&part, path)?;
+        sync_parent(path);
+        Ok(backup_path)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&part);
     }
+    result
+}
 
-    std::fs::write(&form.path, &data)?;
+/// Write `data` to `part` with the form's metadata, falling back to that of
+/// the file at `path` it will replace, and check it on disk.
+fn write_part(form: &WriteFile, path: &Path, part: &Path, data: &[u8]) -> Result<()> {
+    let mut file = std::fs::File::create(part)?;
+    file.write_all(data)?;
+    apply_metadata(&file, path, &FileMetadata {
+        mode:  form.mode,
+        uid:   form.uid,
+        gid:   form.gid,
+        mtime: form.mtime,
+    })?;
+    file.sync_all()?;
+    drop(file);
 
     // Verify post-write checksum
-    if let Some(ref expected) = form.md5sum {
-        let written = std::fs::read(&form.path)?;
-        let actual = md5hex(&written);
-        if &actual != expected {
-            return Err(anyhow!("MD5 mismatch after write: expected {}, got {}", expected, actual));
+    let expected = expected_digest(form.hash_algorithm, &form.hash, &form.md5sum);
+    if expected.is_some() {
+        check_digest(expected, &std::fs::read(part)?, " after write")?;
+    }
+
+    Ok(())
+}
+
+/// Keep the current version of `path` at `{path}.bak`, replacing any older
+/// backup.  A hard link leaves `path` in place until the rename replaces it.
+fn backup(path: &Path) -> Result<String> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = std::path::PathBuf::from(backup);
+
+    match std::fs::remove_file(&backup) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    if std::fs::hard_link(path, &backup).is_err() {
+        std::fs::copy(path, &backup)?;
+    }
+    Ok(backup.to_string_lossy().into_owned())
+}
+
+/// Flush the directory entry of a rename to disk where the platform allows.
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        if let Ok(dir) = std::fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+/// Digest a file into a [`HashFile`] form, reading it in blocks.
+pub fn hash_file(mut form: HashFile) -> HashFile {
+    log::debug!("{}", form.path);
+    match hash_inner(&form) {
+        Ok((hash, size)) => {
+            form.hash  = Some(hash);
+            form.size  = Some(size);
+            form.error = None;
+        }
+        Err(e) => {
+            form.hash  = None;
+            form.size  = None;
+            form.error = Some(e.to_string());
+        }
+    }
+    form
+}
+
+fn hash_inner(form: &HashFile) -> Result<(String, u64)> {
+    let mut file = std::fs::File::open(&form.path)?;
+    let mut hasher = Hasher::new(form.hash_algorithm);
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
         }
+        hasher.update(&buf[..n]);
+        size += n as u64;
     }
+    Ok((hasher.finalize(), size))
+}
+
+// ── Listing and metadata ──────────────────────────────────────────────────────
+
+/// List a directory into a [`ListDirectory`] form.
+pub fn list_directory(mut form: ListDirectory) -> ListDirectory {
+    log::debug!("{}", form.path);
+    match list_inner(&form) {
+        Ok(entries) => {
+            form.entries = entries;
+            form.error   = None;
+        }
+        Err(e) => {
+            form.entries = Vec::new();
+            form.error   = Some(e.to_string());
+        }
+    }
+    form
+}
 
+/// Read the metadata of a single path into a [`StatFile`] form.
+pub fn stat_file(mut form: StatFile) -> StatFile {
+    log::debug!("{}", form.path);
+    let path = Path::new(&form.path);
+    match file_entry(path, entry_name(path), &mut OwnerNames::default()) {
+        Ok(entry) => {
+            form.entry = Some(entry);
+            form.error = None;
+        }
+        Err(e) => {
+            form.entry = None;
+            form.error = Some(e.to_string());
+        }
+    }
+    form
+}
+
+fn list_inner(form: &ListDirectory) -> Result<Vec<FileEntry>> {
+    let root = Path::new(&form.path);
+    let pattern = form.pattern.as_deref().map(glob_regex).transpose()?;
+    let mut names = OwnerNames::default();
+
+    if !std::fs::metadata(root)?.is_dir() {
+        return Ok(vec![file_entry(root, entry_name(root), &mut names)?]);
+    }
+
+    let mut entries = Vec::new();
+    walk(root, Path::new(""), form.depth, pattern.as_ref(), &mut names, &mut entries)?;
+    Ok(entries)
+}
+
+/// Append the entries of `dir` in name order, descending `depth - 1` more
+/// levels.  Symlinked directories are not followed, and subdirectories that
+/// cannot be read are skipped.
+fn walk(
+    dir: &Path,
+    prefix: &Path,
+    depth: u32,
+    pattern: Option<&Regex>,
+    names: &mut OwnerNames,
+    entries: &mut Vec<FileEntry>,
+) -> Result<()> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+    children.sort_by_key(|e| e.file_name());
+
+    for child in children {
+        let file_name = child.file_name();
+        let name = prefix.join(&file_name);
+        let Ok(entry) = file_entry(&child.path(), name.to_string_lossy().into_owned(), names) else {
+            continue;
+        };
+        let descend = depth > 1 && entry.kind == "directory";
+        if pattern.is_none_or(|p| p.is_match(&file_name.to_string_lossy())) {
+            entries.push(entry);
+        }
+        if descend {
+            let _ = walk(&child.path(), &name, depth - 1, pattern, names, entries);
+        }
+    }
+    Ok(())
+}
+
+fn entry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+/// Build the [`FileEntry`] for `path` without following a final symlink.
+fn file_entry(path: &Path, name: String, names: &mut OwnerNames) -> Result<FileEntry> {
+    let meta = std::fs::symlink_metadata(path)?;
+    let file_type = meta.file_type();
+    let kind = if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "other"
+    };
+
+    let mut entry = FileEntry {
+        name,
+        kind:   kind.to_string(),
+        size:   meta.len(),
+        mtime:  meta.modified().ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64()),
+        target: file_type.is_symlink()
+            .then(|| std::fs::read_link(path).ok())
+            .flatten()
+            .map(|t| t.to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        entry.mode  = Some(meta.mode() & 0o7777);
+        entry.owner = Some(names.user(meta.uid()));
+        entry.group = Some(names.group(meta.gid()));
+    }
+    #[cfg(not(unix))]
+    let _ = names;
+
+    Ok(entry)
+}
+
+/// Translate a shell glob (`*`, `?`, `[...]`, `[!...]`) into an anchored regex.
+fn glob_regex(glob: &str) -> Result<Regex> {
+    let mut re = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '[' => {
+                re.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    re.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        re.push('\\');
+                    }
+                    re.push(c);
+                }
+                re.push(']');
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).map_err(|e| anyhow!("invalid pattern {glob}: {e}"))
+}
+
+/// User and group names, looked up once per uid/gid.  Ids without a name are
+/// reported as numbers.
+#[derive(Default)]
+struct OwnerNames {
+    users:  HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+#[cfg(unix)]
+impl OwnerNames {
+    fn user(&mut self, uid: u32) -> String {
+        self.users
+            .entry(uid)
+            .or_insert_with(|| user_name(uid).unwrap_or_else(|| uid.to_string()))
+            .clone()
+    }
+
+    fn group(&mut self, gid: u32) -> String {
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| group_name(gid).unwrap_or_else(|| gid.to_string()))
+            .clone()
+    }
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String> {
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local and `buf.len()` is its real size.
+    let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success `pw_name` points to a NUL-terminated string inside `buf`.
+    Some(unsafe { std::ffi::CStr::from_ptr(pwd.pw_name) }.to_string_lossy().into_owned())
+}
+
+#[cfg(unix)]
+fn group_name(gid: u32) -> Option<String> {
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local and `buf.len()` is its real size.
+    let rc = unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success `gr_name` points to a NUL-terminated string inside `buf`.
+    Some(unsafe { std::ffi::CStr::from_ptr(grp.gr_name) }.to_string_lossy().into_owned())
+}
+
+/// Resolve a user name to its uid and primary gid.
+#[cfg(unix)]
+pub(crate) fn lookup_user(name: &str) -> Result<(u32, u32), String> {
+    let c_name = std::ffi::CString::new(name).map_err(|e| e.to_string())?;
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local and `buf.len()` is its real size.
+    let rc = unsafe {
+        libc::getpwnam_r(c_name.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if rc != 0 {
+        return Err(std::io::Error::from_raw_os_error(rc).to_string());
+    }
+    if result.is_null() {
+        return Err(format!("unknown user: {name}"));
+    }
+    Ok((pwd.pw_uid, pwd.pw_gid))
+}
+
+/// Resolve a group name to its gid.
+#[cfg(unix)]
+fn lookup_group(name: &str) -> Result<u32, String> {
+    let c_name = std::ffi::CString::new(name).map_err(|e| e.to_string())?;
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local and `buf.len()` is its real size.
+    let rc = unsafe {
+        libc::getgrnam_r(c_name.as_ptr(), &mut grp, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if rc != 0 {
+        return Err(std::io::Error::from_raw_os_error(rc).to_string());
+    }
+    if result.is_null() {
+        return Err(format!("unknown group: {name}"));
+    }
+    Ok(grp.gr_gid)
+}
+
+// ── File management ───────────────────────────────────────────────────────────
+
+/// Stable `error_kind` code reported by the file-management forms.
+fn error_kind(e: &io::Error) -> &'static str {
+    match e.kind() {
+        io::ErrorKind::NotFound          => "not_found",
+        io::ErrorKind::PermissionDenied  => "permission_denied",
+        io::ErrorKind::AlreadyExists     => "already_exists",
+        io::ErrorKind::DirectoryNotEmpty => "not_empty",
+        io::ErrorKind::NotADirectory     => "not_a_directory",
+        io::ErrorKind::IsADirectory      => "is_a_directory",
+        io::ErrorKind::InvalidInput      => "invalid_input",
+        io::ErrorKind::Unsupported       => "unsupported",
+        _                                => "other",
+    }
+}
+
+/// Record the outcome of a file-management operation on its form fields.
+fn set_outcome(result: io::Result<()>, error_kind_field: &mut Option<String>, error: &mut Option<String>) {
+    match result {
+        Ok(()) => {
+            *error_kind_field = None;
+            *error            = None;
+        }
+        Err(e) => {
+            *error_kind_field = Some(error_kind(&e).to_string());
+            *error            = Some(e.to_string());
+        }
+    }
+}
+
+/// Delete the path named by a [`DeletePath`] form.
+pub fn delete_path(mut form: DeletePath) -> DeletePath {
+    log::debug!("{}", form.path);
+    let result = delete_inner(&form);
+    set_outcome(result, &mut form.error_kind, &mut form.error);
+    form
+}
+
+/// Move a path as described by a [`MovePath`] form.
+pub fn move_path(mut form: MovePath) -> MovePath {
+    log::debug!("{} -> {}", form.src, form.dst);
+    let result = move_inner(&form);
+    set_outcome(result, &mut form.error_kind, &mut form.error);
+    form
+}
+
+/// Create the directory named by a [`MakeDirectory`] form.
+pub fn make_directory(mut form: MakeDirectory) -> MakeDirectory {
+    log::debug!("{}", form.path);
+    let result = make_directory_inner(&form);
+    set_outcome(result, &mut form.error_kind, &mut form.error);
+    form
+}
+
+/// Change mode and ownership as described by a [`SetPermissions`] form.
+pub fn set_permissions(mut form: SetPermissions) -> SetPermissions {
+    log::debug!("{}", form.path);
+    let result = set_permissions_inner(&form);
+    set_outcome(result, &mut form.error_kind, &mut form.error);
+    form
+}
+
+fn delete_inner(form: &DeletePath) -> io::Result<()> {
+    let path = Path::new(&form.path);
+    if path.parent().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("refusing to delete {}", form.path),
+        ));
+    }
+
+    let meta = std::fs::symlink_metadata(path)?;
+    if !meta.is_dir() {
+        std::fs::remove_file(path)
+    } else if form.recursive {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_dir(path)
+    }
+}
+
+fn move_inner(form: &MovePath) -> io::Result<()> {
+    let src = Path::new(&form.src);
+    let dst = Path::new(&form.dst);
+    let meta = std::fs::symlink_metadata(src)?;
+    if !form.overwrite && std::fs::symlink_metadata(dst).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", form.dst),
+        ));
+    }
+
+    match std::fs::rename(src, dst) {
+        // rename(2) cannot cross file systems; files are copied over instead.
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices && meta.is_file() => {
+            std::fs::copy(src, dst)?;
+            std::fs::remove_file(src)
+        }
+        result => result,
+    }
+}
+
+fn make_directory_inner(form: &MakeDirectory) -> io::Result<()> {
+    let path = Path::new(&form.path);
+    if form.parents && path.is_dir() {
+        return Ok(());
+    }
+
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(form.parents);
+    builder.create(path)?;
+
+    #[cfg(unix)]
+    if let Some(mode) = form.mode {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_permissions_inner(form: &SetPermissions) -> io::Result<()> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+    if form.mode.is_none() && form.owner.is_none() && form.group.is_none() {
+        return Err(invalid("nothing to change: set mode, owner or group".to_string()));
+    }
+
+    let uid = form.owner.as_deref()
+        .map(|o| o.parse().or_else(|_| lookup_user(o).map(|(uid, _)| uid)))
+        .transpose()
+        .map_err(invalid)?;
+    let gid = form.group.as_deref()
+        .map(|g| g.parse().or_else(|_| lookup_group(g)))
+        .transpose()
+        .map_err(invalid)?;
+
+    let path = Path::new(&form.path);
+    if let Some(mode) = form.mode {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    if uid.is_some() || gid.is_some() {
+        std::os::unix::fs::chown(path, uid, gid)?;
+    }
+
+    if form.recursive && std::fs::metadata(path)?.is_dir() {
+        set_permissions_tree(path, form.mode, uid, gid)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_permissions_inner(_form: &SetPermissions) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "set_permissions is not supported on this platform",
+    ))
+}
+
+/// Apply a mode and ownership to everything below `dir`, stopping at the
+/// first failure.  Symlinks are not followed and keep their own mode.
+#[cfg(unix)]
+fn set_permissions_tree(dir: &Path, mode: Option<u32>, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let with_path = |path: &Path, e: io::Error| io::Error::new(e.kind(), format!("{}: {e}", path.display()));
+
+    for child in std::fs::read_dir(dir).map_err(|e| with_path(dir, e))? {
+        let path = child.map_err(|e| with_path(dir, e))?.path();
+        let meta = std::fs::symlink_metadata(&path).map_err(|e| with_path(&path, e))?;
+        if let (Some(mode), false) = (mode, meta.is_symlink()) {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                .map_err(|e| with_path(&path, e))?;
+        }
+        if uid.is_some() || gid.is_some() {
+            std::os::unix::fs::lchown(&path, uid, gid).map_err(|e| with_path(&path, e))?;
+        }
+        if meta.is_dir() {
+            set_permissions_tree(&path, mode, uid, gid)?;
+        }
+    }
     Ok(())
 }
 
@@ -155,6 +829,7 @@ fn write_inner(form: &WriteFile) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::control::Encoding;
 
     // Canonical fixtures — must match Python's test_file.py
     const TEST_DATA:    &[u8] = b"hello, stembot!";
@@ -229,6 +904,7 @@ mod tests {
             error:   None,
             objuuid: None,
             coluuid: None,
+            ..Default::default()
         };
         let data = load_bytes_from_form(&form).unwrap();
         assert_eq!(data, TEST_DATA);
@@ -244,6 +920,7 @@ mod tests {
             error:   Some("previous error".to_string()),
             objuuid: None,
             coluuid: None,
+            ..Default::default()
         };
         assert!(load_bytes_from_form(&form).is_err());
     }
@@ -258,6 +935,7 @@ mod tests {
             error:   None,
             objuuid: None,
             coluuid: None,
+            ..Default::default()
         };
         assert!(load_bytes_from_form(&form).is_err());
     }
@@ -304,6 +982,7 @@ mod tests {
             error:   None,
             objuuid: None,
             coluuid: None,
+            ..Default::default()
         };
 
         let result = write_file_from_form(form);
@@ -324,4 +1003,443 @@ mod tests {
         let result = write_file_from_form(form);
         assert!(result.error.is_some());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_from_form_keeps_replaced_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.conf");
+        std::fs::write(&path, b"old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        let result = write_file_from_form(WriteFile {
+            path: path.to_str().unwrap().to_string(),
+            ..load_form_from_bytes(TEST_DATA)
+        });
+        assert!(result.error.is_none());
+        assert!(result.backup_path.is_none());
+        assert_eq!(std::fs::read(&path).unwrap(), TEST_DATA);
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o7777, 0o640);
+        // Only the target is left; the temporary file was renamed over it.
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_file_from_form_backup() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.conf");
+        std::fs::write(&path, b"old").unwrap();
+
+        let result = write_file_from_form(WriteFile {
+            path:   path.to_str().unwrap().to_string(),
+            backup: true,
+            ..load_form_from_bytes(TEST_DATA)
+        });
+        assert!(result.error.is_none());
+        let backup_path = result.backup_path.unwrap();
+        assert!(backup_path.ends_with("app.conf.bak"));
+        assert_eq!(std::fs::read(&backup_path).unwrap(), b"old");
+        assert_eq!(std::fs::read(&path).unwrap(), TEST_DATA);
+    }
+
+    #[test]
+    fn test_write_file_from_form_md5_mismatch_leaves_target() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.conf");
+        std::fs::write(&path, b"old").unwrap();
+
+        let result = write_file_from_form(WriteFile {
+            path:   path.to_str().unwrap().to_string(),
+            md5sum: Some("00000000000000000000000000000000".to_string()),
+            ..load_form_from_bytes(TEST_DATA)
+        });
+        assert!(result.error.is_some());
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_load_write_roundtrip_preserves_metadata() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dst = dir.path().join("dst.bin");
+        std::fs::write(&src, TEST_DATA).unwrap();
+        std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o604)).unwrap();
+        filetime::set_file_mtime(&src, filetime::FileTime::from_unix_time(1_000_000, 500_000_000)).unwrap();
+
+        let loaded = load_file_to_form(LoadFile {
+            path: src.to_str().unwrap().to_string(),
+            ..Default::default()
+        });
+        assert_eq!(loaded.mode, Some(0o604));
+        assert_eq!(loaded.mtime, Some(1_000_000.5));
+
+        let result = write_file_from_form(WriteFile {
+            b64zlib: loaded.b64zlib.unwrap(),
+            path:    dst.to_str().unwrap().to_string(),
+            md5sum:  loaded.md5sum,
+            mode:    loaded.mode,
+            uid:     loaded.uid,
+            gid:     loaded.gid,
+            mtime:   loaded.mtime,
+            ..Default::default()
+        });
+        assert!(result.error.is_none(), "{:?}", result.error);
+        let meta = std::fs::metadata(&dst).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o7777, 0o604);
+        assert_eq!(filetime::FileTime::from_last_modification_time(&meta).unix_seconds(), 1_000_000);
+    }
+
+    // ── hash_file / hash_algorithm ────────────────────────────────────────────
+
+    #[test]
+    fn test_digest_hex_canonical() {
+        assert_eq!(digest_hex(HashAlgorithm::Md5, b"hello\n"), "b1946ac92492d2347c6235b4d2611184");
+        assert_eq!(
+            digest_hex(HashAlgorithm::Sha256, b"hello\n"),
+            "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03"
+        );
+        assert_eq!(
+            digest_hex(HashAlgorithm::Blake3, b"hello\n"),
+            blake3::hash(b"hello\n").to_hex().to_string()
+        );
+    }
+
+    #[test]
+    fn test_hash_file_matches_digest() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        for algorithm in [HashAlgorithm::Md5, HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            let form = hash_file(HashFile {
+                path:           path.to_str().unwrap().to_string(),
+                hash_algorithm: algorithm,
+                ..Default::default()
+            });
+            assert!(form.error.is_none());
+            assert_eq!(form.size, Some(data.len() as u64));
+            assert_eq!(form.hash, Some(digest_hex(algorithm, &data)));
+        }
+    }
+
+    #[test]
+    fn test_hash_file_missing() {
+        let form = hash_file(HashFile { path: "/nonexistent/file".into(), ..Default::default() });
+        assert!(form.error.is_some());
+        assert!(form.hash.is_none());
+    }
+
+    #[test]
+    fn test_load_write_roundtrip_sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        let dst = dir.path().join("dst.bin");
+        std::fs::write(&src, TEST_DATA).unwrap();
+
+        let loaded = load_file_to_form(LoadFile {
+            path:           src.to_str().unwrap().to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            ..Default::default()
+        });
+        assert!(loaded.md5sum.is_none());
+        assert_eq!(loaded.hash.as_deref().map(str::len), Some(64));
+        assert_eq!(load_bytes_from_form(&loaded).unwrap(), TEST_DATA);
+
+        let form = WriteFile {
+            b64zlib:        loaded.b64zlib.clone().unwrap(),
+            path:           dst.to_str().unwrap().to_string(),
+            hash_algorithm: HashAlgorithm::Sha256,
+            hash:           loaded.hash.clone(),
+            ..Default::default()
+        };
+        assert!(write_file_from_form(form.clone()).error.is_none());
+        assert_eq!(std::fs::read(&dst).unwrap(), TEST_DATA);
+
+        let result = write_file_from_form(WriteFile { hash: Some("0".repeat(64)), ..form });
+        assert!(result.error.unwrap().starts_with("SHA256 mismatch before write"));
+    }
+
+    #[test]
+    fn test_load_write_roundtrip_zstd() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.txt");
+        let dst = dir.path().join("dst.txt");
+        let text = TEST_DATA.repeat(64);
+        std::fs::write(&src, &text).unwrap();
+
+        let loaded = load_file_to_form(LoadFile {
+            path:     src.to_str().unwrap().to_string(),
+            encoding: Encoding::Zstd,
+            ..Default::default()
+        });
+        assert_eq!(loaded.encoding, Encoding::Zstd);
+        assert_eq!(load_bytes_from_form(&loaded).unwrap(), text);
+
+        let form = WriteFile {
+            b64zlib:  loaded.b64zlib.unwrap(),
+            encoding: loaded.encoding,
+            path:     dst.to_str().unwrap().to_string(),
+            md5sum:   loaded.md5sum,
+            ..Default::default()
+        };
+        assert!(write_file_from_form(form).error.is_none());
+        assert_eq!(std::fs::read(&dst).unwrap(), text);
+    }
+
+    #[test]
+    fn test_load_file_to_form_incompressible_falls_back_to_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.bin");
+        std::fs::write(&src, b"x").unwrap();
+
+        let loaded = load_file_to_form(LoadFile {
+            path:     src.to_str().unwrap().to_string(),
+            encoding: Encoding::Gzip,
+            ..Default::default()
+        });
+        assert_eq!(loaded.encoding, Encoding::None);
+        assert_eq!(B64.decode(loaded.b64zlib.unwrap()).unwrap(), b"x");
+    }
+
+    // ── list_directory / stat_file ────────────────────────────────────────────
+
+    fn make_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub/deeper")).unwrap();
+        std::fs::write(dir.path().join("a.conf"), b"aaa").unwrap();
+        std::fs::write(dir.path().join("b.txt"), b"b").unwrap();
+        std::fs::write(dir.path().join("sub/c.conf"), b"cc").unwrap();
+        std::fs::write(dir.path().join("sub/deeper/d.conf"), b"d").unwrap();
+        dir
+    }
+
+    fn list(path: &Path, depth: u32, pattern: Option<&str>) -> ListDirectory {
+        list_directory(ListDirectory {
+            path:    path.to_string_lossy().into_owned(),
+            depth,
+            pattern: pattern.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    fn names(form: &ListDirectory) -> Vec<&str> {
+        form.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_directory_direct_children() {
+        let dir = make_tree();
+        let form = list(dir.path(), 1, None);
+        assert!(form.error.is_none());
+        assert_eq!(names(&form), ["a.conf", "b.txt", "sub"]);
+        assert_eq!(form.entries[0].kind, "file");
+        assert_eq!(form.entries[0].size, 3);
+        assert_eq!(form.entries[2].kind, "directory");
+    }
+
+    #[test]
+    fn test_list_directory_depth_and_pattern() {
+        let dir = make_tree();
+        let form = list(dir.path(), 2, Some("*.conf"));
+        assert_eq!(names(&form), ["a.conf", "sub/c.conf"]);
+
+        let form = list(dir.path(), 3, Some("[!a]*.conf"));
+        assert_eq!(names(&form), ["sub/c.conf", "sub/deeper/d.conf"]);
+    }
+
+    #[test]
+    fn test_list_directory_of_file() {
+        let dir = make_tree();
+        let form = list(&dir.path().join("b.txt"), 1, None);
+        assert_eq!(names(&form), ["b.txt"]);
+    }
+
+    #[test]
+    fn test_list_directory_missing() {
+        let form = list(Path::new("/nonexistent/dir"), 1, None);
+        assert!(form.error.is_some());
+        assert!(form.entries.is_empty());
+    }
+
+    #[test]
+    fn test_glob_regex() {
+        assert!(glob_regex("*.conf").unwrap().is_match("sshd.conf"));
+        assert!(!glob_regex("*.conf").unwrap().is_match("sshd.conf.bak"));
+        assert!(glob_regex("file?.[ch]").unwrap().is_match("file1.c"));
+        assert!(!glob_regex("file?.[ch]").unwrap().is_match("file12.c"));
+        assert!(glob_regex("a+b").unwrap().is_match("a+b"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stat_file_symlink() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = make_tree();
+        let file = dir.path().join("a.conf");
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        let form = stat_file(StatFile { path: file.to_string_lossy().into_owned(), ..Default::default() });
+        let entry = form.entry.unwrap();
+        assert_eq!(entry.name, "a.conf");
+        assert_eq!(entry.mode, Some(0o640));
+        assert!(entry.owner.is_some());
+        assert!(entry.mtime.is_some());
+
+        let form = stat_file(StatFile { path: link.to_string_lossy().into_owned(), ..Default::default() });
+        let entry = form.entry.unwrap();
+        assert_eq!(entry.kind, "symlink");
+        assert_eq!(entry.target.as_deref(), Some(file.to_str().unwrap()));
+    }
+
+    // ── delete_path / move_path / make_directory / set_permissions ────────────
+
+    fn path_str(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_delete_path_directory() {
+        let dir = make_tree();
+        let sub = dir.path().join("sub");
+
+        let form = delete_path(DeletePath { path: path_str(&sub), ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("not_empty"));
+        assert!(sub.exists());
+
+        let form = delete_path(DeletePath { path: path_str(&sub), recursive: true, ..Default::default() });
+        assert!(form.error.is_none());
+        assert!(form.error_kind.is_none());
+        assert!(!sub.exists());
+    }
+
+    #[test]
+    fn test_delete_path_missing_and_root() {
+        let form = delete_path(DeletePath { path: "/nonexistent/file".into(), ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("not_found"));
+
+        let form = delete_path(DeletePath { path: "/".into(), recursive: true, ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("invalid_input"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_path_symlink_keeps_target() {
+        let dir = make_tree();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(dir.path().join("sub"), &link).unwrap();
+
+        let form = delete_path(DeletePath { path: path_str(&link), recursive: true, ..Default::default() });
+        assert!(form.error.is_none());
+        assert!(!link.exists());
+        assert!(dir.path().join("sub/c.conf").exists());
+    }
+
+    #[test]
+    fn test_move_path_overwrite() {
+        let dir = make_tree();
+        let src = dir.path().join("a.conf");
+        let dst = dir.path().join("b.txt");
+
+        let form = move_path(MovePath { src: path_str(&src), dst: path_str(&dst), ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("already_exists"));
+        assert!(src.exists());
+
+        let form = move_path(MovePath {
+            src:       path_str(&src),
+            dst:       path_str(&dst),
+            overwrite: true,
+            ..Default::default()
+        });
+        assert!(form.error.is_none());
+        assert!(!src.exists());
+        assert_eq!(std::fs::read(&dst).unwrap(), b"aaa");
+    }
+
+    #[test]
+    fn test_move_path_missing_source() {
+        let dir = make_tree();
+        let form = move_path(MovePath {
+            src: path_str(&dir.path().join("missing")),
+            dst: path_str(&dir.path().join("x")),
+            ..Default::default()
+        });
+        assert_eq!(form.error_kind.as_deref(), Some("not_found"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_make_directory_parents_and_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = make_tree();
+        let path = dir.path().join("x/y/z");
+
+        let form = make_directory(MakeDirectory { path: path_str(&path), ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("not_found"));
+
+        let form = make_directory(MakeDirectory {
+            path:    path_str(&path),
+            parents: true,
+            mode:    Some(0o700),
+            ..Default::default()
+        });
+        assert!(form.error.is_none());
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o7777, 0o700);
+
+        let form = make_directory(MakeDirectory { path: path_str(&path), parents: true, ..Default::default() });
+        assert!(form.error.is_none());
+        let form = make_directory(MakeDirectory { path: path_str(&path), ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("already_exists"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_permissions_recursive() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let dir = make_tree();
+        let sub = dir.path().join("sub");
+        let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o7777;
+
+        let form = set_permissions(SetPermissions {
+            path:      path_str(&sub),
+            mode:      Some(0o750),
+            owner:     Some(std::fs::metadata(&sub).unwrap().uid().to_string()),
+            recursive: true,
+            ..Default::default()
+        });
+        assert!(form.error.is_none(), "{:?}", form.error);
+        assert_eq!(mode(&sub), 0o750);
+        assert_eq!(mode(&sub.join("c.conf")), 0o750);
+        assert_eq!(mode(&sub.join("deeper/d.conf")), 0o750);
+        assert_ne!(mode(&dir.path().join("a.conf")), 0o750);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_permissions_errors() {
+        let dir = make_tree();
+        let form = set_permissions(SetPermissions { path: path_str(dir.path()), ..Default::default() });
+        assert_eq!(form.error_kind.as_deref(), Some("invalid_input"));
+
+        let form = set_permissions(SetPermissions {
+            path:  path_str(dir.path()),
+            owner: Some("no-such-user-stembot".into()),
+            ..Default::default()
+        });
+        assert_eq!(form.error_kind.as_deref(), Some("invalid_input"));
+        assert!(form.error.unwrap().contains("unknown user"));
+
+        let form = set_permissions(SetPermissions {
+            path: "/nonexistent/file".into(),
+            mode: Some(0o644),
+            ..Default::default()
+        });
+        assert_eq!(form.error_kind.as_deref(), Some("not_found"));
+    }
 }
@@ -1,3 +1,7 @@
 pub mod agent;
+pub mod archive;
+pub mod compression;
 pub mod file;
 pub mod process;
+pub mod tail;
+pub mod transfer;
@@ -1,56 +1,278 @@
 //! Subprocess execution with timeout enforcement and output capture.
 //!
 //! Mirrors Python's `stembot/executor/process.py`.
+//!
+//! [`sync_process`] honours the form's working directory, environment, stdin
+//! and `run_as_user` fields.  [`sync_process_streaming`] runs the same way but also hands each line of
+//! output to a channel while the process is still running.
+//!
+//! Besides the synchronous [`sync_process`], this module keeps a registry of
+//! background processes started with [`start_process`].  Each entry owns the
+//! `Child` handle and the tail of the stdout/stderr read so far by reader
+//! threads, so later control forms can poll, list, or terminate the process
+//! by its `prcuuid` handle.  Background processes run under the same rlimits
+//! as `SyncProcess` commands and hold one of the `max_processes` slots until
+//! they exit.
 
-use std::process::{Command, Stdio};
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::models::control::{CommandArg, SyncProcess};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-/// Execute a subprocess with timeout enforcement and output capture.
+use crate::config::config;
+#[cfg(unix)]
+use crate::executor::file::lookup_user;
+use crate::models::config::Config;
+use crate::metrics;
+use crate::models::control::{
+    CommandArg, KillProcess, ListProcesses, ProcessInfo, ProcessStatus, StartProcess, SyncProcess,
+};
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Build a [`Command`] for a shell string or an argument vector.
 ///
-/// Mirrors Python's `sync_process(form: SyncProcess) -> SyncProcess`.
-/// Works on Unix and Windows.
-pub fn sync_process(mut form: SyncProcess) -> SyncProcess {
-    let mut cmd = match &form.command {
+/// Single strings run through `sh -c` on Unix and `cmd /C` on Windows.
+fn build_command(command: &CommandArg) -> Result<Command, String> {
+    match command {
         CommandArg::Single(s) => {
             #[cfg(unix)]
             {
                 let mut c = Command::new("sh");
                 c.args(["-c", s]);
-                c
+                Ok(c)
             }
             #[cfg(windows)]
             {
                 let mut c = Command::new("cmd");
                 c.args(["/C", s]);
-                c
+                Ok(c)
             }
         }
         CommandArg::Multi(args) => {
             if args.is_empty() {
-                form.error = Some("empty command list".to_string());
-                return form;
+                return Err("empty command list".to_string());
             }
             let mut c = Command::new(&args[0]);
             c.args(&args[1..]);
-            c
+            Ok(c)
+        }
+    }
+}
+
+/// A piece of output read from a running process, split at line boundaries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputChunk {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Execute a subprocess with timeout enforcement and output capture.
+///
+/// Mirrors Python's `sync_process(form: SyncProcess) -> SyncProcess`.
+/// Works on Unix and Windows.
+pub fn sync_process(form: SyncProcess) -> SyncProcess {
+    record_sync_process(|| run_sync_process(form, None))
+}
+
+/// Execute a subprocess like [`sync_process`], forwarding output as it is read.
+///
+/// Every line written to stdout or stderr is sent on `sink` as soon as it is
+/// read; the returned form still carries the complete output.  The channel is
+/// disconnected once both pipes reach EOF.
+pub fn sync_process_streaming(form: SyncProcess, sink: Sender<OutputChunk>) -> SyncProcess {
+    record_sync_process(|| run_sync_process(form, Some(sink)))
+}
+
+/// Run a `SyncProcess` and count it in the process metrics.
+fn record_sync_process(run: impl FnOnce() -> SyncProcess) -> SyncProcess {
+    let started = Instant::now();
+    let form = run();
+    metrics::inc("stembot_process_executions_total", &[("kind", "sync")]);
+    metrics::observe("stembot_process_duration_seconds", &[("kind", "sync")], started.elapsed().as_secs_f64());
+    form
+}
+
+/// How long a timed-out process tree gets to exit after SIGTERM before SIGKILL.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Start the child as the leader of a new process group so that the whole
+/// tree it spawns can be signalled at once.
+fn isolate_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        let _ = cmd;
+    }
+}
+
+/// Send `signal` to every process in the group led by `pid`.
+#[cfg(unix)]
+fn signal_tree(pid: u32, signal: libc::c_int) {
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    if unsafe { libc::kill(-(pid as libc::pid_t), signal) } != 0 {
+        log::debug!("kill(-{pid}, {signal}): {}", std::io::Error::last_os_error());
+    }
+}
+
+/// Terminate the process tree led by `pid`.
+///
+/// On Unix the group gets SIGTERM, then SIGKILL if `done` has not fired within
+/// [`KILL_GRACE_PERIOD`].  On Windows `taskkill /T /F` ends the tree at once.
+fn terminate_tree(pid: u32, done: &Receiver<()>) {
+    #[cfg(unix)]
+    {
+        signal_tree(pid, libc::SIGTERM);
+        if let Err(RecvTimeoutError::Timeout) = done.recv_timeout(KILL_GRACE_PERIOD) {
+            signal_tree(pid, libc::SIGKILL);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = done;
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// The signal that terminated a process, if any.
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal().map(|s| s as i64)
+    }
+    #[cfg(windows)]
+    {
+        let _ = status;
+        None
+    }
+}
+
+/// Apply the working directory, environment and user requested by `form`.
+fn configure_command(cmd: &mut Command, form: &SyncProcess) -> Result<(), String> {
+    if let Some(ref cwd) = form.cwd {
+        cmd.current_dir(cwd);
+    }
+    if form.clear_env {
+        cmd.env_clear();
+    }
+    cmd.envs(&form.env);
+
+    if let Some(ref user) = form.run_as_user {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            let (uid, gid) = lookup_user(user)?;
+            cmd.uid(uid).gid(gid);
+        }
+        #[cfg(windows)]
+        {
+            return Err(format!("run_as_user is not supported on this platform: {user}"));
+        }
+    }
+
+    #[cfg(unix)]
+    set_rlimits(cmd, form);
+    #[cfg(windows)]
+    if form.rlimit_cpu_secs.is_some() || form.rlimit_as_bytes.is_some() || form.rlimit_nofile.is_some() {
+        log::warn!("resource limits are not supported on this platform; ignoring them");
+    }
+    Ok(())
+}
+
+/// Apply the form's rlimits to the child between fork and exec.
+///
+/// Each limit sets both the soft and hard value, never above the hard limit
+/// the agent itself runs with.
+#[cfg(unix)]
+fn set_rlimits(cmd: &mut Command, form: &SyncProcess) {
+    use std::os::unix::process::CommandExt;
+
+    let limits: Vec<_> = [
+        (libc::RLIMIT_CPU,    form.rlimit_cpu_secs),
+        (libc::RLIMIT_AS,     form.rlimit_as_bytes),
+        (libc::RLIMIT_NOFILE, form.rlimit_nofile),
+    ]
+    .into_iter()
+    .filter_map(|(resource, value)| value.map(|v| (resource, v as libc::rlim_t)))
+    .collect();
+    if limits.is_empty() {
+        return;
+    }
+
+    // SAFETY: the hook only calls getrlimit(2) and setrlimit(2), which are
+    // async-signal-safe, and touches no memory shared with the parent.
+    unsafe {
+        cmd.pre_exec(move || {
+            for &(resource, value) in &limits {
+                let mut current: libc::rlimit = std::mem::zeroed();
+                if libc::getrlimit(resource, &mut current) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                let value = value.min(current.rlim_max);
+                let limit = libc::rlimit { rlim_cur: value, rlim_max: value };
+                if libc::setrlimit(resource, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+fn run_sync_process(mut form: SyncProcess, sink: Option<Sender<OutputChunk>>) -> SyncProcess {
+    let mut cmd = match build_command(&form.command) {
+        Ok(c) => c,
+        Err(e) => {
+            form.error = Some(e);
+            return form;
         }
     };
 
+    if let Err(e) = configure_command(&mut cmd, &form) {
+        form.error = Some(e);
+        return form;
+    }
+
+    let stdin = match form.b64stdin.as_deref().map(|s| B64.decode(s)).transpose() {
+        Ok(data) => data,
+        Err(e) => {
+            form.error = Some(format!("invalid b64stdin: {e}"));
+            return form;
+        }
+    };
+    if stdin.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
+
     cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    isolate_process_group(&mut cmd);
 
     let start_instant = Instant::now();
-    form.start_time = Some(
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs_f64(),
-    );
+    form.start_time = Some(unix_now());
 
-    let child = match cmd.spawn() {
+    let mut child = match cmd.spawn() {
         Ok(c) => c,
         Err(e) => {
             form.elapsed_time = Some(0.0);
@@ -59,47 +281,855 @@ pub fn sync_process(mut form: SyncProcess) -> SyncProcess {
         }
     };
 
-    let timeout_secs = form.timeout as f64;
+    // Feed stdin from its own thread so a process that fills its stdout pipe
+    // before reading all of its input cannot deadlock us.
+    if let (Some(data), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        thread::spawn(move || {
+            let _ = pipe.write_all(&data);
+        });
+    }
+
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let stderr = Arc::new(Mutex::new(Vec::new()));
+    let mut readers = Vec::new();
+    if let Some(pipe) = child.stdout.take() {
+        let sink = sink.clone().map(|tx| (tx, OutputChunk::Stdout as fn(_) -> _));
+        readers.push(spawn_reader(pipe, Arc::clone(&stdout), sink, form.max_output_bytes));
+    }
+    if let Some(pipe) = child.stderr.take() {
+        let sink = sink.map(|tx| (tx, OutputChunk::Stderr as fn(_) -> _));
+        readers.push(spawn_reader(pipe, Arc::clone(&stderr), sink, form.max_output_bytes));
+    }
 
-    // Share the Child handle with a watchdog thread using Arc<Mutex<Option<Child>>>.
-    // The watchdog calls child.kill() if the timeout fires before the main thread
-    // takes ownership back. This approach is cross-platform (no libc / SIGKILL).
-    let child_arc = Arc::new(Mutex::new(Some(child)));
-    let child_arc_watchdog = Arc::clone(&child_arc);
+    // The watchdog waits for the main thread to finish or the timeout to fire,
+    // whichever comes first.  On timeout it terminates the whole process tree;
+    // the pipes then close and the readers below can finish.
+    let timeout = Duration::from_secs_f64(form.timeout.max(0) as f64);
+    let pid = child.id();
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let timed_out_watchdog = Arc::clone(&timed_out);
+    let (done_tx, done_rx) = mpsc::channel::<()>();
 
     let watchdog = thread::spawn(move || {
-        thread::sleep(Duration::from_secs_f64(timeout_secs));
-        if let Ok(mut guard) = child_arc_watchdog.lock() {
-            if let Some(ref mut c) = *guard {
-                let _ = c.kill();
-            }
+        if let Err(RecvTimeoutError::Timeout) = done_rx.recv_timeout(timeout) {
+            timed_out_watchdog.store(true, Ordering::SeqCst);
+            log::warn!("process {pid} timed out after {}s", timeout.as_secs_f64());
+            terminate_tree(pid, &done_rx);
         }
     });
 
-    // Take the Child back out so we can call wait_with_output().
-    let child = child_arc
-        .lock()
-        .expect("mutex poisoned")
-        .take()
-        .expect("child already taken");
+    let exit = child.wait();
 
-    let output = child.wait_with_output();
+    // The readers finish once every process holding the pipes has exited, which
+    // includes grandchildren left running in the background by the command.
+    for reader in readers {
+        form.output_truncated |= reader.join().unwrap_or(false);
+    }
 
-    // Joining the watchdog is best-effort; it exits naturally once it sleeps through.
-    drop(watchdog);
+    drop(done_tx);
+    let _ = watchdog.join();
 
     form.elapsed_time = Some(start_instant.elapsed().as_secs_f64());
+    form.timed_out    = timed_out.load(Ordering::SeqCst);
+
+    match exit {
+        Ok(status) => {
+            form.stdout = stdout.lock().ok().map(|b| String::from_utf8_lossy(&b).into_owned());
+            form.stderr = stderr.lock().ok().map(|b| String::from_utf8_lossy(&b).into_owned());
+            form.status = status.code().map(|c| c as i64);
+            form.signal = exit_signal(&status);
+        }
+        Err(e) => {
+            form.error = Some(e.to_string());
+        }
+    }
+
+    form
+}
+
+// ── Resource limits and concurrency ───────────────────────────────────────────
+
+/// The stricter of two limits, where `None` means unlimited.
+fn stricter(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Tighten the limits requested by `form` to the agent-wide ones in `config`.
+///
+/// A form may ask for stricter limits than the agent's, never looser ones.
+/// A zero in `config` means unlimited.
+pub fn apply_limits(form: &mut SyncProcess, config: &Config) {
+    let configured = |v: u64| (v > 0).then_some(v);
+    form.rlimit_cpu_secs  = stricter(form.rlimit_cpu_secs, configured(config.rlimit_cpu_secs));
+    form.rlimit_as_bytes  = stricter(form.rlimit_as_bytes, configured(config.rlimit_as_bytes));
+    form.rlimit_nofile    = stricter(form.rlimit_nofile, configured(config.rlimit_nofile));
+    form.max_output_bytes = stricter(form.max_output_bytes, configured(config.max_output_bytes));
+}
+
+/// How often a queued process re-checks its position in the queue.
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Bounds how many processes run at once, handing out slots in FIFO order.
+pub struct ProcessSlots {
+    semaphore: Arc<Semaphore>,
+    waiting:   Mutex<VecDeque<u64>>,
+    next_id:   AtomicU64,
+}
+
+/// Removes a waiter from the queue however its wait ends.
+struct QueueEntry<'a> {
+    slots: &'a ProcessSlots,
+    id:    u64,
+}
+
+impl Drop for QueueEntry<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut waiting) = self.slots.waiting.lock() {
+            waiting.retain(|w| *w != self.id);
+        }
+    }
+}
+
+impl ProcessSlots {
+    pub fn new(max: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max)),
+            waiting:   Mutex::new(VecDeque::new()),
+            next_id:   AtomicU64::new(0),
+        }
+    }
+
+    /// Take a free slot without waiting, or `None` if all are in use or
+    /// already promised to queued waiters.
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if !self.waiting.lock().unwrap_or_else(|e| e.into_inner()).is_empty() {
+            return None;
+        }
+        Arc::clone(&self.semaphore).try_acquire_owned().ok()
+    }
+
+    /// Wait for a free slot; it is released when the permit is dropped.
+    ///
+    /// While queued, `report` is awaited with the 1-based queue position each
+    /// time it changes, and with `0` once a slot is granted after waiting.
+    pub async fn acquire<F, Fut>(&self, mut report: F) -> OwnedSemaphorePermit
+    where
+        F: FnMut(u64) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        if let Ok(permit) = Arc::clone(&self.semaphore).try_acquire_owned() {
+            return permit;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.waiting.lock().unwrap_or_else(|e| e.into_inner()).push_back(id);
+        let entry = QueueEntry { slots: self, id };
+
+        let acquire = Arc::clone(&self.semaphore).acquire_owned();
+        tokio::pin!(acquire);
+        let mut reported = None;
+        let permit = loop {
+            let position = self
+                .waiting
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .position(|w| *w == id)
+                .map(|i| i as u64 + 1);
+            if position.is_some() && position != reported {
+                reported = position;
+                report(position.unwrap_or_default()).await;
+            }
+            tokio::select! {
+                permit = &mut acquire => break permit.expect("process semaphore closed"),
+                _ = tokio::time::sleep(SLOT_POLL_INTERVAL) => {}
+            }
+        };
+        drop(entry);
+        report(0).await;
+        permit
+    }
+}
+
+static SLOTS: OnceLock<Option<ProcessSlots>> = OnceLock::new();
+
+/// The agent's process slots, or `None` when `max_processes` is unlimited.
+pub fn process_slots() -> Option<&'static ProcessSlots> {
+    SLOTS
+        .get_or_init(|| match config().max_processes {
+            0   => None,
+            max => Some(ProcessSlots::new(max as usize)),
+        })
+        .as_ref()
+}
+
+// ── Background process registry ───────────────────────────────────────────────
+
+/// A background process and the output captured from it so far.
+struct ManagedProcess {
+    command:    CommandArg,
+    child:      Child,
+    /// The process slot held until the process exits.
+    slot:       Option<OwnedSemaphorePermit>,
+    stdout:     Arc<Mutex<Vec<u8>>>,
+    stderr:     Arc<Mutex<Vec<u8>>>,
+    start_time: f64,
+    started:    Instant,
+    status:     Option<i64>,
+    signal:     Option<i64>,
+    elapsed:    Option<f64>,
+    end_time:   Option<f64>,
+}
+
+impl ManagedProcess {
+    /// Reap the child if it has exited and record its status and runtime.
+    fn refresh(&mut self) {
+        if self.end_time.is_some() {
+            return;
+        }
+        if let Ok(Some(exit)) = self.child.try_wait() {
+            self.status   = exit.code().map(|c| c as i64);
+            self.signal   = exit_signal(&exit);
+            self.elapsed  = Some(self.started.elapsed().as_secs_f64());
+            self.end_time = Some(unix_now());
+            self.slot     = None;
+            metrics::observe(
+                "stembot_process_duration_seconds",
+                &[("kind", "background")],
+                self.elapsed.unwrap_or_default(),
+            );
+        }
+    }
+
+    fn running(&self) -> bool {
+        self.end_time.is_none()
+    }
+
+    fn elapsed_time(&self) -> f64 {
+        self.elapsed.unwrap_or_else(|| self.started.elapsed().as_secs_f64())
+    }
+}
+
+static PROCESSES: OnceLock<Mutex<HashMap<String, ManagedProcess>>> = OnceLock::new();
+
+fn processes() -> &'static Mutex<HashMap<String, ManagedProcess>> {
+    PROCESSES.get_or_init(|| Mutex::new(HashMap::new()))
+}
 
-    match output {
-        Ok(o) => {
-            form.stdout = Some(String::from_utf8_lossy(&o.stdout).into_owned());
-            form.stderr = Some(String::from_utf8_lossy(&o.stderr).into_owned());
-            form.status = o.status.code().map(|c| c as i64);
+/// A reader's output channel and the [`OutputChunk`] variant for its pipe.
+type ChunkSink = (Sender<OutputChunk>, fn(String) -> OutputChunk);
+
+/// How much of a pipe a reader takes in one read.
+const READ_BLOCK_SIZE: usize = 8 * 1024;
+
+/// Drain `pipe` into `buffer` on a dedicated thread until EOF.
+///
+/// The pipe is read in fixed-size blocks, so output without newlines never
+/// piles up in memory.  When `sink` is given, the kept output is also sent
+/// wrapped by its constructor, split at line boundaries; a partial line is
+/// held back until its newline arrives, EOF, or it fills a block, and is then
+/// never split inside a UTF-8 sequence, so the pieces decode to the same text
+/// as the whole output.  A closed
+/// receiver is not an error; the output is still buffered.  Once `limit`
+/// bytes have been kept the rest is read and discarded, and the thread
+/// returns `true` to report the truncation.
+fn spawn_reader(
+    mut pipe: impl Read + Send + 'static,
+    buffer: Arc<Mutex<Vec<u8>>>,
+    sink: Option<ChunkSink>,
+    limit: Option<u64>,
+) -> JoinHandle<bool> {
+    thread::spawn(move || {
+        let mut block = vec![0u8; READ_BLOCK_SIZE];
+        let mut partial = Vec::new();
+        let mut kept = 0usize;
+        let mut truncated = false;
+        let send = |bytes: &[u8]| {
+            if let Some((ref tx, wrap)) = sink {
+                let _ = tx.send(wrap(String::from_utf8_lossy(bytes).into_owned()));
+            }
+        };
+        loop {
+            let read = match pipe.read(&mut block) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            let room = limit.map_or(read, |l| (l as usize).saturating_sub(kept));
+            if room < read {
+                truncated = true;
+            }
+            let data = &block[..read.min(room)];
+            if data.is_empty() {
+                continue;
+            }
+            kept += data.len();
+            if let Ok(mut buf) = buffer.lock() {
+                buf.extend_from_slice(data);
+            }
+            if sink.is_some() {
+                for piece in data.split_inclusive(|b| *b == b'\n') {
+                    partial.extend_from_slice(piece);
+                    if piece.ends_with(b"\n") {
+                        send(&partial);
+                        partial.clear();
+                    } else if partial.len() >= READ_BLOCK_SIZE {
+                        let whole = complete_utf8_len(&partial);
+                        send(&partial[..whole]);
+                        partial.drain(..whole);
+                    }
+                }
+            }
+        }
+        if !partial.is_empty() {
+            send(&partial);
         }
+        truncated
+    })
+}
+
+/// Length of `bytes` without a UTF-8 sequence cut short at its end.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let lead = bytes[bytes.len() - back];
+        if lead & 0xC0 == 0x80 {
+            continue;
+        }
+        let needed = match lead {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        return if needed > back { bytes.len() - back } else { bytes.len() };
+    }
+    bytes.len()
+}
+
+/// Bytes of stdout and of stderr kept from a background process when
+/// `max_output_bytes` is unlimited.
+pub const DEFAULT_BACKGROUND_OUTPUT_BYTES: u64 = 1024 * 1024;
+
+/// How often a background process is checked for having exited.
+const REAP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long `kill_process` waits for a killed process to exit.
+const KILL_WAIT: Duration = Duration::from_secs(5);
+
+/// How often `kill_process` checks whether a killed process has exited.
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Drain `pipe` into `buffer` on a dedicated thread until EOF, keeping only
+/// the last `keep` bytes.
+fn spawn_tail_reader(
+    mut pipe: impl Read + Send + 'static,
+    buffer: Arc<Mutex<Vec<u8>>>,
+    keep: usize,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut block = vec![0u8; READ_BLOCK_SIZE];
+        loop {
+            let read = match pipe.read(&mut block) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            if let Ok(mut buf) = buffer.lock() {
+                buf.extend_from_slice(&block[..read]);
+                let excess = buf.len().saturating_sub(keep);
+                buf.drain(..excess);
+            }
+        }
+    })
+}
+
+/// Spawn a background process and register it under a new `prcuuid`.
+///
+/// The agent's rlimits apply as they do to `SyncProcess` commands, and the
+/// process takes one of the `max_processes` slots until it exits; when none
+/// is free the form fails rather than waiting.  Only the last
+/// `max_output_bytes` (or [`DEFAULT_BACKGROUND_OUTPUT_BYTES`]) of stdout and
+/// of stderr are kept.
+pub fn start_process(mut form: StartProcess) -> StartProcess {
+    let mut cmd = match build_command(&form.command) {
+        Ok(c) => c,
+        Err(e) => {
+            form.error = Some(e);
+            return form;
+        }
+    };
+
+    let mut limits = SyncProcess {
+        cwd:         form.cwd.clone(),
+        env:         form.env.clone(),
+        clear_env:   form.clear_env,
+        run_as_user: form.run_as_user.clone(),
+        ..SyncProcess::default()
+    };
+    apply_limits(&mut limits, &config());
+    if let Err(e) = configure_command(&mut cmd, &limits) {
+        form.error = Some(e);
+        return form;
+    }
+
+    let stdin = match form.b64stdin.as_deref().map(|s| B64.decode(s)).transpose() {
+        Ok(data) => data,
+        Err(e) => {
+            form.error = Some(format!("invalid b64stdin: {e}"));
+            return form;
+        }
+    };
+    cmd.stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() });
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    isolate_process_group(&mut cmd);
+
+    let slot = match process_slots().map(ProcessSlots::try_acquire) {
+        Some(None) => {
+            form.error = Some(format!("all {} process slots are in use", config().max_processes));
+            return form;
+        }
+        Some(permit) => permit,
+        None => None,
+    };
+
+    let mut child = match cmd.spawn() {
+        Ok(c) => c,
         Err(e) => {
             form.error = Some(e.to_string());
+            return form;
         }
+    };
+
+    if let (Some(data), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        thread::spawn(move || {
+            let _ = pipe.write_all(&data);
+        });
+    }
+
+    let keep = limits.max_output_bytes.unwrap_or(DEFAULT_BACKGROUND_OUTPUT_BYTES) as usize;
+    let stdout = Arc::new(Mutex::new(Vec::new()));
+    let stderr = Arc::new(Mutex::new(Vec::new()));
+    if let Some(pipe) = child.stdout.take() {
+        spawn_tail_reader(pipe, Arc::clone(&stdout), keep);
+    }
+    if let Some(pipe) = child.stderr.take() {
+        spawn_tail_reader(pipe, Arc::clone(&stderr), keep);
     }
 
+    let prcuuid    = uuid::Uuid::new_v4().to_string();
+    let start_time = unix_now();
+
+    processes().lock().unwrap_or_else(|e| e.into_inner()).insert(
+        prcuuid.clone(),
+        ManagedProcess {
+            command: form.command.clone(),
+            child,
+            slot,
+            stdout,
+            stderr,
+            start_time,
+            started: Instant::now(),
+            status: None,
+            signal: None,
+            elapsed: None,
+            end_time: None,
+        },
+    );
+
+    // Reap the process as soon as it exits so its slot is freed promptly.
+    thread::spawn({
+        let prcuuid = prcuuid.clone();
+        move || loop {
+            thread::sleep(REAP_INTERVAL);
+            let mut registry = processes().lock().unwrap_or_else(|e| e.into_inner());
+            match registry.get_mut(&prcuuid) {
+                Some(p) => {
+                    p.refresh();
+                    if !p.running() {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+    });
+
+    metrics::inc("stembot_process_executions_total", &[("kind", "background")]);
+    log::info!("started process {prcuuid}");
+    form.prcuuid    = Some(prcuuid);
+    form.start_time = Some(start_time);
     form
 }
+
+/// Populate a [`ProcessStatus`] form with the state and output of a registered process.
+pub fn process_status(mut form: ProcessStatus) -> ProcessStatus {
+    let mut registry = processes().lock().unwrap_or_else(|e| e.into_inner());
+    match registry.get_mut(&form.prcuuid) {
+        Some(p) => {
+            p.refresh();
+            form.running      = p.running();
+            form.status       = p.status;
+            form.start_time   = Some(p.start_time);
+            form.elapsed_time = Some(p.elapsed_time());
+            form.stdout = p.stdout.lock().ok().map(|b| String::from_utf8_lossy(&b).into_owned());
+            form.stderr = p.stderr.lock().ok().map(|b| String::from_utf8_lossy(&b).into_owned());
+        }
+        None => form.error = Some(format!("unknown process: {}", form.prcuuid)),
+    }
+    form
+}
+
+/// Terminate a registered process and wait up to [`KILL_WAIT`] for it to exit.
+pub fn kill_process(mut form: KillProcess) -> KillProcess {
+    {
+        let mut registry = processes().lock().unwrap_or_else(|e| e.into_inner());
+        let Some(p) = registry.get_mut(&form.prcuuid) else {
+            form.error = Some(format!("unknown process: {}", form.prcuuid));
+            return form;
+        };
+        p.refresh();
+        if p.running() {
+            #[cfg(unix)]
+            signal_tree(p.child.id(), libc::SIGKILL);
+            if let Err(e) = p.child.kill() {
+                form.error = Some(e.to_string());
+            }
+            log::info!("killed process {}", form.prcuuid);
+        }
+    }
+
+    // Wait for the exit without holding the registry, so that other requests
+    // and the reaper threads are not held up behind a slow exit.
+    let deadline = Instant::now() + KILL_WAIT;
+    loop {
+        let mut registry = processes().lock().unwrap_or_else(|e| e.into_inner());
+        let Some(p) = registry.get_mut(&form.prcuuid) else { break };
+        p.refresh();
+        if !p.running() || Instant::now() >= deadline {
+            form.status = p.status;
+            form.signal = p.signal;
+            break;
+        }
+        drop(registry);
+        thread::sleep(KILL_POLL_INTERVAL);
+    }
+    form
+}
+
+/// List all registered processes, running or finished.
+pub fn list_processes(mut form: ListProcesses) -> ListProcesses {
+    let mut registry = processes().lock().unwrap_or_else(|e| e.into_inner());
+    form.processes = registry
+        .iter_mut()
+        .map(|(prcuuid, p)| {
+            p.refresh();
+            ProcessInfo {
+                prcuuid:      prcuuid.clone(),
+                command:      p.command.clone(),
+                running:      p.running(),
+                status:       p.status,
+                start_time:   p.start_time,
+                elapsed_time: p.elapsed_time(),
+            }
+        })
+        .collect();
+    form.processes.sort_by(|a, b| {
+        a.start_time.partial_cmp(&b.start_time).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    form
+}
+
+/// Drop finished processes whose exit is older than the ticket timeout.
+///
+/// Running processes are never expired; they stay until they exit or are killed.
+pub fn expire_processes() {
+    let cutoff = unix_now() - config().ticket_timeout_secs as f64;
+    let mut registry = processes().lock().unwrap_or_else(|e| e.into_inner());
+    registry.retain(|prcuuid, p| {
+        p.refresh();
+        let expired = p.end_time.is_some_and(|t| t < cutoff);
+        if expired {
+            log::debug!("expiring process {prcuuid}");
+        }
+        !expired
+    });
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn wait_for_exit(prcuuid: &str) -> ProcessStatus {
+        for _ in 0..100 {
+            let status = process_status(ProcessStatus { prcuuid: prcuuid.into(), ..Default::default() });
+            if !status.running {
+                return status;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("process {prcuuid} did not exit");
+    }
+
+    #[test]
+    fn test_start_process_returns_handle_and_collects_output() {
+        let started = start_process(StartProcess {
+            command: CommandArg::Single("echo out; echo err 1>&2; exit 3".into()),
+            ..Default::default()
+        });
+        assert!(started.error.is_none());
+        let prcuuid = started.prcuuid.expect("prcuuid set");
+
+        let status = wait_for_exit(&prcuuid);
+        assert_eq!(status.status, Some(3));
+        // Reader threads may trail the exit slightly.
+        thread::sleep(Duration::from_millis(100));
+        let status = process_status(ProcessStatus { prcuuid, ..Default::default() });
+        assert_eq!(status.stdout.as_deref(), Some("out\n"));
+        assert_eq!(status.stderr.as_deref(), Some("err\n"));
+    }
+
+    #[test]
+    fn test_start_process_applies_cwd_env_and_stdin() {
+        let started = start_process(StartProcess {
+            command: CommandArg::Single("pwd; echo $GREETING; cat".into()),
+            cwd: Some("/tmp".into()),
+            env: [("GREETING".to_string(), "hello".to_string())].into(),
+            b64stdin: Some(B64.encode(b"from stdin")),
+            ..Default::default()
+        });
+        assert!(started.error.is_none());
+        let prcuuid = started.prcuuid.expect("prcuuid set");
+
+        wait_for_exit(&prcuuid);
+        thread::sleep(Duration::from_millis(100));
+        let status = process_status(ProcessStatus { prcuuid, ..Default::default() });
+        assert_eq!(status.stdout.as_deref(), Some("/tmp\nhello\nfrom stdin"));
+    }
+
+    #[test]
+    fn test_tail_reader_keeps_last_bytes() {
+        let data: Vec<u8> = (0..READ_BLOCK_SIZE * 3).map(|i| (i % 251) as u8).collect();
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        spawn_tail_reader(std::io::Cursor::new(data.clone()), Arc::clone(&buffer), 100)
+            .join()
+            .unwrap();
+        assert_eq!(*buffer.lock().unwrap(), data[data.len() - 100..]);
+    }
+
+    #[test]
+    fn test_reader_keeps_multibyte_characters_whole() {
+        // The leading byte puts every block boundary inside a character.
+        let text = format!("a{}", "é".repeat(READ_BLOCK_SIZE));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let sink = (tx, OutputChunk::Stdout as fn(_) -> _);
+        spawn_reader(std::io::Cursor::new(text.clone()), buffer, Some(sink), None)
+            .join()
+            .unwrap();
+
+        let streamed: String = rx
+            .iter()
+            .map(|chunk| match chunk {
+                OutputChunk::Stdout(s) => s,
+                OutputChunk::Stderr(s) => s,
+            })
+            .collect();
+        assert_eq!(streamed, text);
+    }
+
+    #[test]
+    fn test_kill_process_terminates_running_process() {
+        let started = start_process(StartProcess {
+            command: CommandArg::Multi(vec!["sleep".into(), "30".into()]),
+            ..Default::default()
+        });
+        let prcuuid = started.prcuuid.expect("prcuuid set");
+
+        let killed = kill_process(KillProcess { prcuuid: prcuuid.clone(), ..Default::default() });
+        assert!(killed.error.is_none());
+
+        let status = process_status(ProcessStatus { prcuuid: prcuuid.clone(), ..Default::default() });
+        assert!(!status.running);
+
+        let listed = list_processes(ListProcesses::default());
+        assert!(listed.processes.iter().any(|p| p.prcuuid == prcuuid && !p.running));
+    }
+
+    #[test]
+    fn test_sync_process_streaming_sends_lines() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let form = sync_process_streaming(
+            SyncProcess {
+                command: CommandArg::Single("echo one; echo two 1>&2; printf three".into()),
+                ..Default::default()
+            },
+            tx,
+        );
+        assert_eq!(form.stdout.as_deref(), Some("one\nthree"));
+        assert_eq!(form.stderr.as_deref(), Some("two\n"));
+
+        let chunks: Vec<OutputChunk> = rx.iter().collect();
+        assert!(chunks.contains(&OutputChunk::Stdout("one\n".into())));
+        assert!(chunks.contains(&OutputChunk::Stdout("three".into())));
+        assert!(chunks.contains(&OutputChunk::Stderr("two\n".into())));
+    }
+
+    #[test]
+    fn test_sync_process_cwd_env_and_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("pwd; echo $GREETING; cat".into()),
+            cwd: Some(dir.path().to_str().unwrap().to_string()),
+            env: [("GREETING".to_string(), "hello".to_string())].into(),
+            b64stdin: Some(B64.encode(b"from stdin")),
+            ..Default::default()
+        });
+        assert!(form.error.is_none());
+        let expected = format!("{}\nhello\nfrom stdin", dir.path().canonicalize().unwrap().display());
+        assert_eq!(form.stdout.as_deref(), Some(expected.as_str()));
+    }
+
+    #[test]
+    fn test_sync_process_clear_env() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Multi(vec!["/usr/bin/env".into()]),
+            clear_env: true,
+            env: [("ONLY".to_string(), "1".to_string())].into(),
+            ..Default::default()
+        });
+        assert_eq!(form.stdout.as_deref(), Some("ONLY=1\n"));
+    }
+
+    #[test]
+    fn test_sync_process_unknown_user() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("true".into()),
+            run_as_user: Some("no-such-user-stembot".into()),
+            ..Default::default()
+        });
+        assert!(form.error.is_some());
+    }
+
+    #[test]
+    fn test_sync_process_timeout_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("survivor");
+        let started = Instant::now();
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single(format!("(sleep 2; touch {}) & sleep 30", marker.display())),
+            timeout: 1,
+            ..Default::default()
+        });
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(form.timed_out);
+        assert_eq!(form.signal, Some(libc::SIGTERM as i64));
+        assert_eq!(form.status, None);
+
+        // The backgrounded grandchild must have been terminated with the group.
+        thread::sleep(Duration::from_secs(2));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_sync_process_sigkill_after_grace_period() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("trap '' TERM; sleep 30".into()),
+            timeout: 1,
+            ..Default::default()
+        });
+        assert!(form.timed_out);
+        assert_eq!(form.signal, Some(libc::SIGKILL as i64));
+    }
+
+    #[test]
+    fn test_sync_process_within_timeout() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("exit 2".into()),
+            ..Default::default()
+        });
+        assert!(!form.timed_out);
+        assert_eq!(form.signal, None);
+        assert_eq!(form.status, Some(2));
+    }
+
+    #[test]
+    fn test_sync_process_max_output_bytes() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("echo 0123456789; echo short 1>&2".into()),
+            max_output_bytes: Some(4),
+            ..Default::default()
+        });
+        assert_eq!(form.stdout.as_deref(), Some("0123"));
+        assert_eq!(form.stderr.as_deref(), Some("shor"));
+        assert!(form.output_truncated);
+    }
+
+    #[test]
+    fn test_sync_process_max_output_bytes_without_newlines() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("head -c 1000000 /dev/zero | tr '\\0' x".into()),
+            max_output_bytes: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(form.stdout.as_deref(), Some("xxxxxxxxxx"));
+        assert!(form.output_truncated);
+    }
+
+    #[test]
+    fn test_sync_process_rlimit_nofile() {
+        let form = sync_process(SyncProcess {
+            command: CommandArg::Single("ulimit -n".into()),
+            rlimit_nofile: Some(32),
+            ..Default::default()
+        });
+        assert_eq!(form.stdout.as_deref(), Some("32\n"));
+    }
+
+    #[test]
+    fn test_stricter_limit() {
+        assert_eq!(stricter(None, None), None);
+        assert_eq!(stricter(Some(5), None), Some(5));
+        assert_eq!(stricter(None, Some(7)), Some(7));
+        assert_eq!(stricter(Some(5), Some(7)), Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_process_slots_queue_in_order() {
+        let slots = Arc::new(ProcessSlots::new(1));
+        let held = slots.acquire(|_| async {}).await;
+
+        let positions = Arc::new(Mutex::new(Vec::new()));
+        let waiter = {
+            let slots = Arc::clone(&slots);
+            let positions = Arc::clone(&positions);
+            tokio::spawn(async move {
+                let _permit = slots
+                    .acquire(|p| {
+                        positions.lock().unwrap().push(p);
+                        async {}
+                    })
+                    .await;
+            })
+        };
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*positions.lock().unwrap(), vec![1]);
+
+        drop(held);
+        waiter.await.unwrap();
+        assert_eq!(*positions.lock().unwrap(), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_process_status_unknown_handle() {
+        let status = process_status(ProcessStatus { prcuuid: "no-such-process".into(), ..Default::default() });
+        assert!(status.error.is_some());
+    }
+}
@@ -0,0 +1,237 @@
+//! Reading the end of a file for `TailFile`.
+//!
+//! [`tail_file`] answers a one-off request for the last lines or bytes of a
+//! file, or everything after an offset.  A followed `TailFile` ticket then
+//! calls [`read_appended`] repeatedly to pick up what has been written since.
+//! Text is decoded as UTF-8; a multi-byte character cut off at the end of a
+//! read is left for the next read rather than mangled.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::models::control::TailFile;
+
+/// Most bytes returned by one read.
+pub const MAX_TAIL_BYTES: u64 = 1024 * 1024;
+
+const DEFAULT_TAIL_LINES: u64 = 10;
+
+/// Block size used when scanning backwards for line starts.
+const SCAN_BLOCK: u64 = 64 * 1024;
+
+/// Read the part of a file a [`TailFile`] form asks for.
+pub fn tail_file(mut form: TailFile) -> TailFile {
+    log::debug!("{}", form.path);
+    match tail_inner(&form) {
+        Ok((content, next_offset, size)) => {
+            form.content     = Some(content);
+            form.next_offset = Some(next_offset);
+            form.size        = Some(size);
+            form.error       = None;
+        }
+        Err(e) => {
+            form.content     = None;
+            form.next_offset = None;
+            form.size        = None;
+            form.error       = Some(e.to_string());
+        }
+    }
+    form
+}
+
+fn tail_inner(form: &TailFile) -> Result<(String, u64, u64)> {
+    let mut file = File::open(&form.path)?;
+    let size = file.metadata()?.len();
+    let start = match (form.offset, form.bytes) {
+        // An offset past the end means the file was truncated; start over.
+        (Some(offset), _) => if offset > size { 0 } else { offset },
+        (None, Some(bytes)) => size.saturating_sub(bytes),
+        (None, None) => {
+            let lines = form.lines.unwrap_or(DEFAULT_TAIL_LINES);
+            start_of_last_lines(&mut file, size, lines)?.max(size.saturating_sub(MAX_TAIL_BYTES))
+        }
+    };
+    let (content, next_offset) = read_range(&mut file, start, size)?;
+    Ok((content, next_offset, size))
+}
+
+/// Text appended to a file since an earlier read.
+#[derive(Debug)]
+pub struct Appended {
+    pub text:        String,
+    pub next_offset: u64,
+    /// The file had shrunk below the earlier offset and was read from the start.
+    pub truncated:   bool,
+}
+
+/// Read what has been appended to `path` after `offset`.
+pub fn read_appended(path: &Path, offset: u64) -> Result<Appended> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let truncated = size < offset;
+    let start = if truncated { 0 } else { offset };
+    let (text, next_offset) = read_range(&mut file, start, size)?;
+    Ok(Appended { text, next_offset, truncated })
+}
+
+/// Read at most [`MAX_TAIL_BYTES`] from `start` towards `end`, returning the
+/// text and the offset just past it.
+fn read_range(file: &mut File, start: u64, end: u64) -> Result<(String, u64)> {
+    let len = end.saturating_sub(start).min(MAX_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut buf)?;
+
+    // Hold back a character cut off at the end for the next read.
+    if let Err(e) = std::str::from_utf8(&buf) {
+        if e.error_len().is_none() {
+            buf.truncate(e.valid_up_to());
+        }
+    }
+    let next_offset = start + buf.len() as u64;
+    Ok((String::from_utf8_lossy(&buf).into_owned(), next_offset))
+}
+
+/// Offset at which the last `lines` lines of the file begin.  A newline that
+/// ends the file does not start another line.
+fn start_of_last_lines(file: &mut File, size: u64, lines: u64) -> Result<u64> {
+    if lines == 0 {
+        return Ok(size);
+    }
+
+    let mut newlines = 0;
+    let mut pos = size;
+    let mut buf = vec![0u8; SCAN_BLOCK as usize];
+    while pos > 0 && size - pos < MAX_TAIL_BYTES {
+        let n = SCAN_BLOCK.min(pos);
+        pos -= n;
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut buf[..n as usize])?;
+        for (i, &byte) in buf[..n as usize].iter().enumerate().rev() {
+            let at = pos + i as u64;
+            if byte == b'\n' && at + 1 != size {
+                newlines += 1;
+                if newlines == lines {
+                    return Ok(at + 1);
+                }
+            }
+        }
+    }
+    Ok(pos)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tail(path: &Path, lines: Option<u64>, bytes: Option<u64>, offset: Option<u64>) -> TailFile {
+        tail_file(TailFile {
+            path: path.to_string_lossy().into_owned(),
+            lines,
+            bytes,
+            offset,
+            ..Default::default()
+        })
+    }
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|i| format!("line {i}\n")).collect()
+    }
+
+    #[test]
+    fn test_tail_file_default_lines() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, numbered(25)).unwrap();
+
+        let form = tail(&path, None, None, None);
+        assert!(form.error.is_none());
+        assert_eq!(form.content.unwrap(), (16..=25).map(|i| format!("line {i}\n")).collect::<String>());
+        assert_eq!(form.next_offset, form.size);
+    }
+
+    #[test]
+    fn test_tail_file_lines_without_trailing_newline() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "a\nb\nc").unwrap();
+
+        assert_eq!(tail(&path, Some(2), None, None).content.unwrap(), "b\nc");
+        assert_eq!(tail(&path, Some(5), None, None).content.unwrap(), "a\nb\nc");
+        assert_eq!(tail(&path, Some(0), None, None).content.unwrap(), "");
+    }
+
+    #[test]
+    fn test_tail_file_lines_across_blocks() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let long = "x".repeat(SCAN_BLOCK as usize);
+        std::fs::write(&path, format!("first\n{long}\nlast\n")).unwrap();
+
+        assert_eq!(tail(&path, Some(2), None, None).content.unwrap(), format!("{long}\nlast\n"));
+    }
+
+    #[test]
+    fn test_tail_file_bytes_and_offset() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        assert_eq!(tail(&path, None, Some(4), None).content.unwrap(), "6789");
+        let form = tail(&path, None, None, Some(3));
+        assert_eq!(form.content.unwrap(), "3456789");
+        assert_eq!(form.next_offset, Some(10));
+        // An offset past the end reads the whole (truncated) file.
+        assert_eq!(tail(&path, None, None, Some(50)).content.unwrap(), "0123456789");
+    }
+
+    #[test]
+    fn test_tail_file_missing() {
+        let form = tail(Path::new("/nonexistent/app.log"), None, None, None);
+        assert!(form.error.is_some());
+        assert!(form.content.is_none());
+    }
+
+    #[test]
+    fn test_read_appended() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "one\n").unwrap();
+
+        let appended = read_appended(&path, 4).unwrap();
+        assert_eq!(appended.text, "");
+        assert_eq!(appended.next_offset, 4);
+
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let appended = read_appended(&path, 4).unwrap();
+        assert_eq!(appended.text, "two\n");
+        assert_eq!(appended.next_offset, 8);
+        assert!(!appended.truncated);
+
+        std::fs::write(&path, "new\n").unwrap();
+        let appended = read_appended(&path, 8).unwrap();
+        assert_eq!(appended.text, "new\n");
+        assert!(appended.truncated);
+    }
+
+    #[test]
+    fn test_read_appended_holds_back_split_character() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        let text = "caf\u{e9}\n".as_bytes();
+        std::fs::write(&path, &text[..4]).unwrap();
+
+        let appended = read_appended(&path, 0).unwrap();
+        assert_eq!(appended.text, "caf");
+        assert_eq!(appended.next_offset, 3);
+
+        std::fs::write(&path, text).unwrap();
+        let appended = read_appended(&path, appended.next_offset).unwrap();
+        assert_eq!(appended.text, "\u{e9}\n");
+    }
+}
@@ -0,0 +1,543 @@
+//! Chunked, resumable file transfers.
+//!
+//! Large files are moved in fixed-size chunks rather than in a single
+//! `WriteFile` form.  The receiving agent stages the chunks in a datastore
+//! [`File`] sequence named by the transfer's `trnuuid` and only moves the
+//! assembled file into place once every byte has arrived:
+//!
+//! 1. `TransferStatus` reports how many bytes are staged — the resume point.
+//! 2. `WriteChunk` appends one chunk after checking its digest.
+//! 3. `CommitTransfer` checks the staged size, writes the file next to its
+//!    destination and renames it into place.
+//!
+//! `ReadChunk` serves chunks from the sending agent's disk.  Chunks are
+//! compressed under the form's `encoding` and base64-encoded, as with
+//! `LoadFile`/`WriteFile`; checksums are hex
+//! digests of the uncompressed bytes under the form's `hash_algorithm`, with
+//! the MD5 `md5sum` kept as the default for Python compatibility.
+//!
+//! Each staged transfer has a `transfer` record holding the time its last
+//! chunk arrived; [`expire_transfers`] drops transfers abandoned for longer
+//! than [`TRANSFER_EXPIRY_SECS`].
+
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use serde_json::json;
+
+use crate::collections::open_transfers;
+use crate::dao::datastore::{delete_sequence, sequence_size, Datastore, File, CHUNK_SIZE};
+use crate::dao::Document;
+use crate::executor::compression::{compress_payload, decompress};
+use crate::executor::file::{
+    apply_metadata, check_digest, digest_hex, expected_digest, file_metadata, FileMetadata, Hasher,
+};
+use crate::models::control::{CommitTransfer, HashAlgorithm, ReadChunk, TransferStatus, WriteChunk};
+
+/// Bytes carried by each `ReadChunk`/`WriteChunk` form.
+pub const TRANSFER_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// The most a single `ReadChunk` reads; longer requests are clamped to it.
+pub const MAX_CHUNK_LENGTH: u64 = 16 * 1024 * 1024;
+
+/// How long a staged transfer is kept after its last chunk arrived.
+pub const TRANSFER_EXPIRY_SECS: f64 = 24.0 * 60.0 * 60.0;
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Path of the temporary file a transfer is assembled in before it is
+/// renamed to `path`.  It sits in the same directory so the rename is atomic.
+pub fn part_path(path: &Path, trnuuid: &str) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file path", path.display()))?;
+    Ok(path.with_file_name(format!(".{}.{}.part", name.to_string_lossy(), trnuuid)))
+}
+
+// ── ReadChunk ─────────────────────────────────────────────────────────────────
+
+/// Read the chunk at `form.offset` from disk into the form.
+///
+/// `form.length` is clamped to [`MAX_CHUNK_LENGTH`].
+pub fn read_chunk(mut form: ReadChunk) -> ReadChunk {
+    log::debug!("{}@{}", form.path, form.offset);
+    form.length = form.length.min(MAX_CHUNK_LENGTH);
+    let read = read_chunk_inner(&form).and_then(|(data, size)| {
+        let (encoding, compressed) = compress_payload(form.encoding, &data)?;
+        Ok((data, size, encoding, compressed))
+    });
+    match read {
+        Ok((data, size, encoding, compressed)) => {
+            let hash = digest_hex(form.hash_algorithm, &data);
+            form.md5sum   = (form.hash_algorithm == HashAlgorithm::Md5).then(|| hash.clone());
+            form.hash     = Some(hash);
+            form.size     = Some(size);
+            form.b64zlib  = Some(B64.encode(compressed));
+            form.encoding = encoding;
+            form.error    = None;
+            let FileMetadata { mode, uid, gid, mtime } = file_metadata(Path::new(&form.path));
+            form.mode    = mode;
+            form.uid     = uid;
+            form.gid     = gid;
+            form.mtime   = mtime;
+        }
+        Err(e) => {
+            form.b64zlib = None;
+            form.md5sum  = None;
+            form.hash    = None;
+            form.size    = None;
+            form.error   = Some(e.to_string());
+        }
+    }
+    form
+}
+
+fn read_chunk_inner(form: &ReadChunk) -> Result<(Vec<u8>, u64)> {
+    let mut file = std::fs::File::open(&form.path)?;
+    let size = file.metadata()?.len();
+    file.seek(SeekFrom::Start(form.offset))?;
+    let mut data = Vec::new();
+    file.take(form.length).read_to_end(&mut data)?;
+    Ok((data, size))
+}
+
+// ── WriteChunk ────────────────────────────────────────────────────────────────
+
+/// Stage a chunk in the `transfers` datastore.
+pub fn write_chunk(mut form: WriteChunk) -> WriteChunk {
+    match open_transfers() {
+        Ok(datastore) => write_chunk_in(&datastore, form),
+        Err(e) => {
+            form.error = Some(e.to_string());
+            form
+        }
+    }
+}
+
+fn write_chunk_in(datastore: &Datastore, mut form: WriteChunk) -> WriteChunk {
+    log::debug!("{}@{}", form.trnuuid, form.offset);
+    match stage_chunk(datastore, &form) {
+        Ok(received) => {
+            form.received = Some(received);
+            form.error    = None;
+        }
+        Err(e) => {
+            form.received = sequence_size(datastore, &form.trnuuid)
+                .ok()
+                .map(|size| size.unwrap_or(0) as u64);
+            form.error    = Some(e.to_string());
+        }
+    }
+    form.b64zlib = "".to_string();
+    form
+}
+
+fn stage_chunk(datastore: &Datastore, form: &WriteChunk) -> Result<u64> {
+    let data = decompress(form.encoding, &B64.decode(&form.b64zlib)?)?;
+    check_digest(Some(chunk_digest(form)), &data, &format!(" for chunk at offset {}", form.offset))?;
+
+    let staged = sequence_size(datastore, &form.trnuuid)?;
+    if form.offset == 0 && staged.is_some() {
+        // Starting over: drop whatever an earlier attempt staged.
+        File::new(Some(&form.trnuuid), datastore.clone())?.delete()?;
+    } else if form.offset != staged.unwrap_or(0) as u64 {
+        return Err(anyhow!(
+            "chunk offset {} does not match the {} bytes received",
+            form.offset, staged.unwrap_or(0)
+        ));
+    }
+
+    let mut file = File::new(Some(&form.trnuuid), datastore.clone())?;
+    file.append(&data)?;
+    file.close()?;
+    touch_transfer(datastore, &form.trnuuid)?;
+    Ok(file.size() as u64)
+}
+
+/// The digest a chunk must match: `hash` under `hash_algorithm` when set,
+/// otherwise the legacy `md5sum`.
+pub(crate) fn chunk_digest(form: &WriteChunk) -> (HashAlgorithm, &str) {
+    match form.hash {
+        Some(ref hash) => (form.hash_algorithm, hash),
+        None           => (HashAlgorithm::Md5, &form.md5sum),
+    }
+}
+
+/// The object ID of a transfer's `transfer` record.
+fn record_id(trnuuid: &str) -> String {
+    Document::get_uuid_from_str(&format!("transfer:{trnuuid}"))
+}
+
+/// Record that `trnuuid` was active just now.
+fn touch_transfer(datastore: &Datastore, trnuuid: &str) -> Result<()> {
+    datastore.commit_raw(&record_id(trnuuid), &json!({
+        "type":        "transfer",
+        "trnuuid":     trnuuid,
+        "update_time": unix_now(),
+    }))
+}
+
+// ── TransferStatus ────────────────────────────────────────────────────────────
+
+/// Report the number of bytes staged for a transfer; `0` if none are.
+pub fn transfer_status(mut form: TransferStatus) -> TransferStatus {
+    match open_transfers().and_then(|datastore| sequence_size(&datastore, &form.trnuuid)) {
+        Ok(size) => {
+            form.received = Some(size.unwrap_or(0) as u64);
+            form.error    = None;
+        }
+        Err(e) => form.error = Some(e.to_string()),
+    }
+    form
+}
+
+// ── CommitTransfer ────────────────────────────────────────────────────────────
+
+/// Assemble a staged transfer at `form.path` and drop the staged data.
+pub fn commit_transfer(mut form: CommitTransfer) -> CommitTransfer {
+    log::debug!("{} -> {}", form.trnuuid, form.path);
+    match open_transfers().and_then(|datastore| commit_inner(&datastore, &form)) {
+        Ok((algorithm, hash)) => {
+            form.md5sum         = (algorithm == HashAlgorithm::Md5).then(|| hash.clone());
+            form.hash_algorithm = algorithm;
+            form.hash           = Some(hash);
+            form.error          = None;
+        }
+        Err(e) => form.error = Some(e.to_string()),
+    }
+    form
+}
+
+fn commit_inner(datastore: &Datastore, form: &CommitTransfer) -> Result<(HashAlgorithm, String)> {
+    let staged = sequence_size(datastore, &form.trnuuid)?
+        .ok_or_else(|| anyhow!("no staged transfer {}", form.trnuuid))? as u64;
+    if staged != form.size {
+        return Err(anyhow!("transfer incomplete: {} of {} bytes received", staged, form.size));
+    }
+
+    let path = Path::new(&form.path);
+    let part = part_path(path, &form.trnuuid)?;
+    let mut file = File::new(Some(&form.trnuuid), datastore.clone())?;
+
+    let metadata = FileMetadata { mode: form.mode, uid: form.uid, gid: form.gid, mtime: form.mtime };
+    let expected = expected_digest(form.hash_algorithm, &form.hash, &form.md5sum);
+    let algorithm = expected.map_or(form.hash_algorithm, |(algorithm, _)| algorithm);
+    let hash = match assemble(&mut file, &part, path, &metadata, algorithm) {
+        Ok(hash) => hash,
+        Err(e) => {
+            let _ = std::fs::remove_file(&part);
+            return Err(e);
+        }
+    };
+    if let Some((_, expected)) = expected {
+        if hash != expected {
+            let _ = std::fs::remove_file(&part);
+            file.delete()?;
+            return Err(anyhow!(
+                "{} mismatch: expected {}, got {}",
+                algorithm.as_str().to_uppercase(), expected, hash
+            ));
+        }
+    }
+
+    if let Err(e) = std::fs::rename(&part, path) {
+        let _ = std::fs::remove_file(&part);
+        return Err(e.into());
+    }
+    file.delete()?;
+    datastore.delete_raw(&record_id(&form.trnuuid))?;
+    Ok((algorithm, hash))
+}
+
+/// Copy the staged bytes to `part` with the given metadata, falling back to
+/// that of the file at `path` it will replace, and return their digest under
+/// `algorithm`.
+fn assemble(
+    file: &mut File,
+    part: &Path,
+    path: &Path,
+    metadata: &FileMetadata,
+    algorithm: HashAlgorithm,
+) -> Result<String> {
+    let mut out = std::fs::File::create(part)?;
+    let mut hasher = Hasher::new(algorithm);
+    if file.size() > 0 {
+        file.seek(0)?;
+        loop {
+            let data = file.read(Some(CHUNK_SIZE))?;
+            if data.is_empty() {
+                break;
+            }
+            hasher.update(&data);
+            out.write_all(&data)?;
+        }
+    }
+    apply_metadata(&out, path, metadata)?;
+    out.sync_all()?;
+    Ok(hasher.finalize())
+}
+
+// ── Expiry ────────────────────────────────────────────────────────────────────
+
+/// Drop staged transfers whose last chunk arrived more than
+/// [`TRANSFER_EXPIRY_SECS`] ago.
+pub fn expire_transfers() -> Result<()> {
+    expire_transfers_in(&open_transfers()?, unix_now() - TRANSFER_EXPIRY_SECS)
+}
+
+fn expire_transfers_in(datastore: &Datastore, cutoff: f64) -> Result<()> {
+    // Transfers staged before records were kept get one now, and expire later.
+    for sequuid in datastore.find_objuuids(&[("type", "sequence")])? {
+        if datastore.get_raw(&record_id(&sequuid)).is_err() {
+            touch_transfer(datastore, &sequuid)?;
+        }
+    }
+
+    let cutoff = format!("$lt:{cutoff}");
+    for record in datastore.pop(&[("type", "transfer"), ("update_time", cutoff.as_str())])? {
+        let Some(trnuuid) = record.object["trnuuid"].as_str() else { continue };
+        log::warn!("expiring abandoned transfer {trnuuid}");
+        if sequence_size(datastore, trnuuid)?.is_some() {
+            delete_sequence(datastore, trnuuid)?;
+        }
+    }
+    Ok(())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::collection::Collection;
+    use crate::executor::file::{md5hex, zlib_compress, zlib_decompress};
+    use crate::models::control::Encoding;
+
+    fn make_datastore() -> Datastore {
+        let conn = format!("file:{}?mode=memory&cache=shared", uuid::Uuid::new_v4());
+        let col = Collection::new(&format!("ds-{}", uuid::Uuid::new_v4()), Some(&conn)).unwrap();
+        col.create_attribute("type", "/type").unwrap();
+        col.create_attribute("update_time", "/update_time").unwrap();
+        col
+    }
+
+    fn chunk_form(trnuuid: &str, offset: u64, data: &[u8]) -> WriteChunk {
+        WriteChunk {
+            trnuuid: trnuuid.to_string(),
+            offset,
+            b64zlib: B64.encode(zlib_compress(data).unwrap()),
+            md5sum:  md5hex(data),
+            ..Default::default()
+        }
+    }
+
+    fn commit_form(trnuuid: &str, path: &Path, size: u64) -> CommitTransfer {
+        CommitTransfer {
+            trnuuid: trnuuid.to_string(),
+            path:    path.to_string_lossy().into_owned(),
+            size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_read_chunk_reads_range_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("src.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let form = read_chunk(ReadChunk {
+            path: path.to_string_lossy().into_owned(),
+            offset: 8,
+            length: 4,
+            ..Default::default()
+        });
+        assert!(form.error.is_none());
+        assert_eq!(form.size, Some(10));
+        assert_eq!(form.md5sum.as_deref(), Some(md5hex(b"89").as_str()));
+        let data = zlib_decompress(&B64.decode(form.b64zlib.unwrap()).unwrap()).unwrap();
+        assert_eq!(data, b"89");
+    }
+
+    #[test]
+    fn test_read_chunk_clamps_length() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("src.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let form = read_chunk(ReadChunk {
+            path: path.to_string_lossy().into_owned(),
+            length: u64::MAX,
+            ..Default::default()
+        });
+        assert!(form.error.is_none());
+        assert_eq!(form.length, MAX_CHUNK_LENGTH);
+    }
+
+    #[test]
+    fn test_read_chunk_stages_under_requested_encoding() {
+        let ds = make_datastore();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("src.txt");
+        let data = "compressible line\n".repeat(100);
+        std::fs::write(&path, &data).unwrap();
+
+        let read = read_chunk(ReadChunk {
+            path: path.to_string_lossy().into_owned(),
+            length: 4096,
+            encoding: Encoding::Zstd,
+            ..Default::default()
+        });
+        assert_eq!(read.encoding, Encoding::Zstd);
+
+        let form = write_chunk_in(&ds, WriteChunk {
+            trnuuid: "t1".to_string(),
+            b64zlib: read.b64zlib.unwrap(),
+            encoding: read.encoding,
+            md5sum: read.md5sum.unwrap(),
+            ..Default::default()
+        });
+        assert!(form.error.is_none());
+        assert_eq!(form.received, Some(data.len() as u64));
+    }
+
+    #[test]
+    fn test_expire_transfers_drops_abandoned_chunks() {
+        let ds = make_datastore();
+        write_chunk_in(&ds, chunk_form("t1", 0, b"abc"));
+
+        expire_transfers_in(&ds, unix_now() - 60.0).unwrap();
+        assert_eq!(sequence_size(&ds, "t1").unwrap(), Some(3));
+
+        expire_transfers_in(&ds, unix_now() + 1.0).unwrap();
+        assert_eq!(sequence_size(&ds, "t1").unwrap(), None);
+        assert!(ds.find_objuuids(&[("type", "transfer")]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chunks_commit_to_path() {
+        let ds = make_datastore();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dst.bin");
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 7).map(|i| (i % 251) as u8).collect();
+        let (head, tail) = data.split_at(CHUNK_SIZE + 3);
+
+        let form = write_chunk_in(&ds, chunk_form("t1", 0, head));
+        assert!(form.error.is_none());
+        assert_eq!(form.received, Some(head.len() as u64));
+        assert!(form.b64zlib.is_empty());
+        let form = write_chunk_in(&ds, chunk_form("t1", head.len() as u64, tail));
+        assert_eq!(form.received, Some(data.len() as u64));
+
+        let committed = commit_inner(&ds, &commit_form("t1", &path, data.len() as u64)).unwrap();
+        assert_eq!(committed, (HashAlgorithm::Md5, md5hex(&data)));
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        assert_eq!(sequence_size(&ds, "t1").unwrap(), None);
+        assert!(!part_path(&path, "t1").unwrap().exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commit_applies_metadata() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ds = make_datastore();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dst.bin");
+        write_chunk_in(&ds, chunk_form("t1", 0, b"abc"));
+
+        let mut form = commit_form("t1", &path, 3);
+        form.mode  = Some(0o640);
+        form.mtime = Some(1_000_000.0);
+        commit_inner(&ds, &form).unwrap();
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o7777, 0o640);
+        assert_eq!(file_metadata(&path).mtime, Some(1_000_000.0));
+    }
+
+    #[test]
+    fn test_write_chunk_rejects_offset_gap() {
+        let ds = make_datastore();
+        write_chunk_in(&ds, chunk_form("t1", 0, b"abc"));
+        let form = write_chunk_in(&ds, chunk_form("t1", 10, b"def"));
+        assert!(form.error.is_some());
+        assert_eq!(form.received, Some(3));
+    }
+
+    #[test]
+    fn test_write_chunk_rejects_bad_md5() {
+        let ds = make_datastore();
+        let mut form = chunk_form("t1", 0, b"abc");
+        form.md5sum = md5hex(b"abd");
+        let form = write_chunk_in(&ds, form);
+        assert!(form.error.unwrap().contains("MD5 mismatch"));
+        assert_eq!(form.received, Some(0));
+    }
+
+    #[test]
+    fn test_chunks_checked_with_sha256() {
+        let ds = make_datastore();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dst.bin");
+
+        let mut form = chunk_form("t1", 0, b"abc");
+        form.md5sum         = String::new();
+        form.hash_algorithm = HashAlgorithm::Sha256;
+        form.hash           = Some(digest_hex(HashAlgorithm::Sha256, b"abd"));
+        let rejected = write_chunk_in(&ds, form.clone());
+        assert!(rejected.error.unwrap().contains("SHA256 mismatch"));
+
+        form.hash = Some(digest_hex(HashAlgorithm::Sha256, b"abc"));
+        assert!(write_chunk_in(&ds, form).error.is_none());
+
+        let mut commit = commit_form("t1", &path, 3);
+        commit.hash_algorithm = HashAlgorithm::Sha256;
+        commit.hash           = Some(digest_hex(HashAlgorithm::Sha256, b"abc"));
+        let committed = commit_inner(&ds, &commit).unwrap();
+        assert_eq!(committed, (HashAlgorithm::Sha256, digest_hex(HashAlgorithm::Sha256, b"abc")));
+        assert_eq!(std::fs::read(&path).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_write_chunk_at_zero_starts_over() {
+        let ds = make_datastore();
+        write_chunk_in(&ds, chunk_form("t1", 0, b"abcdef"));
+        let form = write_chunk_in(&ds, chunk_form("t1", 0, b"xy"));
+        assert_eq!(form.received, Some(2));
+    }
+
+    #[test]
+    fn test_commit_rejects_incomplete_transfer() {
+        let ds = make_datastore();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dst.bin");
+        write_chunk_in(&ds, chunk_form("t1", 0, b"abc"));
+
+        let err = commit_inner(&ds, &commit_form("t1", &path, 6)).unwrap_err();
+        assert!(err.to_string().contains("3 of 6"));
+        assert!(!path.exists());
+        assert_eq!(sequence_size(&ds, "t1").unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_commit_rejects_md5_mismatch() {
+        let ds = make_datastore();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dst.bin");
+        write_chunk_in(&ds, chunk_form("t1", 0, b"abc"));
+
+        let mut form = commit_form("t1", &path, 3);
+        form.md5sum = Some(md5hex(b"abd"));
+        assert!(commit_inner(&ds, &form).is_err());
+        assert!(!path.exists());
+        assert!(!part_path(&path, "t1").unwrap().exists());
+    }
+}
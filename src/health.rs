@@ -0,0 +1,138 @@
+//! Unauthenticated `/health` and `/ready` probes for orchestrators.
+//!
+//! Both report whether the collections open and answer a query and whether
+//! the scheduler loop has ticked recently; `/ready` also requires a peer to
+//! have been heard from or delivered to within `peer_timeout_secs`.  Each
+//! answers `200` when every check passes and `503` otherwise, with the
+//! details as JSON.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::HttpResponse;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::collections::{
+    open_kvstore, open_messages, open_peers, open_routes, open_tickets, open_traces, open_transfers,
+};
+use crate::config::config;
+use crate::metrics::peers_seen;
+
+/// How long the scheduler loop may go without ticking before it is unhealthy.
+pub const SCHEDULER_STALE_AFTER: Duration = Duration::from_secs(30);
+
+static SCHEDULER_TICK: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Note that the scheduler loop has just run its pending jobs.
+pub fn scheduler_ticked() {
+    *SCHEDULER_TICK.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Open each collection and run a one-row query against it.
+fn check_collections() -> Value {
+    fn probe(result: Result<usize>) -> Option<String> {
+        result.err().map(|e| e.to_string())
+    }
+    let errors: BTreeMap<&str, String> = [
+        ("kvstore",   probe(open_kvstore().and_then(|c| c.find_limited(&[], 1)).map(|r| r.len()))),
+        ("messages",  probe(open_messages().and_then(|c| c.find_limited(&[], 1)).map(|r| r.len()))),
+        ("tickets",   probe(open_tickets().and_then(|c| c.find_limited(&[], 1)).map(|r| r.len()))),
+        ("traces",    probe(open_traces().and_then(|c| c.find_limited(&[], 1)).map(|r| r.len()))),
+        ("peers",     probe(open_peers().and_then(|c| c.find_limited(&[], 1)).map(|r| r.len()))),
+        ("routes",    probe(open_routes().and_then(|c| c.find_limited(&[], 1)).map(|r| r.len()))),
+        ("transfers", probe(open_transfers().and_then(|c| c.find_objuuids(&[])).map(|r| r.len()))),
+    ]
+    .into_iter()
+    .filter_map(|(name, error)| error.map(|e| (name, e)))
+    .collect();
+    json!({ "ok": errors.is_empty(), "errors": errors })
+}
+
+fn check_scheduler(last_tick: Option<Instant>, stale_after: Duration) -> Value {
+    let age = last_tick.map(|t| t.elapsed());
+    json!({
+        "ok":                 age.is_some_and(|a| a <= stale_after),
+        "last_tick_secs_ago": age.map(|a| a.as_secs_f64()),
+    })
+}
+
+fn check_peers(seen: &BTreeMap<String, f64>, now: f64, timeout_secs: f64) -> Value {
+    let reachable: Vec<&String> = seen
+        .iter()
+        .filter(|(_, last_seen)| now - **last_seen <= timeout_secs)
+        .map(|(agtuuid, _)| agtuuid)
+        .collect();
+    json!({ "ok": !reachable.is_empty(), "reachable": reachable })
+}
+
+fn report(checks: BTreeMap<&str, Value>) -> HttpResponse {
+    let ok = checks.values().all(|c| c["ok"] == json!(true));
+    let body = json!({
+        "status":  if ok { "ok" } else { "fail" },
+        "agtuuid": config().agtuuid,
+        "checks":  checks,
+    });
+    if ok {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+fn liveness_checks() -> BTreeMap<&'static str, Value> {
+    let last_tick = *SCHEDULER_TICK.lock().unwrap_or_else(|e| e.into_inner());
+    BTreeMap::from([
+        ("collections", check_collections()),
+        ("scheduler",   check_scheduler(last_tick, SCHEDULER_STALE_AFTER)),
+    ])
+}
+
+/// Handler for the `/health` endpoint.
+pub async fn health_handler() -> HttpResponse {
+    report(liveness_checks())
+}
+
+/// Handler for the `/ready` endpoint.
+pub async fn ready_handler() -> HttpResponse {
+    let mut checks = liveness_checks();
+    checks.insert(
+        "peers",
+        check_peers(&peers_seen(), unix_now(), config().peer_timeout_secs as f64),
+    );
+    report(checks)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_scheduler() {
+        let stale = Duration::from_secs(30);
+        assert_eq!(check_scheduler(Some(Instant::now()), stale)["ok"], json!(true));
+        assert_eq!(check_scheduler(None, stale)["ok"], json!(false));
+        let old = Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+        assert_eq!(check_scheduler(Some(old), stale)["ok"], json!(false));
+    }
+
+    #[test]
+    fn test_check_peers() {
+        let seen = BTreeMap::from([("a1".to_string(), 1000.0), ("a2".to_string(), 900.0)]);
+        let check = check_peers(&seen, 1030.0, 60.0);
+        assert_eq!(check["ok"], json!(true));
+        assert_eq!(check["reachable"], json!(["a1"]));
+        assert_eq!(check_peers(&seen, 2000.0, 60.0)["ok"], json!(false));
+        assert_eq!(check_peers(&BTreeMap::new(), 0.0, 60.0)["ok"], json!(false));
+    }
+}
@@ -1,11 +1,14 @@
 pub mod cli;
 pub mod collections;
 pub mod executor;
+pub mod health;
 pub mod dao;
 pub mod messaging;
+pub mod metrics;
 pub mod models;
 pub mod peering;
 pub mod processor;
 pub mod config;
 pub mod logger;
 pub mod ticketing;
+pub mod topology;
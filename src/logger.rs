@@ -1,39 +1,507 @@
-use chrono::Utc;
-use log::Level;
-use std::{io::Write, path::Path, str::FromStr};
-
-pub fn init_logger(app_loglevel: String, api_loglevel: String) {
-    let api_filter = Level::from_str(&api_loglevel)
-        .expect("invalid API log level filter specified")
-        .to_level_filter();
-
-    env_logger::builder()
-        .filter_level(
-            Level::from_str(&app_loglevel)
-                .expect("invalid log level filter specified")
-                .to_level_filter(),
-        )
-        .filter_module("actix_web", api_filter)
-        .filter_module("actix_server", api_filter)
-        .filter_module("actix_http", api_filter)
-        .filter_module("tracing_actix_web", api_filter)
-        .format(move |buf, record| {
-            let short_filename = record
-                .file()
-                .and_then(|f| Path::new(f).file_name())
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
-            writeln!(
-                buf,
-                "{}|{}|{}:L{}|{}|{}",
-                record.level(),
-                Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                short_filename,
-                record.line().unwrap_or(0),
-                record.target(),
-                record.args()
-            )
-        })
+//! Agent logging.
+//!
+//! Records go to stderr through `env_logger` (so `RUST_LOG` still applies),
+//! to an optional size-rotated log file, and into a buffer of recent entries
+//! that `GetLogs` serves.  Levels can change at runtime: the app and API
+//! levels follow the config, and `SetLogLevel` can override single modules.
+//!
+//! Key-value pairs on a record (`log::info!(tckuuid = id; "...")`) become the
+//! entry's `fields`; the ticket and forwarding paths use the keys `tckuuid`,
+//! `message_type`, `src`, `dest` and `peer_url`.  Lines are written as text
+//! or, with `log_format` set to `json`, as one JSON object each.
+
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use log::kv::{self, VisitSource, VisitValue};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Mutex, OnceLock, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::models::config::{Config, LogFormat, LogLevel};
+use crate::models::control::{GetLogs, LogEntry, SetLogLevel};
+
+/// Targets governed by the API log level rather than the application's.
+const API_TARGETS: &[&str] = &["actix_web", "actix_server", "actix_http", "tracing_actix_web"];
+
+/// Entries kept for `GetLogs`.
+pub const RECENT_LOG_ENTRIES: usize = 10_000;
+
+const DEFAULT_GET_LOGS_LINES: u64 = 100;
+
+struct Filters {
+    app:     LogLevel,
+    api:     LogLevel,
+    /// Per-module overrides; the longest matching module path wins.
+    modules: BTreeMap<String, LogLevel>,
+}
+
+impl Filters {
+    fn filter_for(&self, target: &str) -> LevelFilter {
+        let module = self
+            .modules
+            .iter()
+            .filter(|(module, _)| in_module(target, module))
+            .max_by_key(|(module, _)| module.len());
+        match module {
+            Some((_, level)) => level.to_level_filter(),
+            None if API_TARGETS.iter().any(|t| in_module(target, t)) => self.api.to_level_filter(),
+            None => self.app.to_level_filter(),
+        }
+    }
+
+    fn max_filter(&self) -> LevelFilter {
+        self.modules
+            .values()
+            .map(LogLevel::to_level_filter)
+            .fold(self.app.to_level_filter().max(self.api.to_level_filter()), Ord::max)
+    }
+}
+
+static FILTERS: RwLock<Filters> = RwLock::new(Filters {
+    app:     LogLevel::Info,
+    api:     LogLevel::Info,
+    modules: BTreeMap::new(),
+});
+
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+static LOG_FILE: Mutex<Option<RotatingFile>> = Mutex::new(None);
+
+static FORMAT: RwLock<LogFormat> = RwLock::new(LogFormat::Text);
+
+/// This agent's id, added to every JSON line.
+static AGTUUID: OnceLock<String> = OnceLock::new();
+
+/// Whether `target` is `module` or one of its submodules.
+fn in_module(target: &str, module: &str) -> bool {
+    target.strip_prefix(module).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Collects a record's key-value pairs as strings, skipping `None` values.
+struct FieldCollector<'a>(&'a mut BTreeMap<String, String>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        struct Text(Option<String>);
+        impl<'v> VisitValue<'v> for Text {
+            fn visit_any(&mut self, value: kv::Value) -> Result<(), kv::Error> {
+                self.0 = Some(value.to_string());
+                Ok(())
+            }
+            fn visit_null(&mut self) -> Result<(), kv::Error> {
+                Ok(())
+            }
+        }
+
+        let mut text = Text(None);
+        value.visit(&mut text)?;
+        if let Some(text) = text.0 {
+            self.0.insert(key.to_string(), text);
+        }
+        Ok(())
+    }
+}
+
+fn entry_of(record: &Record) -> LogEntry {
+    let mut fields = BTreeMap::new();
+    // Collecting into a map cannot fail.
+    let _ = record.key_values().visit(&mut FieldCollector(&mut fields));
+    LogEntry {
+        time:    unix_now(),
+        level:   record.level().to_string(),
+        module:  record.target().to_string(),
+        file:    record
+            .file()
+            .and_then(|f| Path::new(f).file_name())
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        line:    record.line().unwrap_or(0),
+        message: record.args().to_string(),
+        fields,
+    }
+}
+
+fn datetime(time: f64) -> Option<DateTime<Utc>> {
+    let secs  = time.floor() as i64;
+    let nanos = ((time - secs as f64) * 1_000_000_000.0) as u32;
+    Utc.timestamp_opt(secs, nanos).single()
+}
+
+/// Format an entry as a pipe-delimited text line, with its fields appended
+/// to the message as `key=value` pairs.
+pub fn format_entry(entry: &LogEntry) -> String {
+    let time = datetime(entry.time)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_default();
+    let mut line = format!(
+        "{}|{}|{}:L{}|{}|{}",
+        entry.level, time, entry.file, entry.line, entry.module, entry.message
+    );
+    for (key, value) in &entry.fields {
+        if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+            line.push_str(&format!(" {key}={value:?}"));
+        } else {
+            line.push_str(&format!(" {key}={value}"));
+        }
+    }
+    line
+}
+
+/// Format an entry as a single-line JSON object.  Fields become top-level
+/// keys, but never replace the standard ones.
+pub fn format_entry_json(entry: &LogEntry) -> String {
+    let mut line: Map<String, Value> = entry
+        .fields
+        .iter()
+        .map(|(key, value)| (key.clone(), json!(value)))
+        .collect();
+    let time = datetime(entry.time).map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true));
+    line.insert("time".into(), json!(time));
+    line.insert("level".into(), json!(entry.level));
+    if let Some(agtuuid) = AGTUUID.get() {
+        line.insert("agtuuid".into(), json!(agtuuid));
+    }
+    line.insert("module".into(), json!(entry.module));
+    line.insert("file".into(), json!(entry.file));
+    line.insert("line".into(), json!(entry.line));
+    line.insert("message".into(), json!(entry.message));
+    Value::Object(line).to_string()
+}
+
+/// Format an entry in the configured `log_format`.
+fn render(entry: &LogEntry) -> String {
+    match *FORMAT.read().unwrap_or_else(|e| e.into_inner()) {
+        LogFormat::Text => format_entry(entry),
+        LogFormat::Json => format_entry_json(entry),
+    }
+}
+
+// ── Rotating file sink ────────────────────────────────────────────────────────
+
+/// A log file that is renamed to `{path}.1` (shifting older files up to
+/// `{path}.{count}`) once it would grow past `max_bytes`.
+struct RotatingFile {
+    path:      PathBuf,
+    max_bytes: u64,
+    count:     u32,
+    file:      File,
+    size:      u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, count: u32) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, max_bytes, count, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.max_bytes > 0 && self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotated(&self, n: u32) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.count == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for n in (1..self.count).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+// ── Logger ────────────────────────────────────────────────────────────────────
+
+/// `env_logger` output behind level filters that can change at runtime.
+struct ReloadableLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let filter = FILTERS.read().unwrap_or_else(|e| e.into_inner()).filter_for(metadata.target());
+        metadata.level() <= filter && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let entry = entry_of(record);
+        if let Some(file) = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            // Nowhere better to report a failing log file than stderr.
+            if let Err(e) = file.write_line(&render(&entry)) {
+                eprintln!("log file error: {e}");
+            }
+        }
+        self.inner.log(record);
+
+        let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == RECENT_LOG_ENTRIES {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+        if let Some(file) = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+pub fn init_logger(config: &Config) {
+    let inner = env_logger::builder()
+        .filter_level(LevelFilter::Trace)
+        .format(|buf, record| writeln!(buf, "{}", render(&entry_of(record))))
         .parse_default_env()
-        .init();
+        .build();
+
+    let _ = AGTUUID.set(config.agtuuid.clone());
+    set_log_format(config.log_format);
+
+    if !config.log_file.is_empty() {
+        match RotatingFile::open(config.log_file.clone().into(), config.log_file_max_bytes, config.log_file_count) {
+            Ok(file) => *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file),
+            Err(e) => eprintln!("cannot open log file {}: {e}", config.log_file),
+        }
+    }
+
+    log::set_boxed_logger(Box::new(ReloadableLogger { inner }))
+        .expect("logger already initialized");
+    set_log_levels(&config.log_level_app, &config.log_level_api);
+}
+
+/// Change the application and API log levels of the running logger.
+/// Module overrides from `SetLogLevel` are kept.
+pub fn set_log_levels(app_loglevel: &LogLevel, api_loglevel: &LogLevel) {
+    let mut filters = FILTERS.write().unwrap_or_else(|e| e.into_inner());
+    filters.app = app_loglevel.clone();
+    filters.api = api_loglevel.clone();
+    log::set_max_level(filters.max_filter());
+}
+
+/// Change the layout of the lines the running logger writes.
+pub fn set_log_format(format: LogFormat) {
+    *FORMAT.write().unwrap_or_else(|e| e.into_inner()) = format;
+}
+
+// ── Control forms ─────────────────────────────────────────────────────────────
+
+/// Apply a [`SetLogLevel`] form and report the levels now in effect.
+///
+/// The changes last until the agent restarts or its config is reloaded;
+/// use `SetConfig` to change the app and API levels for good.
+pub fn set_log_level(mut form: SetLogLevel) -> SetLogLevel {
+    let parse = |level: &str| level.parse::<LogLevel>();
+    let app = form.app.as_deref().map(parse).transpose();
+    let api = form.api.as_deref().map(parse).transpose();
+    let modules: Result<Vec<_>, _> = form
+        .modules
+        .iter()
+        .map(|(module, level)| level.as_deref().map(parse).transpose().map(|l| (module.clone(), l)))
+        .collect();
+
+    let (app, api, modules) = match (app, api, modules) {
+        (Ok(app), Ok(api), Ok(modules)) => (app, api, modules),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            form.error = Some(e);
+            return form;
+        }
+    };
+
+    let mut filters = FILTERS.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(app) = app {
+        filters.app = app;
+    }
+    if let Some(api) = api {
+        filters.api = api;
+    }
+    for (module, level) in modules {
+        match level {
+            Some(level) => filters.modules.insert(module, level),
+            None        => filters.modules.remove(&module),
+        };
+    }
+    log::set_max_level(filters.max_filter());
+
+    form.app     = Some(filters.app.to_string());
+    form.api     = Some(filters.api.to_string());
+    form.modules = filters
+        .modules
+        .iter()
+        .map(|(module, level)| (module.clone(), Some(level.to_string())))
+        .collect();
+    form.error   = None;
+    form
+}
+
+/// Fill a [`GetLogs`] form with the most recent matching entries, oldest first.
+pub fn get_logs(mut form: GetLogs) -> GetLogs {
+    let min_level = match form.level.as_deref().map(str::parse::<LogLevel>).transpose() {
+        Ok(level) => level.map(|l| l.to_level_filter()),
+        Err(e) => {
+            form.error = Some(e);
+            return form;
+        }
+    };
+    let lines = form.lines.unwrap_or(DEFAULT_GET_LOGS_LINES) as usize;
+
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|e| min_level.is_none_or(|min| Level::from_str(&e.level).is_ok_and(|l| l <= min)))
+        .filter(|e| form.module.as_deref().is_none_or(|m| in_module(&e.module, m)))
+        .filter(|e| form.since.is_none_or(|since| e.time >= since))
+        .filter(|e| form.until.is_none_or(|until| e.time <= until))
+        .take(lines)
+        .cloned()
+        .collect();
+    entries.reverse();
+
+    form.entries = entries;
+    form.error   = None;
+    form
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_module() {
+        assert!(in_module("stembot_rust::processor", "stembot_rust"));
+        assert!(in_module("stembot_rust", "stembot_rust"));
+        assert!(!in_module("stembot_rust_extra", "stembot_rust"));
+        assert!(!in_module("actix_web", "stembot_rust"));
+    }
+
+    #[test]
+    fn test_filters_module_override() {
+        let filters = Filters {
+            app:     LogLevel::Info,
+            api:     LogLevel::Warning,
+            modules: BTreeMap::from([
+                ("stembot_rust".to_string(), LogLevel::Error),
+                ("stembot_rust::processor".to_string(), LogLevel::Debug),
+            ]),
+        };
+        assert_eq!(filters.filter_for("stembot_rust::processor"), LevelFilter::Debug);
+        assert_eq!(filters.filter_for("stembot_rust::messaging"), LevelFilter::Error);
+        assert_eq!(filters.filter_for("actix_web::middleware"), LevelFilter::Warn);
+        assert_eq!(filters.filter_for("agt_server"), LevelFilter::Info);
+        assert_eq!(filters.max_filter(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let entry = LogEntry {
+            time:    0.5,
+            level:   "INFO".into(),
+            module:  "stembot_rust::processor".into(),
+            file:    "processor.rs".into(),
+            line:    42,
+            message: "hello".into(),
+            fields:  BTreeMap::from([
+                ("tckuuid".to_string(), "t1".to_string()),
+                ("peer_url".to_string(), "http://a b".to_string()),
+            ]),
+        };
+        assert_eq!(
+            format_entry(&entry),
+            "INFO|1970-01-01 00:00:00.500|processor.rs:L42|stembot_rust::processor|hello \
+             peer_url=\"http://a b\" tckuuid=t1"
+        );
+    }
+
+    #[test]
+    fn test_format_entry_json() {
+        let entry = LogEntry {
+            time:    0.5,
+            level:   "WARN".into(),
+            module:  "stembot_rust::messaging".into(),
+            file:    "messaging.rs".into(),
+            line:    7,
+            message: "no route".into(),
+            fields:  BTreeMap::from([
+                ("dest".to_string(), "a2".to_string()),
+                ("level".to_string(), "spoofed".to_string()),
+            ]),
+        };
+        let line: Value = serde_json::from_str(&format_entry_json(&entry)).unwrap();
+        assert_eq!(line["time"], "1970-01-01T00:00:00.500Z");
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["dest"], "a2");
+        assert_eq!(line["message"], "no route");
+        assert_eq!(line["line"], 7);
+    }
+
+    #[test]
+    fn test_entry_of_collects_fields() {
+        let tckuuid = Some("t1");
+        let src: Option<&str> = None;
+        let kvs: [(&str, Option<&str>); 2] = [("tckuuid", tckuuid), ("src", src)];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("stembot_rust::processor")
+            .args(format_args!("ticket_request"))
+            .key_values(&kvs)
+            .build();
+        let entry = entry_of(&record);
+        assert_eq!(entry.fields, BTreeMap::from([("tckuuid".to_string(), "t1".to_string())]));
+        assert_eq!(entry.message, "ticket_request");
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir  = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        for line in ["one", "two", "three", "four"] {
+            file.write_line(line).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "four\n");
+        assert_eq!(std::fs::read_to_string(dir.path().join("agent.log.1")).unwrap(), "three\n");
+        assert_eq!(std::fs::read_to_string(dir.path().join("agent.log.2")).unwrap(), "one\ntwo\n");
+        assert!(!dir.path().join("agent.log.3").exists());
+    }
 }
@@ -13,7 +13,9 @@ use anyhow::Result;
 
 use crate::collections::{open_messages, open_peers, open_routes};
 use crate::executor::agent::AgentClient;
+use crate::metrics;
 use crate::models::network::{NetworkMessage, NetworkMessagesRequest};
+use crate::peering::peer_encoding;
 use crate::config::config;
 
 fn unix_now() -> f64 {
@@ -29,7 +31,10 @@ fn unix_now() -> f64 {
 ///
 /// Mirrors `push_network_message(message)`.
 pub fn push_network_message(message: NetworkMessage) -> Result<()> {
-    log::debug!("{}", message.message_type());
+    log::debug!(
+        message_type = message.message_type(), tckuuid = message.tckuuid(), dest = dest_of(&message).as_str();
+        "{}", message.message_type()
+    );
     open_messages()?.upsert_object(message)?;
     Ok(())
 }
@@ -248,11 +253,26 @@ pub async fn forward_network_message(message: NetworkMessage) -> Result<()> {
         if let Some(url) = peer_obj.object.url.clone() {
             let client = AgentClient::with_credentials(
                 url.clone(), config().key(), config().agtuuid.clone(),
+            )
+            .with_encoding(peer_encoding(&peer_obj.object));
+            log::debug!(
+                message_type = message.message_type(), tckuuid = message.tckuuid(),
+                dest = dest.as_str(), peer_url = url.as_str();
+                "sending to peer"
             );
             match client.send_network_message(message.clone()).await {
-                Ok(resp) => log_ack_error(&resp),
+                Ok(resp) => {
+                    metrics::inc("stembot_forward_total", &[("peer", &dest), ("result", "success")]);
+                    metrics::peer_seen(&dest);
+                    log_ack_error(&resp)
+                }
                 Err(e) => {
-                    log::error!("Failed to send to {}: {}", url, e);
+                    metrics::inc("stembot_forward_total", &[("peer", &dest), ("result", "failure")]);
+                    log::error!(
+                        message_type = message.message_type(), tckuuid = message.tckuuid(),
+                        dest = dest.as_str(), peer_url = url.as_str();
+                        "Failed to send to {}: {}", url, e
+                    );
                     push_network_message(message)?;
                 }
             }
@@ -277,11 +297,26 @@ pub async fn forward_network_message(message: NetworkMessage) -> Result<()> {
             if let Some(url) = peer_obj.object.url.clone() {
                 let client = AgentClient::with_credentials(
                     url.clone(), config().key(), config().agtuuid.clone(),
+                )
+                .with_encoding(peer_encoding(&peer_obj.object));
+                log::debug!(
+                    message_type = message.message_type(), tckuuid = message.tckuuid(),
+                    dest = dest.as_str(), gtwuuid = gtwuuid.as_str(), peer_url = url.as_str();
+                    "sending to gateway"
                 );
                 match client.send_network_message(message.clone()).await {
-                    Ok(resp) => log_ack_error(&resp),
+                    Ok(resp) => {
+                        metrics::inc("stembot_forward_total", &[("peer", gtwuuid), ("result", "success")]);
+                        metrics::peer_seen(gtwuuid);
+                        log_ack_error(&resp)
+                    }
                     Err(e) => {
-                        log::error!("Failed to send to gateway {}: {}", url, e);
+                        metrics::inc("stembot_forward_total", &[("peer", gtwuuid), ("result", "failure")]);
+                        log::error!(
+                            message_type = message.message_type(), tckuuid = message.tckuuid(),
+                            dest = dest.as_str(), gtwuuid = gtwuuid.as_str(), peer_url = url.as_str();
+                            "Failed to send to gateway {}: {}", url, e
+                        );
                         push_network_message(message)?;
                     }
                 }
@@ -291,6 +326,10 @@ pub async fn forward_network_message(message: NetworkMessage) -> Result<()> {
     }
 
     // No route found — re-queue for later delivery
+    log::debug!(
+        message_type = message.message_type(), tckuuid = message.tckuuid(), dest = dest.as_str();
+        "no route; queueing"
+    );
     push_network_message(message)?;
     Ok(())
 }
@@ -303,7 +342,11 @@ pub async fn forward_network_message(message: NetworkMessage) -> Result<()> {
 pub fn expire_network_messages() -> Result<()> {
     let cutoff = unix_now() - config().message_timeout_secs as f64;
     for obj in open_messages()?.pop(&[("timestamp", &format!("$lt:{}", cutoff))])? {
-        log::warn!("expiring message: {}", obj.object.message_type());
+        log::warn!(
+            message_type = obj.object.message_type(), tckuuid = obj.object.tckuuid(),
+            dest = dest_of(&obj.object).as_str();
+            "expiring message: {}", obj.object.message_type()
+        );
         log::debug!("{:?}", obj.object);
     }
     Ok(())
@@ -312,7 +355,7 @@ pub fn expire_network_messages() -> Result<()> {
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 /// Extract the `dest` field from any `NetworkMessageVariant`.
-fn dest_of(msg: &NetworkMessage) -> String {
+pub(crate) fn dest_of(msg: &NetworkMessage) -> String {
     match msg {
         NetworkMessage::Ping(m)                => m.dest.clone().unwrap_or_default(),
         NetworkMessage::MessagesRequest(m)     => m.dest.clone().unwrap_or_default(),
@@ -322,6 +365,8 @@ fn dest_of(msg: &NetworkMessage) -> String {
         NetworkMessage::TicketTraceResponse(m) => m.dest.clone().unwrap_or_default(),
         NetworkMessage::TicketRequest(m)       => m.dest.clone().unwrap_or_default(),
         NetworkMessage::TicketResponse(m)      => m.dest.clone().unwrap_or_default(),
+        NetworkMessage::TicketProgress(m)      => m.dest.clone().unwrap_or_default(),
+        NetworkMessage::TicketCancel(m)        => m.dest.clone().unwrap_or_default(),
     }
 }
 
@@ -359,9 +404,7 @@ mod tests {
             form: ControlForm::SyncProcess(SyncProcess {
                 command: CommandArg::Single("echo hi".into()),
                 timeout: 15,
-                stdout: None, stderr: None, status: None,
-                start_time: None, elapsed_time: None, error: None,
-                objuuid: None, coluuid: None,
+                ..Default::default()
             }),
             tracing: false,
             src: src.into(),
@@ -0,0 +1,301 @@
+//! Runtime statistics: Prometheus-style metrics for the `/metrics` endpoint
+//! and the `GetStats` control form.
+//!
+//! Counters and summaries are kept in a process-wide registry and updated
+//! where the events happen; gauges such as queue depth and peer count are
+//! read from the collections when they are asked for.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use actix_web::HttpResponse;
+use anyhow::Result;
+
+use crate::collections::{
+    open_kvstore, open_messages, open_peers, open_routes, open_tickets, open_traces, open_transfers,
+};
+use crate::messaging::dest_of;
+use crate::models::control::{GetStats, PeerStats};
+
+/// `(name, type, help)` of every metric family, in exposition order.
+const FAMILIES: &[(&str, &str, &str)] = &[
+    ("stembot_messages_queued",          "gauge",   "Network messages waiting for delivery, by destination."),
+    ("stembot_forward_total",            "counter", "Network messages sent to a peer, by peer and result."),
+    ("stembot_tickets_created_total",    "counter", "Tickets created on this agent."),
+    ("stembot_tickets_serviced_total",   "counter", "Tickets whose response reached this agent."),
+    ("stembot_tickets_expired_total",    "counter", "Tickets dropped after ticket_timeout_secs."),
+    ("stembot_tickets_open",             "gauge",   "Tickets held by this agent."),
+    ("stembot_ticket_latency_seconds",   "summary", "Time from ticket creation to its response."),
+    ("stembot_routes",                   "gauge",   "Entries in the routing table."),
+    ("stembot_peers",                    "gauge",   "Configured peers."),
+    ("stembot_process_executions_total", "counter", "Processes started, by kind."),
+    ("stembot_process_duration_seconds", "summary", "Run time of finished processes, by kind."),
+];
+
+/// A series name and its labels.
+type SeriesKey = (String, Vec<(String, String)>);
+
+/// Counter and summary series by family.
+static REGISTRY: Mutex<BTreeMap<&'static str, BTreeMap<SeriesKey, f64>>> = Mutex::new(BTreeMap::new());
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// When an advertisement was last sent and last received.
+static ADVERTISEMENTS: Mutex<(Option<f64>, Option<f64>)> = Mutex::new((None, None));
+
+/// When each peer was last heard from or delivered to.
+static PEERS_SEEN: Mutex<BTreeMap<String, f64>> = Mutex::new(BTreeMap::new());
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Render a series name with its labels, e.g. `name{peer="a1"}`.
+fn series(name: &str, labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{key}=\"{value}\"")
+        })
+        .collect();
+    format!("{name}{{{}}}", labels.join(","))
+}
+
+fn add(family: &'static str, name: &str, labels: &[(&str, &str)], value: f64) {
+    let key = (
+        name.to_string(),
+        labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+    );
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    *registry.entry(family).or_default().entry(key).or_default() += value;
+}
+
+/// Increment the counter `name` for the given labels.
+pub fn inc(name: &'static str, labels: &[(&str, &str)]) {
+    add(name, name, labels, 1.0);
+}
+
+/// Record one observation of the summary `name` for the given labels.
+pub fn observe(name: &'static str, labels: &[(&str, &str)], value: f64) {
+    add(name, &format!("{name}_sum"), labels, value);
+    add(name, &format!("{name}_count"), labels, 1.0);
+}
+
+/// The series of counter `name` with their labels.
+fn counter(name: &str) -> Vec<(BTreeMap<String, String>, f64)> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get(name)
+        .into_iter()
+        .flatten()
+        .map(|((_, labels), value)| (labels.iter().cloned().collect(), *value))
+        .collect()
+}
+
+/// Note the agent's start, from which `GetStats` reports uptime.
+pub fn mark_started() {
+    STARTED.get_or_init(Instant::now);
+}
+
+pub fn advertisement_sent() {
+    ADVERTISEMENTS.lock().unwrap_or_else(|e| e.into_inner()).0 = Some(unix_now());
+}
+
+pub fn advertisement_received() {
+    ADVERTISEMENTS.lock().unwrap_or_else(|e| e.into_inner()).1 = Some(unix_now());
+}
+
+/// Note that a peer was just heard from or delivered to.
+pub fn peer_seen(agtuuid: &str) {
+    PEERS_SEEN.lock().unwrap_or_else(|e| e.into_inner()).insert(agtuuid.to_string(), unix_now());
+}
+
+/// When each peer was last heard from or delivered to, in Unix seconds.
+pub fn peers_seen() -> BTreeMap<String, f64> {
+    PEERS_SEEN.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Read the gauges from the collections.
+fn gauges() -> Result<BTreeMap<&'static str, BTreeMap<String, f64>>> {
+    let mut gauges: BTreeMap<&'static str, BTreeMap<String, f64>> = BTreeMap::new();
+
+    let queued = gauges.entry("stembot_messages_queued").or_default();
+    for obj in open_messages()?.find(&[])? {
+        let dest = dest_of(&obj.object);
+        *queued.entry(series("stembot_messages_queued", &[("dest", &dest)])).or_default() += 1.0;
+    }
+
+    for (name, count) in [
+        ("stembot_tickets_open", open_tickets()?.find_objuuids(&[])?.len()),
+        ("stembot_routes",       open_routes()?.find_objuuids(&[])?.len()),
+        ("stembot_peers",        open_peers()?.find_objuuids(&[])?.len()),
+    ] {
+        gauges.entry(name).or_default().insert(name.to_string(), count as f64);
+    }
+    Ok(gauges)
+}
+
+/// Render every metric in the Prometheus text exposition format.
+pub fn render() -> Result<String> {
+    render_with(&gauges()?)
+}
+
+fn render_with(gauges: &BTreeMap<&'static str, BTreeMap<String, f64>>) -> Result<String> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+    let mut out = String::new();
+    for (name, kind, help) in FAMILIES {
+        writeln!(out, "# HELP {name} {help}")?;
+        writeln!(out, "# TYPE {name} {kind}")?;
+        if let Some(gauge) = gauges.get(name) {
+            for (series, value) in gauge {
+                writeln!(out, "{series} {value}")?;
+            }
+        }
+        for ((series_name, labels), value) in registry.get(name).into_iter().flatten() {
+            let labels: Vec<(&str, &str)> = labels.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            writeln!(out, "{} {value}", series(series_name, &labels))?;
+        }
+    }
+    Ok(out)
+}
+
+/// Handler for the `/metrics` endpoint, served on `metrics_address`.
+pub async fn metrics_handler() -> HttpResponse {
+    match render() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(body),
+        Err(e) => {
+            log::error!("metrics error: {e}");
+            HttpResponse::InternalServerError().body(e.to_string())
+        }
+    }
+}
+
+// ── GetStats ──────────────────────────────────────────────────────────────────
+
+/// Fill a [`GetStats`] form with this agent's runtime statistics.
+pub fn get_stats(mut form: GetStats) -> GetStats {
+    match get_stats_inner(&mut form) {
+        Ok(()) => form.error = None,
+        Err(e) => form.error = Some(e.to_string()),
+    }
+    form
+}
+
+fn get_stats_inner(form: &mut GetStats) -> Result<()> {
+    form.version     = Some(env!("CARGO_PKG_VERSION").to_string());
+    form.uptime_secs = STARTED.get().map(|t| t.elapsed().as_secs_f64());
+
+    let messages = open_messages()?.find(&[])?;
+    form.queued_messages = BTreeMap::new();
+    for obj in &messages {
+        *form.queued_messages.entry(dest_of(&obj.object)).or_default() += 1;
+    }
+
+    let tickets = open_tickets()?.find(&[])?;
+    let serviced = tickets.iter().filter(|t| t.object.service_time.is_some()).count() as u64;
+    form.tickets_serviced = Some(serviced);
+    form.tickets_open     = Some(tickets.len() as u64 - serviced);
+
+    let traces = open_traces()?.find_objuuids(&[])?.len() as u64;
+    form.traces = Some(traces);
+
+    let (sent, received) = *ADVERTISEMENTS.lock().unwrap_or_else(|e| e.into_inner());
+    form.last_advertisement_sent     = sent;
+    form.last_advertisement_received = received;
+
+    let mut forwards: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for (labels, value) in counter("stembot_forward_total") {
+        let (Some(peer), Some(result)) = (labels.get("peer"), labels.get("result")) else { continue };
+        let counts = forwards.entry(peer.clone()).or_default();
+        match result.as_str() {
+            "success" => counts.0 += value as u64,
+            _         => counts.1 += value as u64,
+        }
+    }
+    let seen  = peers_seen();
+    let peers = open_peers()?.find(&[])?;
+    form.peers = peers
+        .iter()
+        .filter_map(|p| p.object.agtuuid.clone().map(|agtuuid| (agtuuid, &p.object)))
+        .map(|(agtuuid, peer)| {
+            let (forward_successes, forward_failures) = forwards.get(&agtuuid).copied().unwrap_or_default();
+            PeerStats {
+                last_seen: seen.get(&agtuuid).copied(),
+                url: peer.url.clone(),
+                polling: peer.polling,
+                forward_successes,
+                forward_failures,
+                agtuuid,
+            }
+        })
+        .collect();
+
+    form.collections = BTreeMap::from([
+        ("messages".to_string(),  messages.len() as u64),
+        ("tickets".to_string(),   tickets.len() as u64),
+        ("traces".to_string(),    traces),
+        ("peers".to_string(),     peers.len() as u64),
+        ("routes".to_string(),    open_routes()?.find_objuuids(&[])?.len() as u64),
+        ("kvstore".to_string(),   open_kvstore()?.find_objuuids(&[])?.len() as u64),
+        ("transfers".to_string(), open_transfers()?.find_objuuids(&[])?.len() as u64),
+    ]);
+    Ok(())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series_labels() {
+        assert_eq!(series("stembot_peers", &[]), "stembot_peers");
+        assert_eq!(
+            series("stembot_forward_total", &[("peer", "a\"1"), ("result", "success")]),
+            r#"stembot_forward_total{peer="a\"1",result="success"}"#
+        );
+    }
+
+    #[test]
+    fn test_render_counters_and_summaries() {
+        inc("stembot_forward_total", &[("peer", "test-render"), ("result", "failure")]);
+        inc("stembot_forward_total", &[("peer", "test-render"), ("result", "failure")]);
+        observe("stembot_process_duration_seconds", &[("kind", "test-render")], 1.5);
+
+        let gauges = BTreeMap::from([
+            ("stembot_peers", BTreeMap::from([("stembot_peers".to_string(), 3.0)])),
+        ]);
+        let text = render_with(&gauges).unwrap();
+        assert!(text.contains("# TYPE stembot_forward_total counter\n"));
+        assert!(text.contains("stembot_forward_total{peer=\"test-render\",result=\"failure\"} 2\n"));
+        assert!(text.contains("stembot_process_duration_seconds_sum{kind=\"test-render\"} 1.5\n"));
+        assert!(text.contains("stembot_process_duration_seconds_count{kind=\"test-render\"} 1\n"));
+        assert!(text.contains("# TYPE stembot_peers gauge\nstembot_peers 3\n"));
+        assert!(text.contains("# TYPE stembot_routes gauge\n# HELP"));
+    }
+
+    #[test]
+    fn test_counter_labels() {
+        inc("stembot_forward_total", &[("peer", "test-counter"), ("result", "success")]);
+        let series: Vec<_> = counter("stembot_forward_total")
+            .into_iter()
+            .filter(|(labels, _)| labels.get("peer").is_some_and(|p| p == "test-counter"))
+            .collect();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].0.get("result").map(String::as_str), Some("success"));
+        assert_eq!(series[0].1, 1.0);
+    }
+}
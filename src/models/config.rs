@@ -1,7 +1,9 @@
-use serde_json::json;
+use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::dao::kvstore::KVStore;
+use crate::models::control::Encoding;
+use crate::models::routing::{format_labels, parse_labels, validate_name, Labels};
 
 /// Log level, mirroring Python's `LogLevel` IntEnum.
 #[derive(Clone, Debug, PartialEq)]
@@ -28,6 +30,19 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+impl LogLevel {
+    /// The `log` filter for this level; `log` has no level above `Error`.
+    pub fn to_level_filter(&self) -> log::LevelFilter {
+        match self {
+            LogLevel::Debug    => log::LevelFilter::Debug,
+            LogLevel::Info     => log::LevelFilter::Info,
+            LogLevel::Warning  => log::LevelFilter::Warn,
+            LogLevel::Error    => log::LevelFilter::Error,
+            LogLevel::Critical => log::LevelFilter::Error,
+        }
+    }
+}
+
 impl std::str::FromStr for LogLevel {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -42,6 +57,36 @@ impl std::str::FromStr for LogLevel {
     }
 }
 
+/// Layout of the agent's log lines.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum LogFormat {
+    /// Pipe-delimited `LEVEL|time|file:Lline|module|message key=value...` lines.
+    #[default]
+    Text,
+    /// One JSON object per line, with structured fields as top-level keys.
+    Json,
+}
+
+impl LogFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format '{s}'; expected text or json")),
+        }
+    }
+}
+
 /// Configuration settings for the StemBot distributed agent framework.
 /// All values are loaded from the kvstore collection; defaults are seeded on first run.
 #[derive(Clone, Debug)]
@@ -72,6 +117,33 @@ pub struct Config {
     pub ticket_timeout_secs: u32,
     /// Seconds before a pending message is discarded (default: 600).
     pub message_timeout_secs: u32,
+    /// Maximum `SyncProcess` commands running at once; further tickets queue (0 = unlimited, default: 16).
+    pub max_processes: u32,
+    /// CPU seconds allowed per process (0 = unlimited, default: 0).
+    pub rlimit_cpu_secs: u64,
+    /// Address space allowed per process in bytes (0 = unlimited, default: 0).
+    pub rlimit_as_bytes: u64,
+    /// Open files allowed per process (0 = unlimited, default: 0).
+    pub rlimit_nofile: u64,
+    /// Bytes of stdout and of stderr kept per process (0 = unlimited, default: 0).
+    pub max_output_bytes: u64,
+    /// Compression applied to request bodies sent to other agents (default: none).
+    /// Receivers must understand it, so leave it off when any peer runs the Python agent.
+    pub body_encoding: Encoding,
+    /// Path of the agent's own log file; empty keeps logs on stderr only (default: empty).
+    pub log_file: String,
+    /// Size at which the log file is rotated (0 = never, default: 10 MiB).
+    pub log_file_max_bytes: u64,
+    /// Rotated log files kept as `{log_file}.1` .. `{log_file}.N` (default: 5).
+    pub log_file_count: u32,
+    /// Log line layout, `text` or `json` (default: text).
+    pub log_format: LogFormat,
+    /// `host:port` serving `/metrics`, kept apart from the agent's own port; empty disables it (default: empty).
+    pub metrics_address: String,
+    /// Key/value labels advertised to other agents and matched by selectors (default: none).
+    pub labels: Labels,
+    /// Display name other agents and the CLI may address this agent by (default: none).
+    pub name: Option<String>,
 }
 
 impl Config {
@@ -98,6 +170,15 @@ impl Config {
                     .unwrap_or($default as u64) as u32
             };
         }
+        macro_rules! kv_u64 {
+            ($key:expr, $default:expr) => {
+                store
+                    .get($key, Some(json!($default)))
+                    .unwrap_or(json!($default))
+                    .as_u64()
+                    .unwrap_or($default as u64)
+            };
+        }
         macro_rules! kv_level {
             ($key:expr, $default:expr) => {{
                 let s = store
@@ -138,6 +219,19 @@ impl Config {
             max_weight:           kv_u32!("max_weight",           600u32),
             ticket_timeout_secs:  kv_u32!("ticket_timeout_secs",  600u32),
             message_timeout_secs: kv_u32!("message_timeout_secs", 600u32),
+            max_processes:        kv_u32!("max_processes",        16u32),
+            rlimit_cpu_secs:      kv_u64!("rlimit_cpu_secs",      0u64),
+            rlimit_as_bytes:      kv_u64!("rlimit_as_bytes",      0u64),
+            rlimit_nofile:        kv_u64!("rlimit_nofile",        0u64),
+            max_output_bytes:     kv_u64!("max_output_bytes",     0u64),
+            body_encoding:        kv_str!("body_encoding",        "none").parse().unwrap_or(Encoding::None),
+            log_file:             kv_str!("log_file",             ""),
+            log_file_max_bytes:   kv_u64!("log_file_max_bytes",   10_485_760u64),
+            log_file_count:       kv_u32!("log_file_count",       5u32),
+            log_format:           kv_str!("log_format",           "text").parse().unwrap_or_default(),
+            metrics_address:      kv_str!("metrics_address",      ""),
+            labels:               parse_labels(&kv_str!("labels", "")).unwrap_or_default(),
+            name:                 validate_name(&kv_str!("name", "")).ok(),
         }
     }
 
@@ -154,18 +248,210 @@ impl Config {
     /// Log the current configuration values.
     pub fn log(&self) {
         log::info!(
-            "\n  agtuuid:              {}\n  workers:              {}\n  socket_host:          {}\n  socket_port:          {}\n  secret_digest:        {}\n  client_control_url:   {}\n  log_level_app:        {}\n  log_level_api:        {}\n  peer_timeout_secs:    {}\n  peer_refresh_secs:    {}\n  max_weight:           {}\n  ticket_timeout_secs:  {}\n  message_timeout_secs: {}",
+            "\n  agtuuid:              {}\n  workers:              {}\n  socket_host:          {}\n  socket_port:          {}\n  secret_digest:        {}\n  client_control_url:   {}\n  log_level_app:        {}\n  log_level_api:        {}\n  peer_timeout_secs:    {}\n  peer_refresh_secs:    {}\n  max_weight:           {}\n  ticket_timeout_secs:  {}\n  message_timeout_secs: {}\n  max_processes:        {}\n  rlimit_cpu_secs:      {}\n  rlimit_as_bytes:      {}\n  rlimit_nofile:        {}\n  max_output_bytes:     {}\n  body_encoding:        {}\n  log_file:             {}\n  log_file_max_bytes:   {}\n  log_file_count:       {}\n  log_format:           {}\n  metrics_address:      {}\n  labels:               {}\n  name:                 {}",
             self.agtuuid, self.workers, self.socket_host, self.socket_port,
             self.secret_digest, self.client_control_url,
             self.log_level_app, self.log_level_api,
             self.peer_timeout_secs, self.peer_refresh_secs, self.max_weight,
             self.ticket_timeout_secs, self.message_timeout_secs,
+            self.max_processes, self.rlimit_cpu_secs, self.rlimit_as_bytes,
+            self.rlimit_nofile, self.max_output_bytes, self.body_encoding.as_str(),
+            self.log_file, self.log_file_max_bytes, self.log_file_count,
+            self.log_format.as_str(), self.metrics_address, format_labels(&self.labels),
+            self.name.as_deref().unwrap_or(""),
         );
     }
 }
 
+// ── Runtime changes ───────────────────────────────────────────────────────────
+
+/// Keys only read at startup; a `SetConfig` change to one of them takes
+/// effect when the agent is next restarted.  Every other key applies as soon
+/// as the config is reloaded.
+pub const RESTART_REQUIRED_KEYS: &[&str] = &[
+    "agtuuid",
+    "workers",
+    "socket_host",
+    "socket_port",
+    "secret_digest",
+    "max_processes",
+    "log_file",
+    "log_file_max_bytes",
+    "log_file_count",
+    "metrics_address",
+];
+
+impl Config {
+    /// Take the values of [`RESTART_REQUIRED_KEYS`] from `running`, so that a
+    /// reloaded config keeps what the agent started with until it restarts.
+    pub fn keep_restart_required(&mut self, running: &Config) {
+        self.agtuuid            = running.agtuuid.clone();
+        self.workers            = running.workers;
+        self.socket_host        = running.socket_host.clone();
+        self.socket_port        = running.socket_port;
+        self.secret_digest      = running.secret_digest.clone();
+        self.max_processes      = running.max_processes;
+        self.log_file           = running.log_file.clone();
+        self.log_file_max_bytes = running.log_file_max_bytes;
+        self.log_file_count     = running.log_file_count;
+        self.metrics_address    = running.metrics_address.clone();
+    }
+}
+
+/// Check a new value for config `key` and return it as the kvstore keeps it.
+///
+/// Numbers may also be given as numeric strings; log levels and encodings
+/// are normalized to their canonical spelling.
+pub fn validate_config_value(key: &str, value: &Value) -> Result<Value, String> {
+    let text = || -> Result<String, String> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            Value::Number(n) => Ok(n.to_string()),
+            _ => Err(format!("{key}: expected a string or number, got {value}")),
+        }
+    };
+    let number = |max: u64| -> Result<Value, String> {
+        let n = text()?
+            .parse::<u64>()
+            .map_err(|_| format!("{key}: expected a non-negative integer, got {value}"))?;
+        if n > max {
+            return Err(format!("{key}: {n} is out of range (maximum {max})"));
+        }
+        Ok(json!(n))
+    };
+
+    match key {
+        "agtuuid" => {
+            let s = text()?;
+            if s.is_empty() || s.len() > 36 {
+                return Err(format!("{key}: must be 1-36 characters"));
+            }
+            Ok(json!(s))
+        }
+        "socket_host" | "client_control_url" => {
+            let s = text()?;
+            if s.is_empty() {
+                return Err(format!("{key}: must not be empty"));
+            }
+            Ok(json!(s))
+        }
+        "secret_digest" => {
+            let s = text()?.to_lowercase();
+            if s.len() != 64 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("{key}: expected a 64-character SHA-256 hex digest"));
+            }
+            Ok(json!(s))
+        }
+        "socket_port" => number(u16::MAX as u64),
+        "workers" => match number(u32::MAX as u64)? {
+            n if n == json!(0) => Err(format!("{key}: must be at least 1")),
+            n => Ok(n),
+        },
+        "peer_timeout_secs" | "peer_refresh_secs" | "max_weight" | "ticket_timeout_secs"
+        | "message_timeout_secs" | "max_processes" | "log_file_count" => number(u32::MAX as u64),
+        "rlimit_cpu_secs" | "rlimit_as_bytes" | "rlimit_nofile" | "max_output_bytes"
+        | "log_file_max_bytes" => number(u64::MAX),
+        "log_file" => Ok(json!(text()?)),
+        "name" => {
+            let name = text()?;
+            if name.trim().is_empty() {
+                return Ok(json!(""));
+            }
+            Ok(json!(validate_name(&name).map_err(|e| format!("{key}: {e}"))?))
+        }
+        "labels" => {
+            let labels = match value {
+                Value::Object(map) => map
+                    .iter()
+                    .map(|(k, v)| match v {
+                        Value::String(s) => Ok(format!("{k}={s}")),
+                        other => Ok(format!("{k}={other}")),
+                    })
+                    .collect::<Result<Vec<_>, String>>()?
+                    .join(","),
+                _ => text()?,
+            };
+            let labels = parse_labels(&labels).map_err(|e| format!("{key}: {e}"))?;
+            Ok(json!(format_labels(&labels)))
+        }
+        "metrics_address" => {
+            let s = text()?;
+            if !s.is_empty() && s.parse::<std::net::SocketAddr>().is_err() {
+                return Err(format!("{key}: expected an address like 127.0.0.1:9100, or empty to disable"));
+            }
+            Ok(json!(s))
+        }
+        "log_format" => {
+            let format = text()?.parse::<LogFormat>().map_err(|e| format!("{key}: {e}"))?;
+            Ok(json!(format.as_str()))
+        }
+        "log_level_app" | "log_level_api" => {
+            let level = text()?.parse::<LogLevel>().map_err(|e| format!("{key}: {e}"))?;
+            Ok(json!(level.to_string()))
+        }
+        "body_encoding" => {
+            let encoding = text()?.parse::<Encoding>().map_err(|e| format!("{key}: {e}"))?;
+            Ok(json!(encoding.as_str()))
+        }
+        _ => Err(format!("unknown config key '{key}'")),
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self::load()
     }
 }
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_config_value_numbers() {
+        assert_eq!(validate_config_value("peer_timeout_secs", &json!(120)), Ok(json!(120)));
+        assert_eq!(validate_config_value("peer_timeout_secs", &json!("120")), Ok(json!(120)));
+        assert!(validate_config_value("peer_timeout_secs", &json!(-1)).is_err());
+        assert!(validate_config_value("socket_port", &json!(70000)).is_err());
+        assert!(validate_config_value("workers", &json!(0)).is_err());
+        assert!(validate_config_value("max_weight", &json!(true)).is_err());
+    }
+
+    #[test]
+    fn test_validate_config_value_normalizes() {
+        assert_eq!(validate_config_value("log_level_app", &json!("warn")), Ok(json!("WARNING")));
+        assert_eq!(validate_config_value("body_encoding", &json!("zstd")), Ok(json!("zstd")));
+        assert!(validate_config_value("body_encoding", &json!("brotli")).is_err());
+        assert_eq!(validate_config_value("log_format", &json!("JSON")), Ok(json!("json")));
+        assert!(validate_config_value("log_format", &json!("xml")).is_err());
+        assert_eq!(validate_config_value("labels", &json!("role=db, dc=east")), Ok(json!("dc=east,role=db")));
+        assert_eq!(validate_config_value("labels", &json!({"role": "db"})), Ok(json!("role=db")));
+        assert_eq!(validate_config_value("labels", &json!("")), Ok(json!("")));
+        assert!(validate_config_value("labels", &json!("role")).is_err());
+        assert_eq!(validate_config_value("name", &json!(" db-east-1 ")), Ok(json!("db-east-1")));
+        assert_eq!(validate_config_value("name", &json!("")), Ok(json!("")));
+        assert!(validate_config_value("name", &json!("db east")).is_err());
+        assert_eq!(validate_config_value("metrics_address", &json!("")), Ok(json!("")));
+        assert!(validate_config_value("metrics_address", &json!("127.0.0.1:9100")).is_ok());
+        assert!(validate_config_value("metrics_address", &json!("localhost")).is_err());
+        assert_eq!(
+            validate_config_value("secret_digest", &json!("AB".repeat(32))),
+            Ok(json!("ab".repeat(32)))
+        );
+        assert!(validate_config_value("secret_digest", &json!("changeme")).is_err());
+    }
+
+    #[test]
+    fn test_validate_config_value_unknown_key() {
+        assert!(validate_config_value("no_such_key", &json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_restart_required_keys_are_known() {
+        for key in RESTART_REQUIRED_KEYS {
+            assert!(!validate_config_value(key, &json!("x")).is_err_and(|e| e.starts_with("unknown")));
+        }
+    }
+}
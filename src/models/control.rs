@@ -1,7 +1,9 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::models::routing::{Peer, Route};
+use crate::models::routing::{Labels, Peer, Route};
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
@@ -36,6 +38,85 @@ impl Default for CommandArg {
     }
 }
 
+// ── Hash algorithm ────────────────────────────────────────────────────────────
+
+/// Digest used to check file integrity. `md5` is the default so forms from
+/// peers that predate the choice keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Md5,
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Md5    => "md5",
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "md5"    => Ok(Self::Md5),
+            "sha256" => Ok(Self::Sha256),
+            "blake3" => Ok(Self::Blake3),
+            _ => Err(format!("unknown hash algorithm '{s}' (expected md5, sha256 or blake3)")),
+        }
+    }
+}
+
+// ── Payload encoding ──────────────────────────────────────────────────────────
+
+/// Compression applied to file data before it is base64-encoded into
+/// `b64zlib`, and optionally to whole request bodies between agents. `zlib`
+/// is the default so forms from peers that predate the choice keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    None,
+    #[default]
+    Zlib,
+    Gzip,
+    Zstd,
+}
+
+impl Encoding {
+    /// Every encoding this build can compress and decompress.
+    pub const ALL: [Encoding; 4] = [Self::None, Self::Zlib, Self::Gzip, Self::Zstd];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Zlib => "zlib",
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+impl std::str::FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "zlib" => Ok(Self::Zlib),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!("unknown encoding '{s}' (expected none, zlib, gzip or zstd)")),
+        }
+    }
+}
+
 // ── Individual control form structs ──────────────────────────────────────────
 // These structs do NOT carry a `type` field; the type is encoded by the
 // `ControlFormVariant` tagged enum when serialised.
@@ -44,37 +125,528 @@ impl Default for CommandArg {
 
 /// Request to load a file from a remote agent.
 /// Maps to Python's `LoadFile(ControlForm)`.
+///
+/// The reply also carries the file's `mtime` and, on Unix, its `mode`,
+/// `uid` and `gid`, in the form `WriteFile` accepts them.
+///
+/// `hash` is the file's digest under `hash_algorithm`; `md5sum` is only
+/// filled in when that algorithm is `md5`.
+///
+/// `encoding` asks for a compression of `b64zlib`; the reply names the one
+/// used, which is `none` when a non-`zlib` choice would not shrink the file.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct LoadFile {
+    pub path:           String,
+    pub b64zlib:        Option<String>,
+    #[serde(default)]
+    pub encoding:       Encoding,
+    pub size:           Option<i64>,
+    pub md5sum:         Option<String>,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    pub hash:           Option<String>,
+    pub mode:           Option<u32>,
+    pub uid:            Option<u32>,
+    pub gid:            Option<u32>,
+    pub mtime:          Option<f64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Request to write a file to a remote agent.
+/// Maps to Python's `WriteFile(ControlForm)`.
+///
+/// The file is written to a temporary file in the same directory and renamed
+/// into place, so readers never see it half written. `mode`, `uid` and `gid`
+/// (Unix only) default to those of the file being replaced; `mtime` defaults
+/// to the time of the write. With `backup` set, the previous version is kept
+/// at `{path}.bak` and the reply names it in `backup_path`.
+///
+/// The data is checked against `hash` under `hash_algorithm` when set, and
+/// otherwise against `md5sum`. `b64zlib` is decompressed according to
+/// `encoding`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WriteFile {
+    pub b64zlib:        String,
+    #[serde(default)]
+    pub encoding:       Encoding,
+    pub path:           String,
+    pub size:           Option<i64>,
+    pub md5sum:         Option<String>,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    pub hash:           Option<String>,
+    pub mode:           Option<u32>,
+    pub uid:            Option<u32>,
+    pub gid:            Option<u32>,
+    pub mtime:          Option<f64>,
+    #[serde(default)]
+    pub backup:         bool,
+    pub backup_path:    Option<String>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Read the end of a file on a remote agent.
+///
+/// Returns the text from `offset` when set, otherwise the last `bytes` bytes
+/// or the last `lines` lines (10 by default), at most 1 MiB; `next_offset`
+/// is where reading stopped. With `follow` set on a ticket, content appended
+/// afterwards is relayed to the originator as ticket progress until the
+/// ticket is cancelled or `timeout` seconds pass; the final reply then
+/// carries only `next_offset`, for a following request to resume from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailFile {
+    pub path:        String,
+    pub lines:       Option<u64>,
+    pub bytes:       Option<u64>,
+    pub offset:      Option<u64>,
+    #[serde(default)]
+    pub follow:      bool,
+    #[serde(default = "default_tail_timeout")]
+    pub timeout:     u64,
+    pub content:     Option<String>,
+    pub next_offset: Option<u64>,
+    pub size:        Option<u64>,
+    pub error:       Option<String>,
+    pub objuuid:     Option<String>,
+    pub coluuid:     Option<String>,
+}
+
+fn default_tail_timeout() -> u64 { 60 }
+
+impl Default for TailFile {
+    fn default() -> Self {
+        Self {
+            path:        String::new(),
+            lines:       None,
+            bytes:       None,
+            offset:      None,
+            follow:      false,
+            timeout:     default_tail_timeout(),
+            content:     None,
+            next_offset: None,
+            size:        None,
+            error:       None,
+            objuuid:     None,
+            coluuid:     None,
+        }
+    }
+}
+
+/// Request the digest of a file on a remote agent, for comparing copies of
+/// it across agents.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HashFile {
+    pub path:           String,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    pub hash:           Option<String>,
+    pub size:           Option<u64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Read one chunk of a file for a chunked transfer.
+///
+/// The reply carries the chunk at `offset`, its digest and the file's total
+/// `size`; a chunk shorter than `length` is the last one.  The agent clamps
+/// `length` to 16 MiB and echoes the clamped value.  The reply also carries
+/// the file's `mode`, `uid`, `gid` (Unix only) and `mtime`, as `LoadFile` does.
+///
+/// `hash` is the chunk's digest under `hash_algorithm`; `md5sum` is only
+/// filled in when that algorithm is `md5`.
+///
+/// `encoding` asks for a compression of `b64zlib`; the reply names the one
+/// used, as `LoadFile` does.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReadChunk {
+    pub path:           String,
+    pub offset:         u64,
+    pub length:         u64,
+    pub b64zlib:        Option<String>,
+    #[serde(default)]
+    pub encoding:       Encoding,
+    pub md5sum:         Option<String>,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    pub hash:           Option<String>,
+    pub size:           Option<u64>,
+    pub mode:           Option<u32>,
+    pub uid:            Option<u32>,
+    pub gid:            Option<u32>,
+    pub mtime:          Option<f64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Stage one chunk of a chunked transfer on the receiving agent.
+///
+/// `offset` must match the bytes already staged for `trnuuid`, except that
+/// offset `0` starts the transfer over. `received` in the reply is the staged
+/// total, whether or not the chunk was accepted.
+///
+/// The chunk is checked against `hash` under `hash_algorithm` when set, and
+/// otherwise against `md5sum`. `b64zlib` is decompressed according to
+/// `encoding`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WriteChunk {
+    pub trnuuid:        String,
+    pub offset:         u64,
+    pub b64zlib:        String,
+    #[serde(default)]
+    pub encoding:       Encoding,
+    #[serde(default)]
+    pub md5sum:         String,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    pub hash:           Option<String>,
+    pub received:       Option<u64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Report how many bytes of a chunked transfer are staged, so that an
+/// interrupted transfer can resume from there.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TransferStatus {
+    pub trnuuid:  String,
+    pub received: Option<u64>,
+    pub error:    Option<String>,
+    pub objuuid:  Option<String>,
+    pub coluuid:  Option<String>,
+}
+
+/// Move a fully staged transfer into place at `path`.
+///
+/// A supplied `hash` (under `hash_algorithm`) or, failing that, `md5sum` is
+/// checked against the assembled file; the reply carries the assembled file's
+/// `hash` either way, and its `md5sum` when the algorithm is `md5`.  `mode`,
+/// `uid`, `gid` and `mtime` are applied as `WriteFile` applies them.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommitTransfer {
+    pub trnuuid:        String,
+    pub path:           String,
+    pub size:           u64,
+    pub md5sum:         Option<String>,
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    pub hash:           Option<String>,
+    pub mode:           Option<u32>,
+    pub uid:            Option<u32>,
+    pub gid:            Option<u32>,
+    pub mtime:          Option<f64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Metadata of one file-system entry, as returned by `ListDirectory` and
+/// `StatFile`.
+///
+/// `kind` is one of `file`, `directory`, `symlink` or `other`; symlinks are
+/// not followed and carry their `target`. `mode`, `owner` and `group` are
+/// only reported on Unix.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FileEntry {
+    pub name:   String,
+    pub kind:   String,
+    pub size:   u64,
+    pub mode:   Option<u32>,
+    pub owner:  Option<String>,
+    pub group:  Option<String>,
+    pub mtime:  Option<f64>,
+    pub target: Option<String>,
+}
+
+/// Request the entries of a directory on a remote agent.
+///
+/// `depth` is the number of levels listed (`1` = direct children only) and
+/// `pattern` an optional glob matched against entry names; directories that
+/// do not match are still descended into. Entry names are relative to
+/// `path`. Listing a non-directory returns that entry alone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDirectory {
     pub path:    String,
-    pub b64zlib: Option<String>,
-    pub size:    Option<i64>,
-    pub md5sum:  Option<String>,
+    #[serde(default = "default_list_depth")]
+    pub depth:   u32,
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<FileEntry>,
     pub error:   Option<String>,
     pub objuuid: Option<String>,
     pub coluuid: Option<String>,
 }
 
-/// Request to write a file to a remote agent.
-/// Maps to Python's `WriteFile(ControlForm)`.
+fn default_list_depth() -> u32 { 1 }
+
+impl Default for ListDirectory {
+    fn default() -> Self {
+        Self {
+            path:    String::new(),
+            depth:   default_list_depth(),
+            pattern: None,
+            entries: Vec::new(),
+            error:   None,
+            objuuid: None,
+            coluuid: None,
+        }
+    }
+}
+
+/// Request the metadata of a single path on a remote agent.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct WriteFile {
-    pub b64zlib: String,
+pub struct StatFile {
+    pub path:    String,
+    pub entry:   Option<FileEntry>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// One entry of a directory archive and what happened to it.
+///
+/// `kind` follows [`FileEntry`]; `error` is set when the entry could not be
+/// packed or unpacked.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ArchiveEntry {
+    pub name:  String,
+    pub kind:  String,
+    pub size:  u64,
+    pub error: Option<String>,
+}
+
+/// Pack a directory into a gzipped tar archive in the agent's temp directory.
+///
+/// The reply carries the `archive` path and `size` and one entry per packed
+/// path, relative to `path`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PackArchive {
+    pub path:    String,
+    pub archive: Option<String>,
+    pub size:    Option<u64>,
+    #[serde(default)]
+    pub entries: Vec<ArchiveEntry>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// Unpack an archive into the directory `path`, creating it if needed.
+///
+/// Once it has unpacked, the archive is deleted if it is one `PackArchive`
+/// wrote to the temp directory or a hidden `.stembot-*.tar.gz` beside `path`;
+/// any other archive, and one that failed to unpack, is left in place.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UnpackArchive {
+    pub archive: String,
     pub path:    String,
-    pub size:    Option<i64>,
-    pub md5sum:  Option<String>,
+    #[serde(default)]
+    pub entries: Vec<ArchiveEntry>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// Delete an archive left in the temp directory by `PackArchive`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DiscardArchive {
+    pub archive: String,
     pub error:   Option<String>,
     pub objuuid: Option<String>,
     pub coluuid: Option<String>,
 }
 
+/// Delete a file, symlink or directory on a remote agent.
+///
+/// A non-empty directory is only removed when `recursive` is set. Symlinks
+/// are removed themselves, never their targets.
+///
+/// Like the other file-management forms, a failure sets `error_kind` to a
+/// stable code (`not_found`, `permission_denied`, `already_exists`,
+/// `not_empty`, `not_a_directory`, `is_a_directory`, `invalid_input`,
+/// `unsupported` or `other`) alongside the `error` message.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DeletePath {
+    pub path:       String,
+    #[serde(default)]
+    pub recursive:  bool,
+    pub error_kind: Option<String>,
+    pub error:      Option<String>,
+    pub objuuid:    Option<String>,
+    pub coluuid:    Option<String>,
+}
+
+/// Move or rename a path on a remote agent.
+///
+/// An existing `dst` is only replaced when `overwrite` is set. Files are
+/// copied and removed when `src` and `dst` are on different file systems.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MovePath {
+    pub src:        String,
+    pub dst:        String,
+    #[serde(default)]
+    pub overwrite:  bool,
+    pub error_kind: Option<String>,
+    pub error:      Option<String>,
+    pub objuuid:    Option<String>,
+    pub coluuid:    Option<String>,
+}
+
+/// Create a directory on a remote agent.
+///
+/// With `parents` set, missing parent directories are created and an
+/// existing directory is not an error. `mode` (Unix only) is applied to the
+/// new directory exactly, regardless of the agent's umask.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MakeDirectory {
+    pub path:       String,
+    #[serde(default)]
+    pub parents:    bool,
+    pub mode:       Option<u32>,
+    pub error_kind: Option<String>,
+    pub error:      Option<String>,
+    pub objuuid:    Option<String>,
+    pub coluuid:    Option<String>,
+}
+
+/// Change the mode and/or ownership of a path on a remote agent (Unix only).
+///
+/// `owner` and `group` are names or numeric ids. With `recursive` set, a
+/// directory's contents are changed too; symlinks below `path` are not
+/// followed and keep their own mode.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SetPermissions {
+    pub path:       String,
+    pub mode:       Option<u32>,
+    pub owner:      Option<String>,
+    pub group:      Option<String>,
+    #[serde(default)]
+    pub recursive:  bool,
+    pub error_kind: Option<String>,
+    pub error:      Option<String>,
+    pub objuuid:    Option<String>,
+    pub coluuid:    Option<String>,
+}
+
 /// Request to synchronously execute a process on a remote agent.
 /// Maps to Python's `SyncProcess(ControlForm)`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncProcess {
-    pub command:      CommandArg,
+    pub command:          CommandArg,
     #[serde(default = "default_sync_timeout")]
-    pub timeout:      i64,
+    pub timeout:          i64,
+    /// Push partial output back to the ticket originator while the process runs.
+    #[serde(default)]
+    pub stream:           bool,
+    /// Working directory for the process; the agent's own when `None`.
+    pub cwd:              Option<String>,
+    /// Variables added to (or overriding) the process environment.
+    #[serde(default)]
+    pub env:              BTreeMap<String, String>,
+    /// Start from an empty environment instead of inheriting the agent's.
+    #[serde(default)]
+    pub clear_env:        bool,
+    /// Base64-encoded bytes written to the process's stdin.
+    pub b64stdin:         Option<String>,
+    /// Run the process as this user (Unix only; the agent must be privileged).
+    pub run_as_user:      Option<String>,
+    pub stdout:           Option<String>,
+    pub stderr:           Option<String>,
+    pub status:           Option<i64>,
+    /// Signal that terminated the process (Unix only).
+    pub signal:           Option<i64>,
+    /// Whether the timeout fired and the process tree was terminated.
+    #[serde(default)]
+    pub timed_out:        bool,
+    /// CPU time limit in seconds (Unix only).
+    pub rlimit_cpu_secs:  Option<u64>,
+    /// Address space limit in bytes (Unix only).
+    pub rlimit_as_bytes:  Option<u64>,
+    /// Open file descriptor limit (Unix only).
+    pub rlimit_nofile:    Option<u64>,
+    /// Bytes of stdout and of stderr kept; output beyond this is discarded.
+    pub max_output_bytes: Option<u64>,
+    /// Whether stdout or stderr was cut short by `max_output_bytes`.
+    #[serde(default)]
+    pub output_truncated: bool,
+    pub start_time:       Option<f64>,
+    pub elapsed_time:     Option<f64>,
+    pub error:            Option<String>,
+    pub objuuid:          Option<String>,
+    pub coluuid:          Option<String>,
+}
+
+fn default_sync_timeout() -> i64 { 15 }
+
+impl Default for SyncProcess {
+    fn default() -> Self {
+        Self {
+            command:          CommandArg::default(),
+            timeout:          default_sync_timeout(),
+            stream:           false,
+            cwd:              None,
+            env:              BTreeMap::new(),
+            clear_env:        false,
+            b64stdin:         None,
+            run_as_user:      None,
+            stdout:           None,
+            stderr:           None,
+            status:           None,
+            signal:           None,
+            timed_out:        false,
+            rlimit_cpu_secs:  None,
+            rlimit_as_bytes:  None,
+            rlimit_nofile:    None,
+            max_output_bytes: None,
+            output_truncated: false,
+            start_time:       None,
+            elapsed_time:     None,
+            error:            None,
+            objuuid:          None,
+            coluuid:          None,
+        }
+    }
+}
+
+/// Request to start a background process on a remote agent.
+///
+/// Returns immediately with a `prcuuid` handle that later `ProcessStatus`
+/// and `KillProcess` forms refer to.  The process counts against the agent's
+/// `max_processes` until it exits; the form fails when no slot is free.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StartProcess {
+    pub command:     CommandArg,
+    /// Working directory for the process; the agent's own when `None`.
+    pub cwd:         Option<String>,
+    /// Variables added to (or overriding) the process environment.
+    #[serde(default)]
+    pub env:         BTreeMap<String, String>,
+    /// Start from an empty environment instead of inheriting the agent's.
+    #[serde(default)]
+    pub clear_env:   bool,
+    /// Base64-encoded bytes written to the process's stdin.
+    pub b64stdin:    Option<String>,
+    /// Run the process as this user (Unix only; the agent must be privileged).
+    pub run_as_user: Option<String>,
+    pub prcuuid:     Option<String>,
+    pub start_time:  Option<f64>,
+    pub error:       Option<String>,
+    pub objuuid:     Option<String>,
+    pub coluuid:     Option<String>,
+}
+
+/// Request the state and accumulated output of a background process.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProcessStatus {
+    pub prcuuid:      String,
+    #[serde(default)]
+    pub running:      bool,
     pub stdout:       Option<String>,
     pub stderr:       Option<String>,
     pub status:       Option<i64>,
@@ -85,7 +657,38 @@ pub struct SyncProcess {
     pub coluuid:      Option<String>,
 }
 
-fn default_sync_timeout() -> i64 { 15 }
+/// Request to terminate a background process.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct KillProcess {
+    pub prcuuid: String,
+    pub status:  Option<i64>,
+    pub signal:  Option<i64>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// Summary of a background process held in an agent's process registry.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProcessInfo {
+    pub prcuuid:      String,
+    pub command:      CommandArg,
+    #[serde(default)]
+    pub running:      bool,
+    pub status:       Option<i64>,
+    pub start_time:   f64,
+    pub elapsed_time: f64,
+}
+
+/// Request to list the background processes known to an agent.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ListProcesses {
+    #[serde(default)]
+    pub processes: Vec<ProcessInfo>,
+    pub error:     Option<String>,
+    pub objuuid:   Option<String>,
+    pub coluuid:   Option<String>,
+}
 
 /// Request to create a peer connection to another agent.
 /// Maps to Python's `CreatePeer(ControlForm)`.
@@ -147,6 +750,72 @@ pub struct GetRoutes {
     pub coluuid: Option<String>,
 }
 
+/// Request to retrieve the table resolving agent UUIDs and display names to
+/// the agents they address, built from the agent's peers and routes.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GetNames {
+    #[serde(default)]
+    pub names:   BTreeMap<String, BTreeSet<String>>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// Request to resolve a selector (`*` or `key=value[,key=value...]`) to the
+/// agents this agent knows that it picks, sorted by UUID.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SelectAgents {
+    #[serde(default)]
+    pub selector: String,
+    #[serde(default)]
+    pub agents:   Vec<String>,
+    pub error:    Option<String>,
+    pub objuuid:  Option<String>,
+    pub coluuid:  Option<String>,
+}
+
+/// A peer link of one agent in a [`GetTopology`] walk.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TopologyLink {
+    pub agtuuid: String,
+    /// The agent polls this peer for messages instead of being reached by it.
+    #[serde(default)]
+    pub polling: bool,
+    /// `None` for a peer that only contacts the agent, which cannot reach it.
+    pub url:     Option<String>,
+}
+
+/// One agent found by a [`GetTopology`] walk.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TopologyNode {
+    pub agtuuid:   String,
+    pub name:      Option<String>,
+    #[serde(default)]
+    pub labels:    Labels,
+    /// Whether the agent answered the walk.
+    #[serde(default)]
+    pub reachable: bool,
+    /// Whether the walking agent routes to other agents through this one.
+    #[serde(default)]
+    pub gateway:   bool,
+    #[serde(default)]
+    pub peers:     Vec<TopologyLink>,
+    /// Why the agent did not answer.
+    pub error:     Option<String>,
+}
+
+/// Request to walk the mesh from the agent, collecting the peers of every
+/// agent that can be reached within `timeout` seconds (default 15).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GetTopology {
+    pub timeout: Option<f64>,
+    #[serde(default)]
+    pub agents:  Vec<TopologyNode>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
 /// Request to benchmark the remote agent's network throughput.
 /// Maps to Python's `Benchmark(ControlForm)`.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -169,50 +838,226 @@ pub struct GetConfig {
     pub coluuid: Option<String>,
 }
 
-/// Lightweight ticket status check; replaces polling via `ControlFormTicket`.
-/// Maps to Python's `CheckTicket(ControlForm)`.
+/// Change configuration keys on an agent.
+///
+/// `values` maps config keys to their new values.  Every value is checked
+/// before any is stored, so a bad one leaves the config untouched.  Keys
+/// that only take effect after a restart are listed in `restart_required`;
+/// the rest apply immediately.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct CheckTicket {
-    pub tckuuid:      String,
-    pub create_time:  Option<f64>,
-    pub service_time: Option<f64>,
-    pub error:        Option<String>,
-    pub objuuid:      Option<String>,
-    pub coluuid:      Option<String>,
+pub struct SetConfig {
+    #[serde(default)]
+    pub values:           BTreeMap<String, Value>,
+    #[serde(default)]
+    pub restart_required: Vec<String>,
+    pub error:            Option<String>,
+    pub objuuid:          Option<String>,
+    pub coluuid:          Option<String>,
 }
 
-/// Request to close an existing ticket.
-/// Maps to Python's `CloseTicket(ControlForm)`.
+/// Change an agent's log levels at runtime.
+///
+/// `app` and `api` replace the application and API levels; `modules` maps a
+/// module path (such as `stembot_rust::processor`) to the level for it and
+/// its submodules, or to `null` to drop that override.  Levels given as
+/// `null` are left alone.  The reply carries the levels now in effect.
+/// Changes last until the config is reloaded or the agent restarts.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct CloseTicket {
-    pub tckuuid: String,
+pub struct SetLogLevel {
+    pub app:     Option<String>,
+    pub api:     Option<String>,
+    #[serde(default)]
+    pub modules: BTreeMap<String, Option<String>>,
     pub error:   Option<String>,
     pub objuuid: Option<String>,
     pub coluuid: Option<String>,
 }
 
-// ── Tagged union of all control form variants ─────────────────────────────────
+/// One record from an agent's log, as returned by `GetLogs`.
+///
+/// `time` is seconds since the Unix epoch and `module` the record's target.
+/// `fields` holds the record's structured values, such as `tckuuid`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LogEntry {
+    pub time:    f64,
+    pub level:   String,
+    pub module:  String,
+    pub file:    String,
+    pub line:    u32,
+    pub message: String,
+    #[serde(default)]
+    pub fields:  BTreeMap<String, String>,
+}
 
-/// Internally-tagged union of all control forms.
+/// Request the most recent entries of an agent's log.
 ///
-/// Serialises as `{ "type": "<type>", ...fields }`.
-/// Tag values are lowercase to match Python's StrEnum wire format.
-/// Used as the `form` field in `ControlFormTicket` and `NetworkTicket`.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
-pub enum ControlForm {
-    #[serde(rename = "create_peer")]   CreatePeer(CreatePeer),
-    #[serde(rename = "discover_peer")] DiscoverPeer(DiscoverPeer),
-    #[serde(rename = "delete_peers")]  DeletePeers(DeletePeers),
+/// Returns up to `lines` entries (default 100), oldest first, from the
+/// agent's buffer of recent records.  `level` keeps entries at that level or
+/// more severe, `module` keeps a module and its submodules, and `since` /
+/// `until` bound the entry time (Unix seconds, inclusive).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GetLogs {
+    pub lines:   Option<u64>,
+    pub level:   Option<String>,
+    pub module:  Option<String>,
+    pub since:   Option<f64>,
+    pub until:   Option<f64>,
+    #[serde(default)]
+    pub entries: Vec<LogEntry>,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// Runtime view of one peer, as returned by `GetStats`.
+///
+/// `last_seen` is when a message last arrived from the peer or was last
+/// delivered to it; the forward counts cover messages sent to it directly or
+/// through it as a gateway since the agent started.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct PeerStats {
+    pub agtuuid:           String,
+    pub url:               Option<String>,
+    #[serde(default)]
+    pub polling:           bool,
+    pub last_seen:         Option<f64>,
+    #[serde(default)]
+    pub forward_successes: u64,
+    #[serde(default)]
+    pub forward_failures:  u64,
+}
+
+/// Request an agent's runtime statistics.
+///
+/// `queued_messages` counts the messages waiting for delivery by destination
+/// and `collections` the records held in each collection.  `tickets_open`
+/// are tickets still awaiting their response and `tickets_serviced` those
+/// answered but not yet closed.  Times are Unix seconds.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GetStats {
+    pub version:                     Option<String>,
+    pub uptime_secs:                 Option<f64>,
+    #[serde(default)]
+    pub queued_messages:             BTreeMap<String, u64>,
+    pub tickets_open:                Option<u64>,
+    pub tickets_serviced:            Option<u64>,
+    pub traces:                      Option<u64>,
+    pub last_advertisement_sent:     Option<f64>,
+    pub last_advertisement_received: Option<f64>,
+    #[serde(default)]
+    pub peers:                       Vec<PeerStats>,
+    #[serde(default)]
+    pub collections:                 BTreeMap<String, u64>,
+    pub error:                       Option<String>,
+    pub objuuid:                     Option<String>,
+    pub coluuid:                     Option<String>,
+}
+
+/// Lightweight ticket status check; replaces polling via `ControlFormTicket`.
+/// Maps to Python's `CheckTicket(ControlForm)`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CheckTicket {
+    pub tckuuid:        String,
+    pub create_time:    Option<f64>,
+    pub service_time:   Option<f64>,
+    /// Position in the servicing agent's process queue; `0` once running.
+    pub queue_position: Option<u64>,
+    /// `seq` of the latest partial output recorded on the ticket.
+    pub last_seq:       Option<u64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+/// Long-poll for a set of tickets; replaces polling them with `CheckTicket`.
+///
+/// The agent answers once every listed ticket is serviced, the queue position
+/// of one changes, one records new partial output, or `timeout` seconds pass
+/// (capped by the agent), with the status of each listed ticket it still
+/// holds.  `tickets` in the request may carry the statuses the caller last
+/// saw; the agent then answers at once if a ticket has moved on from them.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WaitTickets {
+    pub tckuuids: Vec<String>,
+    pub timeout:  Option<f64>,
+    #[serde(default)]
+    pub tickets:  Vec<CheckTicket>,
+    pub error:    Option<String>,
+    pub objuuid:  Option<String>,
+    pub coluuid:  Option<String>,
+}
+
+/// Request to close an existing ticket.
+/// Maps to Python's `CloseTicket(ControlForm)`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CloseTicket {
+    pub tckuuid: String,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+/// Ask the agent servicing a ticket to stop work on it early, such as a
+/// followed `TailFile`. Sent to the ticket's originating agent, which relays
+/// it to the servicing agent.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CancelTicket {
+    pub tckuuid: String,
+    pub error:   Option<String>,
+    pub objuuid: Option<String>,
+    pub coluuid: Option<String>,
+}
+
+// ── Tagged union of all control form variants ─────────────────────────────────
+
+/// Internally-tagged union of all control forms.
+///
+/// Serialises as `{ "type": "<type>", ...fields }`.
+/// Tag values are lowercase to match Python's StrEnum wire format.
+/// Used as the `form` field in `ControlFormTicket` and `NetworkTicket`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ControlForm {
+    #[serde(rename = "create_peer")]   CreatePeer(CreatePeer),
+    #[serde(rename = "discover_peer")] DiscoverPeer(DiscoverPeer),
+    #[serde(rename = "delete_peers")]  DeletePeers(DeletePeers),
     #[serde(rename = "get_peers")]     GetPeers(GetPeers),
     #[serde(rename = "get_routes")]    GetRoutes(GetRoutes),
+    #[serde(rename = "get_names")]     GetNames(GetNames),
+    #[serde(rename = "select_agents")] SelectAgents(SelectAgents),
+    #[serde(rename = "get_topology")]  GetTopology(GetTopology),
     #[serde(rename = "sync_process")]  SyncProcess(SyncProcess),
+    #[serde(rename = "start_process")]  StartProcess(StartProcess),
+    #[serde(rename = "process_status")] ProcessStatus(ProcessStatus),
+    #[serde(rename = "kill_process")]   KillProcess(KillProcess),
+    #[serde(rename = "list_processes")] ListProcesses(ListProcesses),
     #[serde(rename = "write_file")]    WriteFile(WriteFile),
     #[serde(rename = "load_file")]     LoadFile(LoadFile),
+    #[serde(rename = "hash_file")]     HashFile(HashFile),
+    #[serde(rename = "tail_file")]     TailFile(TailFile),
+    #[serde(rename = "read_chunk")]      ReadChunk(ReadChunk),
+    #[serde(rename = "write_chunk")]     WriteChunk(WriteChunk),
+    #[serde(rename = "transfer_status")] TransferStatus(TransferStatus),
+    #[serde(rename = "commit_transfer")] CommitTransfer(CommitTransfer),
+    #[serde(rename = "list_directory")]  ListDirectory(ListDirectory),
+    #[serde(rename = "stat_file")]       StatFile(StatFile),
+    #[serde(rename = "pack_archive")]    PackArchive(PackArchive),
+    #[serde(rename = "unpack_archive")]  UnpackArchive(UnpackArchive),
+    #[serde(rename = "discard_archive")] DiscardArchive(DiscardArchive),
+    #[serde(rename = "delete_path")]     DeletePath(DeletePath),
+    #[serde(rename = "move_path")]       MovePath(MovePath),
+    #[serde(rename = "make_directory")]  MakeDirectory(MakeDirectory),
+    #[serde(rename = "set_permissions")] SetPermissions(SetPermissions),
     #[serde(rename = "benchmark")]     Benchmark(Benchmark),
     #[serde(rename = "get_config")]    GetConfig(GetConfig),
+    #[serde(rename = "set_config")]    SetConfig(SetConfig),
+    #[serde(rename = "set_log_level")] SetLogLevel(SetLogLevel),
+    #[serde(rename = "get_logs")]      GetLogs(GetLogs),
+    #[serde(rename = "get_stats")]     GetStats(GetStats),
     #[serde(rename = "check_ticket")]  CheckTicket(CheckTicket),
+    #[serde(rename = "wait_tickets")]  WaitTickets(WaitTickets),
     #[serde(rename = "close_ticket")]  CloseTicket(CloseTicket),
+    #[serde(rename = "cancel_ticket")] CancelTicket(CancelTicket),
 }
 
 impl Default for ControlForm {
@@ -230,13 +1075,41 @@ impl ControlForm {
             Self::DeletePeers(_)  => "delete_peers",
             Self::GetPeers(_)     => "get_peers",
             Self::GetRoutes(_)    => "get_routes",
+            Self::GetNames(_)     => "get_names",
+            Self::SelectAgents(_) => "select_agents",
+            Self::GetTopology(_)  => "get_topology",
             Self::SyncProcess(_)  => "sync_process",
+            Self::StartProcess(_)  => "start_process",
+            Self::ProcessStatus(_) => "process_status",
+            Self::KillProcess(_)   => "kill_process",
+            Self::ListProcesses(_) => "list_processes",
             Self::WriteFile(_)    => "write_file",
             Self::LoadFile(_)     => "load_file",
+            Self::HashFile(_)     => "hash_file",
+            Self::TailFile(_)     => "tail_file",
+            Self::ReadChunk(_)      => "read_chunk",
+            Self::WriteChunk(_)     => "write_chunk",
+            Self::TransferStatus(_) => "transfer_status",
+            Self::CommitTransfer(_) => "commit_transfer",
+            Self::ListDirectory(_)  => "list_directory",
+            Self::StatFile(_)       => "stat_file",
+            Self::PackArchive(_)    => "pack_archive",
+            Self::UnpackArchive(_)  => "unpack_archive",
+            Self::DiscardArchive(_) => "discard_archive",
+            Self::DeletePath(_)     => "delete_path",
+            Self::MovePath(_)       => "move_path",
+            Self::MakeDirectory(_)  => "make_directory",
+            Self::SetPermissions(_) => "set_permissions",
             Self::Benchmark(_)    => "benchmark",
             Self::GetConfig(_)    => "get_config",
+            Self::SetConfig(_)    => "set_config",
+            Self::SetLogLevel(_)  => "set_log_level",
+            Self::GetLogs(_)      => "get_logs",
+            Self::GetStats(_)     => "get_stats",
             Self::CheckTicket(_)  => "check_ticket",
+            Self::WaitTickets(_)  => "wait_tickets",
             Self::CloseTicket(_)  => "close_ticket",
+            Self::CancelTicket(_) => "cancel_ticket",
         }
     }
 }
@@ -252,48 +1125,109 @@ pub struct Hop {
     pub type_str: String,
 }
 
+// ── Progress ──────────────────────────────────────────────────────────────────
+
+/// A chunk of partial output reported while a ticket is still being serviced.
+///
+/// Chunks are kept on the originator's stored `ControlFormTicket` in `seq`
+/// order, the order in which they were produced.  `stdout_offset` and
+/// `stderr_offset` are where the chunk's text starts in the command's whole
+/// output, in bytes.  Once a ticket holds more than 1 MiB of output the
+/// oldest chunks lose their text but keep their `seq` and offsets.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Progress {
+    pub seq:           u64,
+    pub stdout:        Option<String>,
+    pub stderr:        Option<String>,
+    pub stdout_offset: Option<u64>,
+    pub stderr_offset: Option<u64>,
+    pub progress_time: f64,
+}
+
 // ── ControlFormTicket ─────────────────────────────────────────────────────────
 
+/// Completion counts of a multicast ticket.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TicketCounts {
+    pub total:    u64,
+    pub serviced: u64,
+    pub pending:  u64,
+}
+
 /// A ticket for asynchronous control form delivery.
+///
+/// A ticket with `dsts` or a `selector` is a multicast ticket: the
+/// originating agent sends its form to each agent in its own per-agent
+/// ticket, and the multicast ticket is serviced once all of them are.
 /// Maps to Python's `ControlFormTicket(ControlForm)`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControlFormTicket {
     #[serde(rename = "type", default = "default_create_ticket")]
-    pub form_type:    String,
+    pub form_type:      String,
     #[serde(default = "gen_uuid")]
-    pub tckuuid:      String,
+    pub tckuuid:        String,
     #[serde(default)]
-    pub src:          String,
+    pub src:            String,
     #[serde(default)]
-    pub dst:          String,
+    pub dst:            String,
     #[serde(default = "unix_now_f64")]
-    pub create_time:  f64,
+    pub create_time:    f64,
     #[serde(default)]
-    pub tracing:      bool,
+    pub tracing:        bool,
     #[serde(default)]
-    pub hops:         Vec<Hop>,
-    pub form:         ControlForm,
-    pub service_time: Option<f64>,
-    pub error:        Option<String>,
-    pub objuuid:      Option<String>,
-    pub coluuid:      Option<String>,
+    pub hops:           Vec<Hop>,
+    #[serde(default)]
+    pub progress:       Vec<Progress>,
+    /// Last queue position reported by the servicing agent.
+    pub queue_position: Option<u64>,
+    /// Agents a multicast ticket is sent to; filled in from `selector` if set.
+    #[serde(default)]
+    pub dsts:           Vec<String>,
+    /// Picks the agents of a multicast ticket; `*` is every known agent.
+    pub selector:       Option<String>,
+    /// The multicast ticket this per-agent ticket belongs to.
+    pub parent:         Option<String>,
+    /// Per-agent tickets of a multicast ticket, filled in by `read_ticket`.
+    #[serde(default)]
+    pub results:        Vec<ControlFormTicket>,
+    /// Completion counts of a multicast ticket, filled in by `read_ticket`.
+    pub counts:         Option<TicketCounts>,
+    pub form:           ControlForm,
+    pub service_time:   Option<f64>,
+    pub error:          Option<String>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+impl ControlFormTicket {
+    /// Whether this ticket is sent to several agents.
+    pub fn is_multicast(&self) -> bool {
+        !self.dsts.is_empty() || self.selector.is_some()
+    }
 }
 
 impl Default for ControlFormTicket {
     fn default() -> Self {
         Self {
-            form_type:    "create_ticket".to_string(),
-            tckuuid:      gen_uuid(),
-            src:          String::new(),
-            dst:          String::new(),
-            create_time:  unix_now_f64(),
-            tracing:      false,
-            hops:         Vec::new(),
-            form:         ControlForm::default(),
-            service_time: None,
-            error:        None,
-            objuuid:      None,
-            coluuid:      None,
+            form_type:      "create_ticket".to_string(),
+            tckuuid:        gen_uuid(),
+            src:            String::new(),
+            dst:            String::new(),
+            create_time:    unix_now_f64(),
+            tracing:        false,
+            hops:           Vec::new(),
+            progress:       Vec::new(),
+            queue_position: None,
+            dsts:           Vec::new(),
+            selector:       None,
+            parent:         None,
+            results:        Vec::new(),
+            counts:         None,
+            form:           ControlForm::default(),
+            service_time:   None,
+            error:          None,
+            objuuid:        None,
+            coluuid:        None,
         }
     }
 }
@@ -301,6 +1235,7 @@ impl Default for ControlFormTicket {
 // ── Tests ─────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
+#[allow(clippy::useless_concat)]
 mod tests {
     use super::*;
 
@@ -328,104 +1263,636 @@ mod tests {
 
     const LOAD_FILE_REQUEST_JSON: &str = concat!(
         r#"{"type":"load_file","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""b64zlib":null,"path":"/etc/hosts","size":null,"md5sum":null}"#
+        r#""b64zlib":null,"encoding":"zlib","path":"/etc/hosts","size":null,"md5sum":null,"#,
+        r#""hash_algorithm":"md5","hash":null,"mode":null,"uid":null,"gid":null,"mtime":null}"#
+    );
+    const LOAD_FILE_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"load_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""b64zlib":"abc123","encoding":"zstd","path":"/etc/hosts","size":1024,"#,
+        r#""md5sum":"d8e8fca2dc0f896fd7cb4cb0031ba249","#,
+        r#""hash_algorithm":"md5","hash":"d8e8fca2dc0f896fd7cb4cb0031ba249","#,
+        r#""mode":420,"uid":0,"gid":0,"mtime":1000.5}"#
+    );
+
+    #[test]
+    fn test_ser_load_file_request() {
+        let form = ControlForm::LoadFile(LoadFile {
+            path: "/etc/hosts".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, LOAD_FILE_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_load_file_response() {
+        let form = ControlForm::LoadFile(LoadFile {
+            path: "/etc/hosts".into(),
+            b64zlib: Some("abc123".into()),
+            encoding: Encoding::Zstd,
+            size: Some(1024),
+            md5sum: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
+            hash: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
+            mode: Some(0o644),
+            uid: Some(0),
+            gid: Some(0),
+            mtime: Some(1000.5),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, LOAD_FILE_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_load_file_request() {
+        assert_deser_roundtrip::<ControlForm>(LOAD_FILE_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_deser_load_file_response() {
+        assert_deser_roundtrip::<ControlForm>(LOAD_FILE_RESPONSE_JSON);
+    }
+
+    // ── WriteFile ─────────────────────────────────────────────────────────────
+
+    const WRITE_FILE_REQUEST_JSON: &str = concat!(
+        r#"{"type":"write_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""b64zlib":"abc123","encoding":"zlib","path":"/tmp/out.txt","size":null,"md5sum":null,"#,
+        r#""hash_algorithm":"md5","hash":null,"mode":null,"uid":null,"gid":null,"mtime":null,"backup":false,"backup_path":null}"#
+    );
+    const WRITE_FILE_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"write_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""b64zlib":"abc123","encoding":"zlib","path":"/tmp/out.txt","size":6,"#,
+        r#""md5sum":"d8e8fca2dc0f896fd7cb4cb0031ba249","hash_algorithm":"md5","hash":null,"#,
+        r#""mode":384,"uid":1000,"gid":1000,"mtime":1000.5,"#,
+        r#""backup":true,"backup_path":"/tmp/out.txt.bak"}"#
+    );
+    const WRITE_FILE_SHA256_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"write_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""b64zlib":"abc123","encoding":"gzip","path":"/tmp/out.txt","size":6,"#,
+        r#""md5sum":null,"hash_algorithm":"sha256","#,
+        r#""hash":"ae216c2ef5247a3782c135efa279a3e4cdc61094270f5d2be58c6204b7a612c9","#,
+        r#""mode":384,"uid":1000,"gid":1000,"mtime":1000.5,"#,
+        r#""backup":true,"backup_path":"/tmp/out.txt.bak"}"#
+    );
+
+    #[test]
+    fn test_ser_write_file_request() {
+        let form = ControlForm::WriteFile(WriteFile {
+            b64zlib: "abc123".into(),
+            path: "/tmp/out.txt".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, WRITE_FILE_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_write_file_response() {
+        let form = ControlForm::WriteFile(WriteFile {
+            b64zlib: "abc123".into(),
+            path: "/tmp/out.txt".into(),
+            size: Some(6),
+            md5sum: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
+            mode: Some(0o600),
+            uid: Some(1000),
+            gid: Some(1000),
+            mtime: Some(1000.5),
+            backup: true,
+            backup_path: Some("/tmp/out.txt.bak".into()),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, WRITE_FILE_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_ser_write_file_sha256_response() {
+        let form = ControlForm::WriteFile(WriteFile {
+            b64zlib: "abc123".into(),
+            encoding: Encoding::Gzip,
+            path: "/tmp/out.txt".into(),
+            size: Some(6),
+            hash_algorithm: HashAlgorithm::Sha256,
+            hash: Some("ae216c2ef5247a3782c135efa279a3e4cdc61094270f5d2be58c6204b7a612c9".into()),
+            mode: Some(0o600),
+            uid: Some(1000),
+            gid: Some(1000),
+            mtime: Some(1000.5),
+            backup: true,
+            backup_path: Some("/tmp/out.txt.bak".into()),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, WRITE_FILE_SHA256_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_write_file_request() {
+        assert_deser_roundtrip::<ControlForm>(WRITE_FILE_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_deser_write_file_response() {
+        assert_deser_roundtrip::<ControlForm>(WRITE_FILE_RESPONSE_JSON);
+        assert_deser_roundtrip::<ControlForm>(WRITE_FILE_SHA256_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_write_file_without_metadata() {
+        let json = r#"{"type":"write_file","b64zlib":"abc123","path":"/tmp/out.txt","size":null,"md5sum":null,"error":null,"objuuid":null,"coluuid":null}"#;
+        match serde_json::from_str::<ControlForm>(json).unwrap() {
+            ControlForm::WriteFile(f) => {
+                assert_eq!(f.encoding, Encoding::Zlib);
+                assert_eq!(f.hash_algorithm, HashAlgorithm::Md5);
+                assert!(f.mode.is_none());
+                assert!(f.mtime.is_none());
+                assert!(!f.backup);
+            }
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    // ── HashFile ──────────────────────────────────────────────────────────────
+
+    const HASH_FILE_JSON: &str = concat!(
+        r#"{"type":"hash_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/etc/hosts","hash_algorithm":"blake3","#,
+        r#""hash":"d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24","size":6}"#
+    );
+
+    #[test]
+    fn test_ser_hash_file() {
+        let form = ControlForm::HashFile(HashFile {
+            path: "/etc/hosts".into(),
+            hash_algorithm: HashAlgorithm::Blake3,
+            hash: Some("d74981efa70a0c880b8d8c1985d075dbcbf679b99a5f9914e5aaf96b831a9e24".into()),
+            size: Some(6),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, HASH_FILE_JSON);
+    }
+
+    #[test]
+    fn test_deser_hash_file() {
+        assert_deser_roundtrip::<ControlForm>(HASH_FILE_JSON);
+    }
+
+    #[test]
+    fn test_deser_hash_file_unknown_algorithm() {
+        let json = r#"{"type":"hash_file","path":"/etc/hosts","hash_algorithm":"crc32"}"#;
+        assert!(serde_json::from_str::<ControlForm>(json).is_err());
+    }
+
+    #[test]
+    fn test_hash_algorithm_from_str() {
+        assert_eq!("sha256".parse::<HashAlgorithm>(), Ok(HashAlgorithm::Sha256));
+        assert_eq!(HashAlgorithm::Blake3.as_str(), "blake3");
+        assert!("sha1".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_encoding_from_str() {
+        assert_eq!("zstd".parse::<Encoding>(), Ok(Encoding::Zstd));
+        assert_eq!(Encoding::None.as_str(), "none");
+        assert!("brotli".parse::<Encoding>().is_err());
+    }
+
+    // ── TailFile ──────────────────────────────────────────────────────────────
+
+    const TAIL_FILE_REQUEST_JSON: &str = concat!(
+        r#"{"type":"tail_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/var/log/syslog","lines":100,"bytes":null,"offset":null,"follow":true,"#,
+        r#""timeout":60,"content":null,"next_offset":null,"size":null}"#
+    );
+    const TAIL_FILE_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"tail_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/var/log/syslog","lines":null,"bytes":null,"offset":4096,"follow":false,"#,
+        r#""timeout":60,"content":"started\n","next_offset":4104,"size":4104}"#
+    );
+
+    #[test]
+    fn test_ser_tail_file_request() {
+        let form = ControlForm::TailFile(TailFile {
+            path: "/var/log/syslog".into(),
+            lines: Some(100),
+            follow: true,
+            ..Default::default()
+        });
+        assert_ser_eq(&form, TAIL_FILE_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_tail_file_response() {
+        let form = ControlForm::TailFile(TailFile {
+            path: "/var/log/syslog".into(),
+            offset: Some(4096),
+            content: Some("started\n".into()),
+            next_offset: Some(4104),
+            size: Some(4104),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, TAIL_FILE_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_tail_file_request() {
+        assert_deser_roundtrip::<ControlForm>(TAIL_FILE_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_deser_tail_file_response() {
+        assert_deser_roundtrip::<ControlForm>(TAIL_FILE_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_tail_file_defaults() {
+        let json = r#"{"type":"tail_file","path":"/var/log/syslog"}"#;
+        match serde_json::from_str::<ControlForm>(json).unwrap() {
+            ControlForm::TailFile(f) => {
+                assert!(!f.follow);
+                assert_eq!(f.timeout, 60);
+                assert!(f.lines.is_none());
+            }
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    // ── ReadChunk / WriteChunk / TransferStatus / CommitTransfer ──────────────
+
+    const READ_CHUNK_JSON: &str = concat!(
+        r#"{"type":"read_chunk","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/tmp/big.iso","offset":1048576,"length":1048576,"b64zlib":"abc123","encoding":"zlib","#,
+        r#""md5sum":"d8e8fca2dc0f896fd7cb4cb0031ba249","hash_algorithm":"md5","#,
+        r#""hash":"d8e8fca2dc0f896fd7cb4cb0031ba249","size":3145728,"#,
+        r#""mode":null,"uid":null,"gid":null,"mtime":null}"#
+    );
+    const WRITE_CHUNK_JSON: &str = concat!(
+        r#"{"type":"write_chunk","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""trnuuid":"t1","offset":1048576,"b64zlib":"abc123","encoding":"zlib","#,
+        r#""md5sum":"d8e8fca2dc0f896fd7cb4cb0031ba249","hash_algorithm":"md5","hash":null,"#,
+        r#""received":null}"#
+    );
+    const TRANSFER_STATUS_JSON: &str = concat!(
+        r#"{"type":"transfer_status","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""trnuuid":"t1","received":2097152}"#
+    );
+    const COMMIT_TRANSFER_JSON: &str = concat!(
+        r#"{"type":"commit_transfer","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""trnuuid":"t1","path":"/tmp/big.iso","size":3145728,"md5sum":null,"#,
+        r#""hash_algorithm":"md5","hash":null,"mode":null,"uid":null,"gid":null,"mtime":null}"#
+    );
+
+    #[test]
+    fn test_ser_read_chunk() {
+        let form = ControlForm::ReadChunk(ReadChunk {
+            path: "/tmp/big.iso".into(),
+            offset: 1048576,
+            length: 1048576,
+            b64zlib: Some("abc123".into()),
+            md5sum: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
+            hash: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
+            size: Some(3145728),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, READ_CHUNK_JSON);
+    }
+
+    #[test]
+    fn test_ser_write_chunk() {
+        let form = ControlForm::WriteChunk(WriteChunk {
+            trnuuid: "t1".into(),
+            offset: 1048576,
+            b64zlib: "abc123".into(),
+            md5sum: "d8e8fca2dc0f896fd7cb4cb0031ba249".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, WRITE_CHUNK_JSON);
+    }
+
+    #[test]
+    fn test_ser_transfer_status() {
+        let form = ControlForm::TransferStatus(TransferStatus {
+            trnuuid: "t1".into(),
+            received: Some(2097152),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, TRANSFER_STATUS_JSON);
+    }
+
+    #[test]
+    fn test_ser_commit_transfer() {
+        let form = ControlForm::CommitTransfer(CommitTransfer {
+            trnuuid: "t1".into(),
+            path: "/tmp/big.iso".into(),
+            size: 3145728,
+            ..Default::default()
+        });
+        assert_ser_eq(&form, COMMIT_TRANSFER_JSON);
+    }
+
+    #[test]
+    fn test_deser_read_chunk() {
+        assert_deser_roundtrip::<ControlForm>(READ_CHUNK_JSON);
+    }
+
+    #[test]
+    fn test_deser_write_chunk() {
+        assert_deser_roundtrip::<ControlForm>(WRITE_CHUNK_JSON);
+    }
+
+    #[test]
+    fn test_deser_transfer_status() {
+        assert_deser_roundtrip::<ControlForm>(TRANSFER_STATUS_JSON);
+    }
+
+    #[test]
+    fn test_deser_commit_transfer() {
+        assert_deser_roundtrip::<ControlForm>(COMMIT_TRANSFER_JSON);
+    }
+
+    // ── ListDirectory / StatFile ──────────────────────────────────────────────
+
+    const LIST_DIRECTORY_REQUEST_JSON: &str = concat!(
+        r#"{"type":"list_directory","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/etc","depth":1,"pattern":null,"entries":[]}"#
+    );
+    const LIST_DIRECTORY_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"list_directory","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/etc","depth":2,"pattern":"*.conf","entries":["#,
+        r#"{"name":"ssh/sshd.conf","kind":"file","size":3289,"mode":420,"#,
+        r#""owner":"root","group":"root","mtime":1000.5,"target":null},"#,
+        r#"{"name":"localtime","kind":"symlink","size":27,"mode":511,"#,
+        r#""owner":"root","group":"root","mtime":1000.0,"target":"/usr/share/zoneinfo/UTC"}]}"#
+    );
+    const STAT_FILE_JSON: &str = concat!(
+        r#"{"type":"stat_file","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/etc/hosts","entry":{"name":"hosts","kind":"file","size":174,"#,
+        r#""mode":420,"owner":"root","group":"root","mtime":1000.5,"target":null}}"#
+    );
+
+    fn hosts_entry(name: &str) -> FileEntry {
+        FileEntry {
+            name: name.into(),
+            kind: "file".into(),
+            size: 174,
+            mode: Some(0o644),
+            owner: Some("root".into()),
+            group: Some("root".into()),
+            mtime: Some(1000.5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ser_list_directory_request() {
+        let form = ControlForm::ListDirectory(ListDirectory {
+            path: "/etc".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, LIST_DIRECTORY_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_list_directory_response() {
+        let form = ControlForm::ListDirectory(ListDirectory {
+            path: "/etc".into(),
+            depth: 2,
+            pattern: Some("*.conf".into()),
+            entries: vec![
+                FileEntry { size: 3289, ..hosts_entry("ssh/sshd.conf") },
+                FileEntry {
+                    name: "localtime".into(),
+                    kind: "symlink".into(),
+                    size: 27,
+                    mode: Some(0o777),
+                    mtime: Some(1000.0),
+                    target: Some("/usr/share/zoneinfo/UTC".into()),
+                    ..hosts_entry("")
+                },
+            ],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, LIST_DIRECTORY_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_ser_stat_file() {
+        let form = ControlForm::StatFile(StatFile {
+            path: "/etc/hosts".into(),
+            entry: Some(hosts_entry("hosts")),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, STAT_FILE_JSON);
+    }
+
+    #[test]
+    fn test_deser_list_directory_request() {
+        assert_deser_roundtrip::<ControlForm>(LIST_DIRECTORY_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_deser_list_directory_response() {
+        assert_deser_roundtrip::<ControlForm>(LIST_DIRECTORY_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_list_directory_defaults() {
+        let json = r#"{"type":"list_directory","path":"/etc","pattern":null,"error":null,"objuuid":null,"coluuid":null}"#;
+        match serde_json::from_str::<ControlForm>(json).unwrap() {
+            ControlForm::ListDirectory(f) => {
+                assert_eq!(f.depth, 1);
+                assert!(f.entries.is_empty());
+            }
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_deser_stat_file() {
+        assert_deser_roundtrip::<ControlForm>(STAT_FILE_JSON);
+    }
+
+    // ── PackArchive / UnpackArchive / DiscardArchive ──────────────────────────
+
+    const PACK_ARCHIVE_JSON: &str = concat!(
+        r#"{"type":"pack_archive","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/opt/app","archive":"/tmp/stembot-a1.tar.gz","size":2048,"entries":["#,
+        r#"{"name":"bin","kind":"directory","size":0,"error":null},"#,
+        r#"{"name":"bin/app","kind":"file","size":4096,"error":null},"#,
+        r#"{"name":"secret","kind":"file","size":12,"error":"Permission denied (os error 13)"}]}"#
+    );
+    const UNPACK_ARCHIVE_JSON: &str = concat!(
+        r#"{"type":"unpack_archive","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""archive":"/opt/app.t1.tar.gz","path":"/opt/app","entries":[]}"#
+    );
+    const DISCARD_ARCHIVE_JSON: &str = concat!(
+        r#"{"type":"discard_archive","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""archive":"/tmp/stembot-a1.tar.gz"}"#
+    );
+
+    #[test]
+    fn test_ser_pack_archive() {
+        let entry = |name: &str, kind: &str, size| ArchiveEntry {
+            name: name.into(),
+            kind: kind.into(),
+            size,
+            error: None,
+        };
+        let form = ControlForm::PackArchive(PackArchive {
+            path: "/opt/app".into(),
+            archive: Some("/tmp/stembot-a1.tar.gz".into()),
+            size: Some(2048),
+            entries: vec![
+                entry("bin", "directory", 0),
+                entry("bin/app", "file", 4096),
+                ArchiveEntry {
+                    error: Some("Permission denied (os error 13)".into()),
+                    ..entry("secret", "file", 12)
+                },
+            ],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, PACK_ARCHIVE_JSON);
+    }
+
+    #[test]
+    fn test_ser_unpack_archive() {
+        let form = ControlForm::UnpackArchive(UnpackArchive {
+            archive: "/opt/app.t1.tar.gz".into(),
+            path: "/opt/app".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, UNPACK_ARCHIVE_JSON);
+    }
+
+    #[test]
+    fn test_ser_discard_archive() {
+        let form = ControlForm::DiscardArchive(DiscardArchive {
+            archive: "/tmp/stembot-a1.tar.gz".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, DISCARD_ARCHIVE_JSON);
+    }
+
+    #[test]
+    fn test_deser_pack_archive() {
+        assert_deser_roundtrip::<ControlForm>(PACK_ARCHIVE_JSON);
+    }
+
+    #[test]
+    fn test_deser_unpack_archive() {
+        assert_deser_roundtrip::<ControlForm>(UNPACK_ARCHIVE_JSON);
+    }
+
+    #[test]
+    fn test_deser_discard_archive() {
+        assert_deser_roundtrip::<ControlForm>(DISCARD_ARCHIVE_JSON);
+    }
+
+    // ── DeletePath / MovePath / MakeDirectory / SetPermissions ────────────────
+
+    const DELETE_PATH_JSON: &str = concat!(
+        r#"{"type":"delete_path","error":"Directory not empty (os error 39)","objuuid":null,"coluuid":null,"#,
+        r#""path":"/opt/app","recursive":false,"error_kind":"not_empty"}"#
     );
-    const LOAD_FILE_RESPONSE_JSON: &str = concat!(
-        r#"{"type":"load_file","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""b64zlib":"abc123","path":"/etc/hosts","size":1024,"#,
-        r#""md5sum":"d8e8fca2dc0f896fd7cb4cb0031ba249"}"#
+    const MOVE_PATH_JSON: &str = concat!(
+        r#"{"type":"move_path","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""src":"/tmp/app.conf","dst":"/etc/app.conf","overwrite":true,"error_kind":null}"#
+    );
+    const MAKE_DIRECTORY_JSON: &str = concat!(
+        r#"{"type":"make_directory","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/opt/app/logs","parents":true,"mode":488,"error_kind":null}"#
+    );
+    const SET_PERMISSIONS_JSON: &str = concat!(
+        r#"{"type":"set_permissions","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""path":"/opt/app","mode":493,"owner":"app","group":null,"recursive":true,"error_kind":null}"#
     );
 
     #[test]
-    fn test_ser_load_file_request() {
-        let form = ControlForm::LoadFile(LoadFile {
-            path: "/etc/hosts".into(),
+    fn test_ser_delete_path() {
+        let form = ControlForm::DeletePath(DeletePath {
+            path: "/opt/app".into(),
+            error_kind: Some("not_empty".into()),
+            error: Some("Directory not empty (os error 39)".into()),
             ..Default::default()
         });
-        assert_ser_eq(&form, LOAD_FILE_REQUEST_JSON);
+        assert_ser_eq(&form, DELETE_PATH_JSON);
     }
 
     #[test]
-    fn test_ser_load_file_response() {
-        let form = ControlForm::LoadFile(LoadFile {
-            path: "/etc/hosts".into(),
-            b64zlib: Some("abc123".into()),
-            size: Some(1024),
-            md5sum: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
+    fn test_ser_move_path() {
+        let form = ControlForm::MovePath(MovePath {
+            src: "/tmp/app.conf".into(),
+            dst: "/etc/app.conf".into(),
+            overwrite: true,
             ..Default::default()
         });
-        assert_ser_eq(&form, LOAD_FILE_RESPONSE_JSON);
+        assert_ser_eq(&form, MOVE_PATH_JSON);
     }
 
     #[test]
-    fn test_deser_load_file_request() {
-        assert_deser_roundtrip::<ControlForm>(LOAD_FILE_REQUEST_JSON);
+    fn test_ser_make_directory() {
+        let form = ControlForm::MakeDirectory(MakeDirectory {
+            path: "/opt/app/logs".into(),
+            parents: true,
+            mode: Some(0o750),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, MAKE_DIRECTORY_JSON);
     }
 
     #[test]
-    fn test_deser_load_file_response() {
-        assert_deser_roundtrip::<ControlForm>(LOAD_FILE_RESPONSE_JSON);
+    fn test_ser_set_permissions() {
+        let form = ControlForm::SetPermissions(SetPermissions {
+            path: "/opt/app".into(),
+            mode: Some(0o755),
+            owner: Some("app".into()),
+            recursive: true,
+            ..Default::default()
+        });
+        assert_ser_eq(&form, SET_PERMISSIONS_JSON);
     }
 
-    // ── WriteFile ─────────────────────────────────────────────────────────────
-
-    const WRITE_FILE_REQUEST_JSON: &str = concat!(
-        r#"{"type":"write_file","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""b64zlib":"abc123","path":"/tmp/out.txt","size":null,"md5sum":null}"#
-    );
-    const WRITE_FILE_RESPONSE_JSON: &str = concat!(
-        r#"{"type":"write_file","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""b64zlib":"abc123","path":"/tmp/out.txt","size":6,"#,
-        r#""md5sum":"d8e8fca2dc0f896fd7cb4cb0031ba249"}"#
-    );
+    #[test]
+    fn test_deser_delete_path() {
+        assert_deser_roundtrip::<ControlForm>(DELETE_PATH_JSON);
+    }
 
     #[test]
-    fn test_ser_write_file_request() {
-        let form = ControlForm::WriteFile(WriteFile {
-            b64zlib: "abc123".into(),
-            path: "/tmp/out.txt".into(),
-            ..Default::default()
-        });
-        assert_ser_eq(&form, WRITE_FILE_REQUEST_JSON);
+    fn test_deser_move_path() {
+        assert_deser_roundtrip::<ControlForm>(MOVE_PATH_JSON);
     }
 
     #[test]
-    fn test_ser_write_file_response() {
-        let form = ControlForm::WriteFile(WriteFile {
-            b64zlib: "abc123".into(),
-            path: "/tmp/out.txt".into(),
-            size: Some(6),
-            md5sum: Some("d8e8fca2dc0f896fd7cb4cb0031ba249".into()),
-            ..Default::default()
-        });
-        assert_ser_eq(&form, WRITE_FILE_RESPONSE_JSON);
+    fn test_deser_make_directory() {
+        assert_deser_roundtrip::<ControlForm>(MAKE_DIRECTORY_JSON);
     }
 
     #[test]
-    fn test_deser_write_file_request() {
-        assert_deser_roundtrip::<ControlForm>(WRITE_FILE_REQUEST_JSON);
+    fn test_deser_set_permissions() {
+        assert_deser_roundtrip::<ControlForm>(SET_PERMISSIONS_JSON);
     }
 
     #[test]
-    fn test_deser_write_file_response() {
-        assert_deser_roundtrip::<ControlForm>(WRITE_FILE_RESPONSE_JSON);
+    fn test_deser_delete_path_defaults() {
+        let json = r#"{"type":"delete_path","path":"/tmp/x","error_kind":null,"error":null,"objuuid":null,"coluuid":null}"#;
+        match serde_json::from_str::<ControlForm>(json).unwrap() {
+            ControlForm::DeletePath(f) => assert!(!f.recursive),
+            _ => panic!("wrong variant"),
+        }
     }
 
     // ── SyncProcess ───────────────────────────────────────────────────────────
 
     const SYNC_PROCESS_STR_CMD_JSON: &str = concat!(
         r#"{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":"ls /","stdout":null,"stderr":null,"#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":"ls /","stdout":null,"stderr":null,"#,
         r#""status":null,"start_time":null,"elapsed_time":null}"#
     );
     const SYNC_PROCESS_LIST_CMD_JSON: &str = concat!(
         r#"{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":["ls","/"],"stdout":null,"stderr":null,"#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":["ls","/"],"stdout":null,"stderr":null,"#,
         r#""status":null,"start_time":null,"elapsed_time":null}"#
     );
     const SYNC_PROCESS_RESPONSE_JSON: &str = concat!(
         r#"{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":"ls /","stdout":"bin\nboot\n","stderr":""#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":"ls /","stdout":"bin\nboot\n","stderr":""#,
         r#"","status":0,"start_time":1000.0,"elapsed_time":0.01}"#
     );
 
@@ -434,9 +1901,7 @@ mod tests {
         let form = ControlForm::SyncProcess(SyncProcess {
             command: CommandArg::Single("ls /".into()),
             timeout: 15,
-            stdout: None, stderr: None, status: None,
-            start_time: None, elapsed_time: None,
-            error: None, objuuid: None, coluuid: None,
+            ..Default::default()
         });
         assert_ser_eq(&form, SYNC_PROCESS_STR_CMD_JSON);
     }
@@ -446,9 +1911,7 @@ mod tests {
         let form = ControlForm::SyncProcess(SyncProcess {
             command: CommandArg::Multi(vec!["ls".into(), "/".into()]),
             timeout: 15,
-            stdout: None, stderr: None, status: None,
-            start_time: None, elapsed_time: None,
-            error: None, objuuid: None, coluuid: None,
+            ..Default::default()
         });
         assert_ser_eq(&form, SYNC_PROCESS_LIST_CMD_JSON);
     }
@@ -463,7 +1926,7 @@ mod tests {
             status: Some(0),
             start_time: Some(1000.0),
             elapsed_time: Some(0.01),
-            error: None, objuuid: None, coluuid: None,
+            ..Default::default()
         });
         assert_ser_eq(&form, SYNC_PROCESS_RESPONSE_JSON);
     }
@@ -483,6 +1946,99 @@ mod tests {
         assert_deser_roundtrip::<ControlForm>(SYNC_PROCESS_RESPONSE_JSON);
     }
 
+    // ── StartProcess / ProcessStatus / KillProcess / ListProcesses ────────────
+
+    const START_PROCESS_JSON: &str = concat!(
+        r#"{"type":"start_process","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""command":"sleep 60","cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"#,
+        r#""prcuuid":"p1","start_time":1000.0}"#
+    );
+    const PROCESS_STATUS_JSON: &str = concat!(
+        r#"{"type":"process_status","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""prcuuid":"p1","running":false,"stdout":"done\n","stderr":"","#,
+        r#""status":0,"start_time":1000.0,"elapsed_time":2.5}"#
+    );
+    const KILL_PROCESS_JSON: &str = concat!(
+        r#"{"type":"kill_process","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""prcuuid":"p1","status":null,"signal":null}"#
+    );
+    const LIST_PROCESSES_JSON: &str = concat!(
+        r#"{"type":"list_processes","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""processes":[{"prcuuid":"p1","command":["sleep","60"],"running":true,"#,
+        r#""status":null,"start_time":1000.0,"elapsed_time":1.0}]}"#
+    );
+
+    #[test]
+    fn test_ser_start_process() {
+        let form = ControlForm::StartProcess(StartProcess {
+            command: CommandArg::Single("sleep 60".into()),
+            prcuuid: Some("p1".into()),
+            start_time: Some(1000.0),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, START_PROCESS_JSON);
+    }
+
+    #[test]
+    fn test_ser_process_status() {
+        let form = ControlForm::ProcessStatus(ProcessStatus {
+            prcuuid: "p1".into(),
+            running: false,
+            stdout: Some("done\n".into()),
+            stderr: Some("".into()),
+            status: Some(0),
+            start_time: Some(1000.0),
+            elapsed_time: Some(2.5),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, PROCESS_STATUS_JSON);
+    }
+
+    #[test]
+    fn test_ser_kill_process() {
+        let form = ControlForm::KillProcess(KillProcess {
+            prcuuid: "p1".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, KILL_PROCESS_JSON);
+    }
+
+    #[test]
+    fn test_ser_list_processes() {
+        let form = ControlForm::ListProcesses(ListProcesses {
+            processes: vec![ProcessInfo {
+                prcuuid: "p1".into(),
+                command: CommandArg::Multi(vec!["sleep".into(), "60".into()]),
+                running: true,
+                status: None,
+                start_time: 1000.0,
+                elapsed_time: 1.0,
+            }],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, LIST_PROCESSES_JSON);
+    }
+
+    #[test]
+    fn test_deser_start_process() {
+        assert_deser_roundtrip::<ControlForm>(START_PROCESS_JSON);
+    }
+
+    #[test]
+    fn test_deser_process_status() {
+        assert_deser_roundtrip::<ControlForm>(PROCESS_STATUS_JSON);
+    }
+
+    #[test]
+    fn test_deser_kill_process() {
+        assert_deser_roundtrip::<ControlForm>(KILL_PROCESS_JSON);
+    }
+
+    #[test]
+    fn test_deser_list_processes() {
+        assert_deser_roundtrip::<ControlForm>(LIST_PROCESSES_JSON);
+    }
+
     // ── CreatePeer ────────────────────────────────────────────────────────────
 
     // Python's HttpUrl normalises by appending a trailing slash
@@ -574,7 +2130,7 @@ mod tests {
     const GET_PEERS_DATA_JSON: &str = concat!(
         r#"{"type":"get_peers","error":null,"objuuid":null,"coluuid":null,"#,
         r#""peers":[{"agtuuid":"a2","polling":false,"destroy_time":2000.0,"#,
-        r#""refresh_time":1000.0,"url":"http://10.0.0.2:8080","objuuid":null,"coluuid":null}]}"#
+        r#""refresh_time":1000.0,"url":"http://10.0.0.2:8080","name":null,"labels":{},"encodings":[],"objuuid":null,"coluuid":null}]}"#
     );
 
     #[test]
@@ -593,6 +2149,9 @@ mod tests {
                 destroy_time: Some(2000.0),
                 refresh_time: Some(1000.0),
                 url: Some("http://10.0.0.2:8080".into()),
+                name: None,
+                labels: Default::default(),
+                encodings: vec![],
                 objuuid: None,
                 coluuid: None,
             }],
@@ -618,7 +2177,7 @@ mod tests {
     );
     const GET_ROUTES_DATA_JSON: &str = concat!(
         r#"{"type":"get_routes","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""routes":[{"agtuuid":"a2","gtwuuid":"a1","weight":1,"objuuid":null,"coluuid":null}]}"#
+        r#""routes":[{"agtuuid":"a2","gtwuuid":"a1","weight":1,"name":null,"labels":{},"objuuid":null,"coluuid":null}]}"#
     );
 
     #[test]
@@ -631,7 +2190,7 @@ mod tests {
     fn test_ser_get_routes_with_data() {
         use crate::models::routing::Route;
         let form = ControlForm::GetRoutes(GetRoutes {
-            routes: vec![Route { agtuuid: "a2".into(), gtwuuid: "a1".into(), weight: 1, objuuid: None, coluuid: None }],
+            routes: vec![Route { agtuuid: "a2".into(), gtwuuid: "a1".into(), weight: 1, name: None, labels: Default::default(), objuuid: None, coluuid: None }],
             ..Default::default()
         });
         assert_ser_eq(&form, GET_ROUTES_DATA_JSON);
@@ -647,6 +2206,111 @@ mod tests {
         assert_deser_roundtrip::<ControlForm>(GET_ROUTES_DATA_JSON);
     }
 
+    // ── GetNames ──────────────────────────────────────────────────────────────
+
+    const GET_NAMES_JSON: &str = concat!(
+        r#"{"type":"get_names","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""names":{"a1":["a1"],"a2":["a2"],"db":["a1","a2"]}}"#
+    );
+
+    #[test]
+    fn test_ser_get_names() {
+        let form = ControlForm::GetNames(GetNames {
+            names: BTreeMap::from([
+                ("a1".to_string(), BTreeSet::from(["a1".to_string()])),
+                ("a2".to_string(), BTreeSet::from(["a2".to_string()])),
+                ("db".to_string(), BTreeSet::from(["a1".to_string(), "a2".to_string()])),
+            ]),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, GET_NAMES_JSON);
+    }
+
+    #[test]
+    fn test_deser_get_names() {
+        assert_deser_roundtrip::<ControlForm>(GET_NAMES_JSON);
+        let form: ControlForm = serde_json::from_str(r#"{"type":"get_names"}"#).unwrap();
+        assert!(matches!(form, ControlForm::GetNames(f) if f.names.is_empty()));
+    }
+
+    // ── SelectAgents ──────────────────────────────────────────────────────────
+
+    const SELECT_AGENTS_JSON: &str = concat!(
+        r#"{"type":"select_agents","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""selector":"role=db","agents":["a1","a2"]}"#
+    );
+
+    #[test]
+    fn test_ser_select_agents() {
+        let form = ControlForm::SelectAgents(SelectAgents {
+            selector: "role=db".to_string(),
+            agents: vec!["a1".to_string(), "a2".to_string()],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, SELECT_AGENTS_JSON);
+    }
+
+    #[test]
+    fn test_deser_select_agents() {
+        assert_deser_roundtrip::<ControlForm>(SELECT_AGENTS_JSON);
+    }
+
+    // ── GetTopology ───────────────────────────────────────────────────────────
+
+    const GET_TOPOLOGY_REQUEST_JSON: &str = concat!(
+        r#"{"type":"get_topology","timeout":10.0,"agents":[],"#,
+        r#""error":null,"objuuid":null,"coluuid":null}"#
+    );
+    const GET_TOPOLOGY_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"get_topology","timeout":10.0,"agents":["#,
+        r#"{"agtuuid":"a1","name":"web-1","labels":{"role":"web"},"reachable":true,"gateway":false,"#,
+        r#""peers":[{"agtuuid":"a2","polling":true,"url":"http://a2:8080/mpi"}],"error":null},"#,
+        r#"{"agtuuid":"a2","name":null,"labels":{},"reachable":false,"gateway":true,"#,
+        r#""peers":[],"error":"no response"}],"#,
+        r#""error":null,"objuuid":null,"coluuid":null}"#
+    );
+
+    #[test]
+    fn test_ser_get_topology_request() {
+        let form = ControlForm::GetTopology(GetTopology { timeout: Some(10.0), ..Default::default() });
+        assert_ser_eq(&form, GET_TOPOLOGY_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_get_topology_response() {
+        let form = ControlForm::GetTopology(GetTopology {
+            timeout: Some(10.0),
+            agents: vec![
+                TopologyNode {
+                    agtuuid: "a1".into(),
+                    name: Some("web-1".into()),
+                    labels: Labels::from([("role".into(), "web".into())]),
+                    reachable: true,
+                    peers: vec![TopologyLink {
+                        agtuuid: "a2".into(),
+                        polling: true,
+                        url: Some("http://a2:8080/mpi".into()),
+                    }],
+                    ..Default::default()
+                },
+                TopologyNode {
+                    agtuuid: "a2".into(),
+                    gateway: true,
+                    error: Some("no response".into()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, GET_TOPOLOGY_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_get_topology() {
+        assert_deser_roundtrip::<ControlForm>(GET_TOPOLOGY_REQUEST_JSON);
+        assert_deser_roundtrip::<ControlForm>(GET_TOPOLOGY_RESPONSE_JSON);
+    }
+
     // ── GetConfig ─────────────────────────────────────────────────────────────
 
     const GET_CONFIG_REQUEST_JSON: &str = concat!(
@@ -682,6 +2346,158 @@ mod tests {
         assert_deser_roundtrip::<ControlForm>(GET_CONFIG_RESPONSE_JSON);
     }
 
+    // ── SetConfig ─────────────────────────────────────────────────────────────
+
+    const SET_CONFIG_JSON: &str = concat!(
+        r#"{"type":"set_config","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""values":{"log_level_app":"DEBUG","workers":4},"restart_required":["workers"]}"#
+    );
+
+    #[test]
+    fn test_ser_set_config() {
+        let form = ControlForm::SetConfig(SetConfig {
+            values: BTreeMap::from([
+                ("log_level_app".to_string(), serde_json::json!("DEBUG")),
+                ("workers".to_string(), serde_json::json!(4)),
+            ]),
+            restart_required: vec!["workers".into()],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, SET_CONFIG_JSON);
+    }
+
+    #[test]
+    fn test_deser_set_config() {
+        assert_deser_roundtrip::<ControlForm>(SET_CONFIG_JSON);
+    }
+
+    // ── SetLogLevel ───────────────────────────────────────────────────────────
+
+    const SET_LOG_LEVEL_JSON: &str = concat!(
+        r#"{"type":"set_log_level","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""app":"DEBUG","api":null,"modules":{"stembot_rust::messaging":null,"stembot_rust::processor":"INFO"}}"#
+    );
+
+    #[test]
+    fn test_ser_set_log_level() {
+        let form = ControlForm::SetLogLevel(SetLogLevel {
+            app: Some("DEBUG".into()),
+            modules: BTreeMap::from([
+                ("stembot_rust::messaging".to_string(), None),
+                ("stembot_rust::processor".to_string(), Some("INFO".into())),
+            ]),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, SET_LOG_LEVEL_JSON);
+    }
+
+    #[test]
+    fn test_deser_set_log_level() {
+        assert_deser_roundtrip::<ControlForm>(SET_LOG_LEVEL_JSON);
+    }
+
+    // ── GetLogs ───────────────────────────────────────────────────────────────
+
+    const GET_LOGS_REQUEST_JSON: &str = concat!(
+        r#"{"type":"get_logs","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""lines":50,"level":"WARNING","module":"stembot_rust","since":1000.0,"until":null,"entries":[]}"#
+    );
+
+    const GET_LOGS_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"get_logs","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""lines":null,"level":null,"module":null,"since":null,"until":null,"#,
+        r#""entries":[{"time":1000.5,"level":"WARN","module":"stembot_rust::processor","#,
+        r#""file":"processor.rs","line":42,"message":"peer unreachable","fields":{"tckuuid":"t1"}}]}"#
+    );
+
+    #[test]
+    fn test_ser_get_logs_request() {
+        let form = ControlForm::GetLogs(GetLogs {
+            lines: Some(50),
+            level: Some("WARNING".into()),
+            module: Some("stembot_rust".into()),
+            since: Some(1000.0),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, GET_LOGS_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_get_logs_response() {
+        let form = ControlForm::GetLogs(GetLogs {
+            entries: vec![LogEntry {
+                time: 1000.5,
+                level: "WARN".into(),
+                module: "stembot_rust::processor".into(),
+                file: "processor.rs".into(),
+                line: 42,
+                message: "peer unreachable".into(),
+                fields: BTreeMap::from([("tckuuid".to_string(), "t1".to_string())]),
+            }],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, GET_LOGS_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_get_logs() {
+        assert_deser_roundtrip::<ControlForm>(GET_LOGS_REQUEST_JSON);
+        assert_deser_roundtrip::<ControlForm>(GET_LOGS_RESPONSE_JSON);
+    }
+
+    // ── GetStats ──────────────────────────────────────────────────────────────
+
+    const GET_STATS_REQUEST_JSON: &str = concat!(
+        r#"{"type":"get_stats","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""version":null,"uptime_secs":null,"queued_messages":{},"tickets_open":null,"#,
+        r#""tickets_serviced":null,"traces":null,"last_advertisement_sent":null,"#,
+        r#""last_advertisement_received":null,"peers":[],"collections":{}}"#
+    );
+
+    const GET_STATS_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"get_stats","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""version":"2.1.1","uptime_secs":60.5,"queued_messages":{"a2":3},"tickets_open":1,"#,
+        r#""tickets_serviced":2,"traces":0,"last_advertisement_sent":1000.0,"#,
+        r#""last_advertisement_received":null,"#,
+        r#""peers":[{"agtuuid":"a2","url":"http://a2:8080/mpi","polling":false,"last_seen":990.0,"#,
+        r#""forward_successes":5,"forward_failures":1}],"collections":{"messages":3}}"#
+    );
+
+    #[test]
+    fn test_ser_get_stats_request() {
+        assert_ser_eq(&ControlForm::GetStats(GetStats::default()), GET_STATS_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_get_stats_response() {
+        let form = ControlForm::GetStats(GetStats {
+            version: Some("2.1.1".into()),
+            uptime_secs: Some(60.5),
+            queued_messages: BTreeMap::from([("a2".to_string(), 3)]),
+            tickets_open: Some(1),
+            tickets_serviced: Some(2),
+            traces: Some(0),
+            last_advertisement_sent: Some(1000.0),
+            peers: vec![PeerStats {
+                agtuuid: "a2".into(),
+                url: Some("http://a2:8080/mpi".into()),
+                polling: false,
+                last_seen: Some(990.0),
+                forward_successes: 5,
+                forward_failures: 1,
+            }],
+            collections: BTreeMap::from([("messages".to_string(), 3)]),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, GET_STATS_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_get_stats() {
+        assert_deser_roundtrip::<ControlForm>(GET_STATS_REQUEST_JSON);
+        assert_deser_roundtrip::<ControlForm>(GET_STATS_RESPONSE_JSON);
+    }
+
     // ── Hop ───────────────────────────────────────────────────────────────────
 
     const HOP_JSON: &str =
@@ -703,18 +2519,20 @@ mod tests {
     const CFT_CREATE_JSON: &str = concat!(
         r#"{"type":"create_ticket","error":null,"objuuid":null,"coluuid":null,"#,
         r#""tckuuid":"t1","src":"a1","dst":"a2","create_time":1000.0,"#,
-        r#""service_time":null,"tracing":false,"hops":[],"#,
+        r#""service_time":null,"tracing":false,"hops":[],"progress":[],"queue_position":null,"#,
+        r#""dsts":[],"selector":null,"parent":null,"results":[],"counts":null,"#,
         r#""form":{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":"ls /","stdout":null,"stderr":null,"#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":"ls /","stdout":null,"stderr":null,"#,
         r#""status":null,"start_time":null,"elapsed_time":null}}"#
     );
     const CFT_READ_WITH_HOPS_JSON: &str = concat!(
         r#"{"type":"read_ticket","error":null,"objuuid":null,"coluuid":null,"#,
         r#""tckuuid":"t1","src":"a1","dst":"a2","create_time":1000.0,"#,
         r#""service_time":0.5,"tracing":true,"#,
-        r#""hops":[{"agtuuid":"a1","hop_time":1001.0,"type_str":"ticket_request"}],"#,
+        r#""hops":[{"agtuuid":"a1","hop_time":1001.0,"type_str":"ticket_request"}],"progress":[],"queue_position":null,"#,
+        r#""dsts":[],"selector":null,"parent":null,"results":[],"counts":null,"#,
         r#""form":{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":"ls /","stdout":null,"stderr":null,"#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":"ls /","stdout":null,"stderr":null,"#,
         r#""status":null,"start_time":null,"elapsed_time":null}}"#
     );
 
@@ -722,9 +2540,7 @@ mod tests {
         ControlForm::SyncProcess(SyncProcess {
             command: CommandArg::Single("ls /".into()),
             timeout: 15,
-            stdout: None, stderr: None, status: None,
-            start_time: None, elapsed_time: None,
-            error: None, objuuid: None, coluuid: None,
+            ..Default::default()
         })
     }
 
@@ -739,6 +2555,13 @@ mod tests {
             service_time: None,
             tracing: false,
             hops: vec![],
+            progress: vec![],
+            queue_position: None,
+            dsts: vec![],
+            selector: None,
+            parent: None,
+            results: vec![],
+            counts: None,
             form: sync_process_ls(),
             error: None, objuuid: None, coluuid: None,
         };
@@ -756,6 +2579,13 @@ mod tests {
             service_time: Some(0.5),
             tracing: true,
             hops: vec![Hop { agtuuid: "a1".into(), hop_time: 1001.0, type_str: "ticket_request".into() }],
+            progress: vec![],
+            queue_position: None,
+            dsts: vec![],
+            selector: None,
+            parent: None,
+            results: vec![],
+            counts: None,
             form: sync_process_ls(),
             error: None, objuuid: None, coluuid: None,
         };
@@ -772,15 +2602,68 @@ mod tests {
         assert_deser_roundtrip::<ControlFormTicket>(CFT_READ_WITH_HOPS_JSON);
     }
 
+    const CFT_READ_MULTICAST_JSON: &str = concat!(
+        r#"{"type":"read_ticket","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""tckuuid":"t1","src":"a1","dst":"","create_time":1000.0,"#,
+        r#""service_time":null,"tracing":false,"hops":[],"progress":[],"queue_position":null,"#,
+        r#""dsts":["a2","a3"],"selector":null,"parent":null,"#,
+        r#""results":[{"type":"create_ticket","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""tckuuid":"t2","src":"a1","dst":"a2","create_time":1000.0,"#,
+        r#""service_time":1000.5,"tracing":false,"hops":[],"progress":[],"queue_position":null,"#,
+        r#""dsts":[],"selector":null,"parent":"t1","results":[],"counts":null,"#,
+        r#""form":{"type":"get_config","error":null,"objuuid":null,"coluuid":null,"config":null}}],"#,
+        r#""counts":{"total":2,"serviced":1,"pending":1},"#,
+        r#""form":{"type":"get_config","error":null,"objuuid":null,"coluuid":null,"config":null}}"#
+    );
+
+    #[test]
+    fn test_ser_control_form_ticket_read_multicast() {
+        let ticket = ControlFormTicket {
+            form_type: "read_ticket".to_string(),
+            tckuuid: "t1".into(),
+            src: "a1".into(),
+            create_time: 1000.0,
+            dsts: vec!["a2".into(), "a3".into()],
+            results: vec![ControlFormTicket {
+                tckuuid: "t2".into(),
+                src: "a1".into(),
+                dst: "a2".into(),
+                create_time: 1000.0,
+                service_time: Some(1000.5),
+                parent: Some("t1".into()),
+                ..ControlFormTicket::default()
+            }],
+            counts: Some(TicketCounts { total: 2, serviced: 1, pending: 1 }),
+            ..ControlFormTicket::default()
+        };
+        assert!(ticket.is_multicast());
+        assert!(!ticket.results[0].is_multicast());
+        assert_ser_eq(&ticket, CFT_READ_MULTICAST_JSON);
+    }
+
+    #[test]
+    fn test_deser_control_form_ticket_read_multicast() {
+        assert_deser_roundtrip::<ControlFormTicket>(CFT_READ_MULTICAST_JSON);
+    }
+
+    #[test]
+    fn test_deser_control_form_ticket_selector() {
+        let json = r#"{"type":"create_ticket","selector":"*","form":{"type":"get_config"}}"#;
+        let ticket: ControlFormTicket = serde_json::from_str(json).unwrap();
+        assert!(ticket.is_multicast());
+        assert!(ticket.dsts.is_empty());
+        assert_eq!(ticket.selector.as_deref(), Some("*"));
+    }
+
     // ── CheckTicket ───────────────────────────────────────────────────────────
 
     const CHECK_TICKET_PENDING_JSON: &str = concat!(
         r#"{"type":"check_ticket","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""tckuuid":"t1","create_time":1000.0,"service_time":null}"#
+        r#""tckuuid":"t1","create_time":1000.0,"service_time":null,"queue_position":null,"last_seq":null}"#
     );
     const CHECK_TICKET_SERVICED_JSON: &str = concat!(
         r#"{"type":"check_ticket","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""tckuuid":"t1","create_time":1000.0,"service_time":0.5}"#
+        r#""tckuuid":"t1","create_time":1000.0,"service_time":0.5,"queue_position":null,"last_seq":null}"#
     );
 
     #[test]
@@ -814,6 +2697,64 @@ mod tests {
         assert_deser_roundtrip::<ControlForm>(CHECK_TICKET_SERVICED_JSON);
     }
 
+    // ── WaitTickets ───────────────────────────────────────────────────────────
+
+    const WAIT_TICKETS_REQUEST_JSON: &str = concat!(
+        r#"{"type":"wait_tickets","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""tckuuids":["t1","t2"],"timeout":30.0,"tickets":[]}"#
+    );
+    const WAIT_TICKETS_RESPONSE_JSON: &str = concat!(
+        r#"{"type":"wait_tickets","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""tckuuids":["t1","t2"],"timeout":30.0,"tickets":["#,
+        r#"{"tckuuid":"t1","create_time":1000.0,"service_time":1000.5,"queue_position":null,"#,
+        r#""last_seq":null,"error":null,"objuuid":null,"coluuid":null}]}"#
+    );
+
+    #[test]
+    fn test_ser_wait_tickets_request() {
+        let form = ControlForm::WaitTickets(WaitTickets {
+            tckuuids: vec!["t1".into(), "t2".into()],
+            timeout: Some(30.0),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, WAIT_TICKETS_REQUEST_JSON);
+    }
+
+    #[test]
+    fn test_ser_wait_tickets_response() {
+        let form = ControlForm::WaitTickets(WaitTickets {
+            tckuuids: vec!["t1".into(), "t2".into()],
+            timeout: Some(30.0),
+            tickets: vec![CheckTicket {
+                tckuuid: "t1".into(),
+                create_time: Some(1000.0),
+                service_time: Some(1000.5),
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        assert_ser_eq(&form, WAIT_TICKETS_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_wait_tickets() {
+        assert_deser_roundtrip::<ControlForm>(WAIT_TICKETS_REQUEST_JSON);
+        assert_deser_roundtrip::<ControlForm>(WAIT_TICKETS_RESPONSE_JSON);
+    }
+
+    #[test]
+    fn test_deser_wait_tickets_without_tickets() {
+        let json = r#"{"type":"wait_tickets","tckuuids":["t1"]}"#;
+        match serde_json::from_str::<ControlForm>(json).unwrap() {
+            ControlForm::WaitTickets(f) => {
+                assert_eq!(f.tckuuids, vec!["t1".to_string()]);
+                assert!(f.tickets.is_empty());
+                assert!(f.timeout.is_none());
+            }
+            other => panic!("expected WaitTickets, got {other:?}"),
+        }
+    }
+
     // ── CloseTicket ───────────────────────────────────────────────────────────
 
     const CLOSE_TICKET_JSON: &str = concat!(
@@ -834,5 +2775,26 @@ mod tests {
     fn test_deser_close_ticket() {
         assert_deser_roundtrip::<ControlForm>(CLOSE_TICKET_JSON);
     }
+
+    // ── CancelTicket ──────────────────────────────────────────────────────────
+
+    const CANCEL_TICKET_JSON: &str = concat!(
+        r#"{"type":"cancel_ticket","error":null,"objuuid":null,"coluuid":null,"#,
+        r#""tckuuid":"t1"}"#
+    );
+
+    #[test]
+    fn test_ser_cancel_ticket() {
+        let form = ControlForm::CancelTicket(CancelTicket {
+            tckuuid: "t1".into(),
+            ..Default::default()
+        });
+        assert_ser_eq(&form, CANCEL_TICKET_JSON);
+    }
+
+    #[test]
+    fn test_deser_cancel_ticket() {
+        assert_deser_roundtrip::<ControlForm>(CANCEL_TICKET_JSON);
+    }
 }
 
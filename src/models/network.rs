@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-use crate::models::control::{ControlForm, Hop};
-use crate::models::routing::Route;
+use crate::models::control::{ControlForm, Hop, Progress};
+use crate::models::routing::{Labels, Route};
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
@@ -75,6 +75,13 @@ pub struct Acknowledgement {
     pub timestamp: Option<f64>,
     pub forwarded: Option<String>,
     pub error:     Option<String>,
+    /// Display name of the acknowledging agent, given in answer to a `Ping`.
+    #[serde(default)]
+    pub name:      Option<String>,
+    /// Body encodings the acknowledging agent accepts, given in answer to a
+    /// `Ping`.  Agents that predate body encodings leave it empty.
+    #[serde(default)]
+    pub encodings: Vec<String>,
     pub objuuid:   Option<String>,
     pub coluuid:   Option<String>,
 }
@@ -89,6 +96,8 @@ impl Default for Acknowledgement {
             timestamp: None,
             forwarded: None,
             error:     None,
+            name:      None,
+            encodings: Vec::new(),
             objuuid:   None,
             coluuid:   None,
         }
@@ -102,6 +111,12 @@ pub struct Advertisement {
     pub agtuuid:   String,
     #[serde(default)]
     pub routes:    Vec<Route>,
+    /// Display name of the advertising agent.
+    #[serde(default)]
+    pub name:      Option<String>,
+    /// Labels of the advertising agent.
+    #[serde(default)]
+    pub labels:    Labels,
     #[serde(default)]
     pub src:       String,
     pub dest:      Option<String>,
@@ -199,6 +214,60 @@ pub struct NetworkTicket {
     pub coluuid:      Option<String>,
 }
 
+/// Partial output or queue status for a ticket that is still being serviced.
+/// Sent by the servicing agent back to the ticket's originator.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TicketProgress {
+    pub tckuuid:        String,
+    #[serde(default)]
+    pub seq:            u64,
+    pub stdout:         Option<String>,
+    pub stderr:         Option<String>,
+    /// Where `stdout` starts in the command's whole stdout, in bytes.
+    pub stdout_offset:  Option<u64>,
+    /// Where `stderr` starts in the command's whole stderr, in bytes.
+    pub stderr_offset:  Option<u64>,
+    /// Position in the servicing agent's process queue; `0` once running.
+    pub queue_position: Option<u64>,
+    #[serde(default)]
+    pub src:            String,
+    pub dest:           Option<String>,
+    pub isrc:           Option<String>,
+    #[serde(default = "unix_now_opt")]
+    pub timestamp:      Option<f64>,
+    pub objuuid:        Option<String>,
+    pub coluuid:        Option<String>,
+}
+
+impl TicketProgress {
+    /// Returns the `Progress` entry recorded on the originator's ticket.
+    pub fn progress(&self) -> Progress {
+        Progress {
+            seq:           self.seq,
+            stdout:        self.stdout.clone(),
+            stderr:        self.stderr.clone(),
+            stdout_offset: self.stdout_offset,
+            stderr_offset: self.stderr_offset,
+            progress_time: self.timestamp.unwrap_or_else(unix_now_f64),
+        }
+    }
+}
+
+/// Request from a ticket's originator that the servicing agent stop work on
+/// the ticket early.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TicketCancel {
+    pub tckuuid:   String,
+    #[serde(default)]
+    pub src:       String,
+    pub dest:      Option<String>,
+    pub isrc:      Option<String>,
+    #[serde(default = "unix_now_opt")]
+    pub timestamp: Option<f64>,
+    pub objuuid:   Option<String>,
+    pub coluuid:   Option<String>,
+}
+
 // ── Tagged union of all network message variants ──────────────────────────────
 
 /// Internally-tagged union of all network message types.
@@ -217,6 +286,8 @@ pub enum NetworkMessage {
     #[serde(rename = "ticket_trace_response")] TicketTraceResponse(TicketTraceResponse),
     #[serde(rename = "ticket_request")]        TicketRequest(NetworkTicket),
     #[serde(rename = "ticket_response")]       TicketResponse(NetworkTicket),
+    #[serde(rename = "ticket_progress")]       TicketProgress(TicketProgress),
+    #[serde(rename = "ticket_cancel")]         TicketCancel(TicketCancel),
 }
 
 impl Default for NetworkMessage {
@@ -237,6 +308,20 @@ impl NetworkMessage {
             Self::TicketTraceResponse(_) => "ticket_trace_response",
             Self::TicketRequest(_)       => "ticket_request",
             Self::TicketResponse(_)      => "ticket_response",
+            Self::TicketProgress(_)      => "ticket_progress",
+            Self::TicketCancel(_)        => "ticket_cancel",
+        }
+    }
+
+    /// Return the ticket a ticket-related message belongs to.
+    pub fn tckuuid(&self) -> Option<&str> {
+        match self {
+            Self::TicketTraceResponse(m) => Some(&m.tckuuid),
+            Self::TicketRequest(m)       => Some(&m.tckuuid),
+            Self::TicketResponse(m)      => Some(&m.tckuuid),
+            Self::TicketProgress(m)      => Some(&m.tckuuid),
+            Self::TicketCancel(m)        => Some(&m.tckuuid),
+            _ => None,
         }
     }
 }
@@ -395,15 +480,18 @@ mod tests {
 
     const ACK_PING_JSON: &str = concat!(
         r#"{"type":"acknowledgement","dest":null,"src":"a1","isrc":null,"timestamp":1000.0,"#,
-        r#""objuuid":null,"coluuid":null,"ack_type":"ping","forwarded":null,"error":null}"#
+        r#""objuuid":null,"coluuid":null,"ack_type":"ping","forwarded":null,"error":null,"name":"web-1","#,
+        r#""encodings":["none","zstd"]}"#
     );
     const ACK_ERROR_JSON: &str = concat!(
         r#"{"type":"acknowledgement","dest":null,"src":"a1","isrc":null,"timestamp":1000.0,"#,
-        r#""objuuid":null,"coluuid":null,"ack_type":"ticket_request","forwarded":null,"error":"timeout"}"#
+        r#""objuuid":null,"coluuid":null,"ack_type":"ticket_request","forwarded":null,"error":"timeout","name":null,"#,
+        r#""encodings":[]}"#
     );
     const ACK_FORWARDED_JSON: &str = concat!(
         r#"{"type":"acknowledgement","dest":null,"src":"a1","isrc":null,"timestamp":1000.0,"#,
-        r#""objuuid":null,"coluuid":null,"ack_type":"ping","forwarded":"a2","error":null}"#
+        r#""objuuid":null,"coluuid":null,"ack_type":"ping","forwarded":"a2","error":null,"name":null,"#,
+        r#""encodings":[]}"#
     );
 
     #[test]
@@ -412,6 +500,8 @@ mod tests {
             ack_type: "ping".to_string(),
             src: "a1".into(),
             timestamp: Some(1000.0),
+            name: Some("web-1".into()),
+            encodings: vec!["none".into(), "zstd".into()],
             dest: None, isrc: None, forwarded: None, error: None, objuuid: None, coluuid: None,
         });
         assert_ser_eq(&msg, ACK_PING_JSON);
@@ -424,7 +514,8 @@ mod tests {
             src: "a1".into(),
             timestamp: Some(1000.0),
             error: Some("timeout".into()),
-            dest: None, isrc: None, forwarded: None, objuuid: None, coluuid: None,
+            encodings: vec![],
+            dest: None, isrc: None, forwarded: None, name: None, objuuid: None, coluuid: None,
         });
         assert_ser_eq(&msg, ACK_ERROR_JSON);
     }
@@ -436,11 +527,19 @@ mod tests {
             src: "a1".into(),
             timestamp: Some(1000.0),
             forwarded: Some("a2".into()),
-            dest: None, isrc: None, error: None, objuuid: None, coluuid: None,
+            encodings: vec![],
+            dest: None, isrc: None, error: None, name: None, objuuid: None, coluuid: None,
         });
         assert_ser_eq(&msg, ACK_FORWARDED_JSON);
     }
 
+    #[test]
+    fn test_deser_acknowledgement_without_encodings() {
+        let json = r#"{"type":"acknowledgement","src":"a1","ack_type":"ping"}"#;
+        let msg: NetworkMessage = serde_json::from_str(json).unwrap();
+        assert!(matches!(msg, NetworkMessage::Acknowledgement(a) if a.encodings.is_empty()));
+    }
+
     #[test]
     fn test_deser_acknowledgement_ping() {
         assert_deser_roundtrip::<NetworkMessage>(ACK_PING_JSON);
@@ -460,11 +559,16 @@ mod tests {
 
     const ADV_EMPTY_JSON: &str = concat!(
         r#"{"type":"advertisement","dest":null,"src":"a1","isrc":null,"timestamp":1000.0,"#,
-        r#""objuuid":null,"coluuid":null,"routes":[],"agtuuid":"a1"}"#
+        r#""objuuid":null,"coluuid":null,"routes":[],"name":null,"labels":{},"agtuuid":"a1"}"#
     );
     const ADV_ROUTES_JSON: &str = concat!(
         r#"{"type":"advertisement","dest":null,"src":"a1","isrc":null,"timestamp":1000.0,"#,
         r#""objuuid":null,"coluuid":null,"#,
+        r#""routes":[{"agtuuid":"a2","gtwuuid":"a1","weight":1,"name":"db-1","labels":{"role":"db"},"objuuid":null,"coluuid":null}],"#,
+        r#""name":"web-1","labels":{"dc":"east"},"agtuuid":"a1"}"#
+    );
+    const ADV_LEGACY_JSON: &str = concat!(
+        r#"{"type":"advertisement","src":"a1","timestamp":1000.0,"#,
         r#""routes":[{"agtuuid":"a2","gtwuuid":"a1","weight":1,"objuuid":null,"coluuid":null}],"#,
         r#""agtuuid":"a1"}"#
     );
@@ -476,6 +580,8 @@ mod tests {
             src: "a1".into(),
             timestamp: Some(1000.0),
             routes: vec![],
+            name:   None,
+            labels: Labels::new(),
             dest: None, isrc: None, objuuid: None, coluuid: None,
         });
         assert_ser_eq(&msg, ADV_EMPTY_JSON);
@@ -488,7 +594,17 @@ mod tests {
             agtuuid: "a1".into(),
             src: "a1".into(),
             timestamp: Some(1000.0),
-            routes: vec![Route { agtuuid: "a2".into(), gtwuuid: "a1".into(), weight: 1, objuuid: None, coluuid: None }],
+            routes: vec![Route {
+                agtuuid: "a2".into(),
+                gtwuuid: "a1".into(),
+                weight:  1,
+                name:    Some("db-1".into()),
+                labels:  Labels::from([("role".into(), "db".into())]),
+                objuuid: None,
+                coluuid: None,
+            }],
+            name:   Some("web-1".into()),
+            labels: Labels::from([("dc".into(), "east".into())]),
             dest: None, isrc: None, objuuid: None, coluuid: None,
         });
         assert_ser_eq(&msg, ADV_ROUTES_JSON);
@@ -504,6 +620,14 @@ mod tests {
         assert_deser_roundtrip::<NetworkMessage>(ADV_ROUTES_JSON);
     }
 
+    #[test]
+    fn test_deser_advertisement_from_older_agent() {
+        let msg: NetworkMessage = serde_json::from_str(ADV_LEGACY_JSON).unwrap();
+        let NetworkMessage::Advertisement(adv) = msg else { panic!("expected advertisement") };
+        assert!(adv.labels.is_empty() && adv.name.is_none());
+        assert!(adv.routes[0].labels.is_empty() && adv.routes[0].name.is_none());
+    }
+
     // ── NetworkMessagesResponse ───────────────────────────────────────────────
 
     const MSGS_RESP_EMPTY_JSON: &str = concat!(
@@ -579,6 +703,72 @@ mod tests {
         assert_deser_roundtrip::<NetworkMessage>(TTR_JSON);
     }
 
+    // ── TicketProgress ────────────────────────────────────────────────────────
+
+    const TICKET_PROGRESS_JSON: &str = concat!(
+        r#"{"type":"ticket_progress","dest":"a2","src":"a1","isrc":null,"timestamp":1000.0,"#,
+        r#""objuuid":null,"coluuid":null,"tckuuid":"t1","seq":3,"stdout":"bin\n","stderr":null,"#,
+        r#""stdout_offset":120,"stderr_offset":null,"queue_position":null}"#
+    );
+
+    #[test]
+    fn test_ser_ticket_progress() {
+        let msg = NetworkMessage::TicketProgress(TicketProgress {
+            tckuuid: "t1".into(),
+            seq: 3,
+            stdout: Some("bin\n".into()),
+            stdout_offset: Some(120),
+            src: "a1".into(),
+            dest: Some("a2".into()),
+            timestamp: Some(1000.0),
+            ..Default::default()
+        });
+        assert_ser_eq(&msg, TICKET_PROGRESS_JSON);
+    }
+
+    #[test]
+    fn test_deser_ticket_progress() {
+        assert_deser_roundtrip::<NetworkMessage>(TICKET_PROGRESS_JSON);
+    }
+
+    #[test]
+    fn test_ticket_progress_entry() {
+        let parsed: NetworkMessage = serde_json::from_str(TICKET_PROGRESS_JSON).unwrap();
+        if let NetworkMessage::TicketProgress(msg) = parsed {
+            let progress = msg.progress();
+            assert_eq!(progress.seq, 3);
+            assert_eq!(progress.stdout.as_deref(), Some("bin\n"));
+            assert_eq!(progress.stdout_offset, Some(120));
+            assert_eq!(progress.progress_time, 1000.0);
+        } else {
+            panic!("wrong variant");
+        }
+    }
+
+    // ── TicketCancel ──────────────────────────────────────────────────────────
+
+    const TICKET_CANCEL_JSON: &str = concat!(
+        r#"{"type":"ticket_cancel","dest":"a2","src":"a1","isrc":null,"timestamp":1000.0,"#,
+        r#""objuuid":null,"coluuid":null,"tckuuid":"t1"}"#
+    );
+
+    #[test]
+    fn test_ser_ticket_cancel() {
+        let msg = NetworkMessage::TicketCancel(TicketCancel {
+            tckuuid: "t1".into(),
+            src: "a1".into(),
+            dest: Some("a2".into()),
+            timestamp: Some(1000.0),
+            ..Default::default()
+        });
+        assert_ser_eq(&msg, TICKET_CANCEL_JSON);
+    }
+
+    #[test]
+    fn test_deser_ticket_cancel() {
+        assert_deser_roundtrip::<NetworkMessage>(TICKET_CANCEL_JSON);
+    }
+
     // ── NetworkTicket ─────────────────────────────────────────────────────────
 
     const NT_REQUEST_JSON: &str = concat!(
@@ -586,7 +776,7 @@ mod tests {
         r#""objuuid":null,"coluuid":null,"tckuuid":"t1","error":null,"create_time":null,"#,
         r#""service_time":null,"tracing":false,"#,
         r#""form":{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":"ls /","stdout":null,"stderr":null,"#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":"ls /","stdout":null,"stderr":null,"#,
         r#""status":null,"start_time":null,"elapsed_time":null}}"#
     );
     const NT_RESPONSE_JSON: &str = concat!(
@@ -594,7 +784,7 @@ mod tests {
         r#""objuuid":null,"coluuid":null,"tckuuid":"t1","error":null,"create_time":null,"#,
         r#""service_time":0.5,"tracing":false,"#,
         r#""form":{"type":"sync_process","error":null,"objuuid":null,"coluuid":null,"#,
-        r#""timeout":15,"command":"ls /","stdout":"bin\n","stderr":null,"#,
+        r#""timeout":15,"stream":false,"cwd":null,"env":{},"clear_env":false,"b64stdin":null,"run_as_user":null,"signal":null,"timed_out":false,"rlimit_cpu_secs":null,"rlimit_as_bytes":null,"rlimit_nofile":null,"max_output_bytes":null,"output_truncated":false,"command":"ls /","stdout":"bin\n","stderr":null,"#,
         r#""status":0,"start_time":1000.0,"elapsed_time":0.1}}"#
     );
 
@@ -602,9 +792,7 @@ mod tests {
         ControlForm::SyncProcess(SyncProcess {
             command: CommandArg::Single("ls /".into()),
             timeout: 15,
-            stdout: None, stderr: None, status: None,
-            start_time: None, elapsed_time: None,
-            error: None, objuuid: None, coluuid: None,
+            ..Default::default()
         })
     }
 
@@ -638,7 +826,7 @@ mod tests {
                 status: Some(0),
                 start_time: Some(1000.0),
                 elapsed_time: Some(0.1),
-                error: None, objuuid: None, coluuid: None,
+                ..Default::default()
             }),
             tracing: false,
             dest: None, isrc: None,
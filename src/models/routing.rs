@@ -1,5 +1,10 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use serde::{Deserialize, Serialize};
 
+/// Key/value labels an agent carries, such as `role=db`.
+pub type Labels = BTreeMap<String, String>;
+
 /// A route to another agent through a gateway.
 /// Maps to Python's `Route(BaseModel)` in `models/routing.py`.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -7,6 +12,12 @@ pub struct Route {
     pub agtuuid: String,
     pub gtwuuid: String,
     pub weight:  i64,
+    /// Display name of the agent at the end of the route.
+    #[serde(default)]
+    pub name:    Option<String>,
+    /// Labels of the agent at the end of the route.
+    #[serde(default)]
+    pub labels:  Labels,
     pub objuuid: Option<String>,
     pub coluuid: Option<String>,
 }
@@ -21,6 +32,213 @@ pub struct Peer {
     pub destroy_time: Option<f64>,
     pub refresh_time: Option<f64>,
     pub url:          Option<String>,
+    /// Display name the peer last gave.
+    #[serde(default)]
+    pub name:         Option<String>,
+    /// Labels the peer last advertised.
+    #[serde(default)]
+    pub labels:       Labels,
+    /// Body encodings the peer said it accepts when it answered a `Ping`.
+    #[serde(default)]
+    pub encodings:    Vec<String>,
     pub objuuid:      Option<String>,
     pub coluuid:      Option<String>,
 }
+
+// ── Labels and selectors ──────────────────────────────────────────────────────
+
+/// Parse comma-separated `key=value` labels, such as `role=db,dc=east`.
+///
+/// Keys must not be empty; neither keys nor values may contain whitespace,
+/// `,` or `=`.  An empty string is no labels.
+pub fn parse_labels(s: &str) -> Result<Labels, String> {
+    let valid = |part: &str| !part.chars().any(|c| c.is_whitespace() || c == ',' || c == '=');
+    let mut labels = Labels::new();
+    for term in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        match term.split_once('=') {
+            Some((key, value)) if !key.is_empty() && valid(key) && valid(value) => {
+                labels.insert(key.to_string(), value.to_string());
+            }
+            _ => return Err(format!("invalid label '{term}'; expected key=value")),
+        }
+    }
+    Ok(labels)
+}
+
+/// Format labels the way `parse_labels` reads them, sorted by key.
+pub fn format_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Picks agents by their labels: `*` picks every agent, and `role=db,dc=east`
+/// picks those carrying all of the given labels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    All,
+    Labels(Labels),
+}
+
+impl Selector {
+    /// Whether a destination argument is a selector rather than an agent UUID.
+    pub fn is_selector(s: &str) -> bool {
+        s.trim() == "*" || s.contains('=')
+    }
+
+    /// Whether an agent carrying `labels` is picked.
+    pub fn matches(&self, labels: &Labels) -> bool {
+        match self {
+            Selector::All => true,
+            Selector::Labels(wanted) => wanted.iter().all(|(key, value)| labels.get(key) == Some(value)),
+        }
+    }
+}
+
+impl std::str::FromStr for Selector {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(Selector::All);
+        }
+        let labels = parse_labels(s)?;
+        if labels.is_empty() {
+            return Err(format!("empty selector '{s}'; expected '*' or key=value[,key=value...]"));
+        }
+        Ok(Selector::Labels(labels))
+    }
+}
+
+// ── Display names ─────────────────────────────────────────────────────────────
+
+/// Check an agent display name, returning it trimmed.
+///
+/// Names must not be empty or contain whitespace, `,`, `=` or `*`, so a
+/// name can never be mistaken for a selector.
+pub fn validate_name(s: &str) -> Result<String, String> {
+    let name = s.trim();
+    if name.is_empty() {
+        return Err("empty agent name".to_string());
+    }
+    if name.chars().any(|c| c.is_whitespace() || matches!(c, ',' | '=' | '*')) {
+        return Err(format!("invalid agent name '{name}'; whitespace, ',', '=' and '*' are not allowed"));
+    }
+    Ok(name.to_string())
+}
+
+/// Build the table resolving destination arguments to agents from each
+/// known agent's UUID and display name.
+///
+/// A UUID addresses only its own agent, even when another agent uses it as
+/// a name; a name shared by several agents addresses all of them.
+pub fn name_table(
+    agents: impl IntoIterator<Item = (String, Option<String>)>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let agents: Vec<_> = agents.into_iter().collect();
+    let mut table: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (agtuuid, name) in &agents {
+        if let Some(name) = name {
+            table.entry(name.clone()).or_default().insert(agtuuid.clone());
+        }
+    }
+    for (agtuuid, _) in agents {
+        table.insert(agtuuid.clone(), BTreeSet::from([agtuuid]));
+    }
+    table
+}
+
+/// Resolve an agent UUID or display name through a [`name_table`].
+///
+/// Anything the table does not know is returned as-is and treated as a UUID;
+/// a name shared by several agents is an error listing them.
+pub fn resolve_name(table: &BTreeMap<String, BTreeSet<String>>, dst: &str) -> Result<String, String> {
+    match table.get(dst) {
+        None => Ok(dst.to_string()),
+        Some(agents) if agents.len() == 1 => Ok(agents.iter().next().cloned().unwrap_or_default()),
+        Some(agents) => Err(format!(
+            "ambiguous agent name '{dst}' matches {} agents: {}",
+            agents.len(),
+            agents.iter().cloned().collect::<Vec<_>>().join(", "),
+        )),
+    }
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> Labels {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_labels() {
+        assert_eq!(parse_labels(""), Ok(Labels::new()));
+        assert_eq!(parse_labels("role=db, dc=east"), Ok(labels(&[("dc", "east"), ("role", "db")])));
+        assert_eq!(parse_labels("tier="), Ok(labels(&[("tier", "")])));
+        assert!(parse_labels("role").is_err());
+        assert!(parse_labels("=db").is_err());
+        assert!(parse_labels("role=d b").is_err());
+        assert!(parse_labels("role=a=b").is_err());
+    }
+
+    #[test]
+    fn test_format_labels() {
+        let l = labels(&[("role", "db"), ("dc", "east")]);
+        assert_eq!(format_labels(&l), "dc=east,role=db");
+        assert_eq!(parse_labels(&format_labels(&l)), Ok(l));
+        assert_eq!(format_labels(&Labels::new()), "");
+    }
+
+    #[test]
+    fn test_selector() {
+        assert!(Selector::is_selector("*"));
+        assert!(Selector::is_selector("role=db"));
+        assert!(!Selector::is_selector("agent-1"));
+
+        let db_east = labels(&[("role", "db"), ("dc", "east"), ("os", "linux")]);
+        let web_east = labels(&[("role", "web"), ("dc", "east")]);
+
+        let all: Selector = "*".parse().unwrap();
+        assert_eq!(all, Selector::All);
+        assert!(all.matches(&Labels::new()));
+
+        let selector: Selector = "role=db,dc=east".parse().unwrap();
+        assert!(selector.matches(&db_east));
+        assert!(!selector.matches(&web_east));
+        assert!(!selector.matches(&Labels::new()));
+
+        assert!(" , ".parse::<Selector>().is_err());
+        assert!("role".parse::<Selector>().is_err());
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(validate_name(" db-east-1 "), Ok("db-east-1".to_string()));
+        assert!(validate_name("").is_err());
+        assert!(validate_name("db east").is_err());
+        assert!(validate_name("role=db").is_err());
+        assert!(validate_name("*").is_err());
+    }
+
+    #[test]
+    fn test_resolve_name() {
+        let table = name_table([
+            ("a1".to_string(), Some("web".to_string())),
+            ("a2".to_string(), Some("db".to_string())),
+            ("a3".to_string(), Some("db".to_string())),
+            ("a4".to_string(), Some("a1".to_string())),
+            ("a5".to_string(), None),
+        ]);
+        assert_eq!(resolve_name(&table, "web"), Ok("a1".to_string()));
+        assert_eq!(resolve_name(&table, "a5"), Ok("a5".to_string()));
+        assert_eq!(resolve_name(&table, "a1"), Ok("a1".to_string()));
+        assert_eq!(resolve_name(&table, "unknown"), Ok("unknown".to_string()));
+        let err = resolve_name(&table, "db").unwrap_err();
+        assert!(err.contains("a2, a3"), "{err}");
+    }
+}
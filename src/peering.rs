@@ -7,14 +7,18 @@
 //! - Route creation and aging to maintain optimal paths through the network
 //! - Route advertisement processing to discover new network paths
 //! - Network topology cleanup and pruning of expired entries
+//! - Resolving multicast ticket selectors to the known agents they pick
+//! - Resolving agent display names to the agents they address
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use crate::collections::{open_peers, open_routes};
+use crate::models::control::Encoding;
 use crate::models::network::Advertisement;
-use crate::models::routing::{Peer, Route};
+use crate::models::routing::{name_table, Labels, Peer, Route, Selector};
 use crate::config::config;
 
 fn unix_now() -> f64 {
@@ -104,6 +108,39 @@ pub fn create_peer(
     Ok(())
 }
 
+/// Record the display name a peer gave, if it is a known peer.
+pub fn name_peer(agtuuid: &str, name: Option<String>) -> Result<()> {
+    for mut peer in open_peers()?.find(&[("agtuuid", agtuuid)])? {
+        if peer.object.name != name {
+            peer.object.name = name.clone();
+            peer.commit()?;
+        }
+    }
+    Ok(())
+}
+
+/// Record the body encodings a peer said it accepts.
+pub fn set_peer_encodings(agtuuid: &str, encodings: Vec<String>) -> Result<()> {
+    for mut peer in open_peers()?.find(&[("agtuuid", agtuuid)])? {
+        if peer.object.encodings != encodings {
+            peer.object.encodings = encodings.clone();
+            peer.commit()?;
+        }
+    }
+    Ok(())
+}
+
+/// The encoding to compress request bodies to `peer` with: the configured
+/// `body_encoding` if the peer has said it accepts it, otherwise `none`.
+pub fn peer_encoding(peer: &Peer) -> Encoding {
+    let wanted = config().body_encoding;
+    if peer.encodings.iter().any(|e| e == wanted.as_str()) {
+        wanted
+    } else {
+        Encoding::None
+    }
+}
+
 // ── Route management ──────────────────────────────────────────────────────────
 
 /// Delete a specific route from the in-memory route collection.
@@ -142,8 +179,18 @@ pub fn age_routes(v: i64) -> Result<()> {
 /// - If one entry exists with a higher weight, its weight is lowered.
 /// - If no entry exists one is created.
 ///
+/// The route's display name and labels are replaced with `name` and `labels`
+/// in every case.
+///
 /// Mirrors `create_route(agtuuid, gtwuuid, weight)`.
-pub fn create_route(agtuuid: &str, gtwuuid: &str, weight: i64) -> Result<()> {
+pub fn create_route(
+    agtuuid: &str,
+    gtwuuid: &str,
+    weight:  i64,
+    name:    Option<&str>,
+    labels:  &Labels,
+) -> Result<()> {
+    let name = name.map(str::to_string);
     let routes = open_routes()?;
     let matches = routes.find(&[("agtuuid", agtuuid), ("gtwuuid", gtwuuid)])?;
 
@@ -153,13 +200,17 @@ pub fn create_route(agtuuid: &str, gtwuuid: &str, weight: i64) -> Result<()> {
                 agtuuid: agtuuid.to_string(),
                 gtwuuid: gtwuuid.to_string(),
                 weight,
+                name,
+                labels:  labels.clone(),
                 ..Default::default()
             })?;
         }
         1 => {
             let mut route = matches.into_iter().next().unwrap();
-            if route.object.weight > weight {
-                route.object.weight = weight;
+            if route.object.weight > weight || route.object.name != name || route.object.labels != *labels {
+                route.object.weight = route.object.weight.min(weight);
+                route.object.name   = name;
+                route.object.labels = labels.clone();
                 route.commit()?;
             }
         }
@@ -172,6 +223,8 @@ pub fn create_route(agtuuid: &str, gtwuuid: &str, weight: i64) -> Result<()> {
                 agtuuid: agtuuid.to_string(),
                 gtwuuid: gtwuuid.to_string(),
                 weight,
+                name,
+                labels:  labels.clone(),
                 ..Default::default()
             })?;
         }
@@ -185,21 +238,35 @@ pub fn create_route(agtuuid: &str, gtwuuid: &str, weight: i64) -> Result<()> {
 ///
 /// Ignores routes to self and already-known peers.  Increments advertised
 /// weights by 1 to account for the additional hop through this agent.
-/// Runs `prune()` afterwards to clean up stale data.
+/// Records the advertiser's own display name and labels on its peer entry
+/// and each route's on the route.  Runs `prune()` afterwards to clean up stale data.
 ///
 /// Mirrors `process_route_advertisement(advertisement)`.
 pub fn process_route_advertisement(advertisement: &Advertisement) -> Result<()> {
     let peers  = open_peers()?;
 
     let mut ignored: Vec<String> = vec![config().agtuuid.clone()];
-    for obj in peers.find(&[])? {
+    for mut obj in peers.find(&[])? {
         if let Some(ref a) = obj.object.agtuuid {
             ignored.push(a.clone());
         }
+        if obj.object.agtuuid.as_deref() == Some(advertisement.agtuuid.as_str())
+            && (obj.object.name != advertisement.name || obj.object.labels != advertisement.labels)
+        {
+            obj.object.name   = advertisement.name.clone();
+            obj.object.labels = advertisement.labels.clone();
+            obj.commit()?;
+        }
     }
 
     for route in advertisement.routes.iter().filter(|r| !ignored.contains(&r.agtuuid)) {
-        create_route(&route.agtuuid, &advertisement.agtuuid, route.weight + 1)?;
+        create_route(
+            &route.agtuuid,
+            &advertisement.agtuuid,
+            route.weight + 1,
+            route.name.as_deref(),
+            &route.labels,
+        )?;
     }
 
     prune()?;
@@ -209,7 +276,8 @@ pub fn process_route_advertisement(advertisement: &Advertisement) -> Result<()>
 /// Build an advertisement containing all known routes and directly reachable peers.
 ///
 /// Sets each route's `gtwuuid` to this agent's UUID so the recipient can route
-/// back through us.  Runs `prune()` first to ensure stale data is removed.
+/// back through us, and carries this agent's display name and labels along
+/// with those of every agent advertised.  Runs `prune()` first to ensure stale data is removed.
 ///
 /// Mirrors `create_route_advertisement()`.
 pub fn create_route_advertisement() -> Result<Advertisement> {
@@ -220,6 +288,8 @@ pub fn create_route_advertisement() -> Result<Advertisement> {
 
     let mut advertisement = Advertisement {
         agtuuid: config().agtuuid.clone(),
+        name:    config().name.clone(),
+        labels:  config().labels.clone(),
         ..Default::default()
     };
 
@@ -237,6 +307,8 @@ pub fn create_route_advertisement() -> Result<Advertisement> {
                 agtuuid: agtuuid.clone(),
                 gtwuuid: config().agtuuid.clone(),
                 weight:  0,
+                name:    obj.object.name.clone(),
+                labels:  obj.object.labels.clone(),
                 ..Default::default()
             });
         }
@@ -269,6 +341,51 @@ pub fn get_routes() -> Result<Vec<Route>> {
         .collect())
 }
 
+/// Return this agent and every agent it has a peer or a route to, with the
+/// labels each was last advertised with, sorted by UUID.
+pub fn known_agents() -> Result<BTreeMap<String, Labels>> {
+    let mut agents: BTreeMap<String, Labels> = BTreeMap::new();
+    for route in get_routes()? {
+        agents.insert(route.agtuuid, route.labels);
+    }
+    for peer in get_peers()? {
+        if let Some(agtuuid) = peer.agtuuid {
+            agents.insert(agtuuid, peer.labels);
+        }
+    }
+    agents.insert(config().agtuuid.clone(), config().labels.clone());
+    Ok(agents)
+}
+
+/// Resolve a multicast ticket's selector to the agents it picks.
+///
+/// `*` picks every known agent; `role=db,dc=east` picks those carrying all of
+/// the given labels.
+pub fn select_agents(selector: &str) -> Result<Vec<String>> {
+    let selector: Selector = selector.parse().map_err(|e: String| anyhow!(e))?;
+    Ok(known_agents()?
+        .into_iter()
+        .filter(|(_, labels)| selector.matches(labels))
+        .map(|(agtuuid, _)| agtuuid)
+        .collect())
+}
+
+/// Build the table resolving agent UUIDs and display names to the agents
+/// they address, from this agent, its peers and its routes.
+pub fn agent_names() -> Result<BTreeMap<String, BTreeSet<String>>> {
+    let mut agents: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for route in get_routes()? {
+        agents.insert(route.agtuuid, route.name);
+    }
+    for peer in get_peers()? {
+        if let Some(agtuuid) = peer.agtuuid {
+            agents.insert(agtuuid, peer.name);
+        }
+    }
+    agents.insert(config().agtuuid.clone(), config().name.clone());
+    Ok(name_table(agents))
+}
+
 // ── Pruning ───────────────────────────────────────────────────────────────────
 
 /// Remove expired peers and routes with invalid gateways.
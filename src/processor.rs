@@ -12,28 +12,62 @@
 //! - The AES nonce and MAC tag are transmitted as hex strings in the
 //!   Nonce and Tag HTTP headers respectively.
 
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
 use serde_json::{json, Value};
 
 use crate::collections::{open_peers, open_tickets};
-use crate::config::config;
-use crate::executor::agent::{decrypt, encrypt, AgentClient};
-use crate::executor::file::{load_file_to_form, write_file_from_form};
-use crate::executor::process::sync_process;
+use crate::config::{config, set_config};
+use crate::executor::agent::{body_encoding, decrypt, encrypt, AgentClient};
+use crate::executor::compression::{compress, decompress};
+use crate::executor::archive::{discard_archive, pack_archive, unpack_archive};
+use crate::executor::file::{
+    delete_path, hash_file, list_directory, load_file_to_form, make_directory, move_path,
+    set_permissions, stat_file, write_file_from_form,
+};
+use crate::executor::tail::{read_appended, tail_file};
+use crate::executor::transfer::{commit_transfer, read_chunk, transfer_status, write_chunk};
+use crate::executor::process::{
+    apply_limits, kill_process, list_processes, process_slots, process_status, start_process,
+    sync_process, sync_process_streaming, OutputChunk,
+};
+use crate::logger::{get_logs, set_log_level};
 use crate::messaging::{forward_network_message, pop_network_messages, pull_filtered_network_messages};
+use crate::metrics;
 use crate::models::config::Config;
 use crate::models::control::{
-    CheckTicket, CommandArg, ControlFormTicket, ControlForm, SyncProcess as SyncProcessForm,
+    CheckTicket, CommitTransfer, ControlFormTicket, ControlForm, DeletePath, Encoding, HashFile,
+    KillProcess, ListDirectory, MakeDirectory, MovePath, PackArchive, ReadChunk, SetPermissions,
+    StartProcess, StatFile, SyncProcess as SyncProcessForm, TailFile, TransferStatus, UnpackArchive,
+    WaitTickets, WriteChunk,
 };
+use crate::models::routing::format_labels;
 use crate::models::network::{
     Acknowledgement, NetworkMessage, NetworkMessagesRequest, NetworkMessagesResponse,
-    NetworkTicket,
+    NetworkTicket, TicketCancel, TicketProgress,
 };
 use crate::peering::{
-    age_routes, create_peer, create_route_advertisement, delete_peer, delete_peers, get_peers,
-    get_routes, process_route_advertisement, touch_peer,
+    age_routes, agent_names, create_peer, create_route_advertisement, delete_peer, delete_peers,
+    get_peers, get_routes, name_peer, peer_encoding, process_route_advertisement, select_agents,
+    set_peer_encodings, touch_peer,
 };
-use crate::ticketing::{check_ticket, close_ticket, dedup_trace, read_ticket, service_ticket, service_trace};
+use crate::topology::get_topology;
+use crate::ticketing::{
+    cancel_ticket, check_ticket, close_ticket, dedup_trace, fail_ticket, progress_ticket,
+    read_ticket, service_ticket, service_trace, wait_tickets, CancelWatch,
+};
+
+/// How often buffered output from a streaming process is relayed to the originator.
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
 
 // ── HTTP Handlers ─────────────────────────────────────────────────────────────
 
@@ -41,7 +75,8 @@ use crate::ticketing::{check_ticket, close_ticket, dedup_trace, read_ticket, ser
 ///
 /// Receives raw binary AES-256 EAX ciphertext bodies, decrypts using the
 /// hex-encoded Nonce and Tag headers, processes, and returns an encrypted
-/// response in the same format.
+/// response in the same format.  A body compressed under the `Encoding`
+/// header is decompressed first, and the response is compressed to match.
 ///
 /// Mirrors Python's `/control` endpoint.
 pub async fn control_handler(
@@ -50,13 +85,7 @@ pub async fn control_handler(
     request: HttpRequest,
 ) -> ActixResult<HttpResponse> {
     let key = config_data.key();
-
-    let nonce = extract_header_hex(&request, "Nonce")?;
-    let tag   = extract_header_hex(&request, "Tag")?;
-    let ct    = body.to_vec();
-
-    let plaintext = decrypt(&key, &nonce, &tag, &ct)
-        .map_err(actix_web::error::ErrorBadRequest)?;
+    let (encoding, plaintext) = open_request(&key, &request, &body)?;
 
     let raw: Value = serde_json::from_slice(&plaintext)
         .map_err(actix_web::error::ErrorBadRequest)?;
@@ -65,7 +94,10 @@ pub async fn control_handler(
         Some("create_ticket") | Some("read_ticket") => {
             let ticket: ControlFormTicket = serde_json::from_value(raw)
                 .map_err(actix_web::error::ErrorBadRequest)?;
-            log::debug!("{}", ticket.form_type);
+            log::debug!(
+                tckuuid = ticket.tckuuid.as_str(), dest = ticket.dst.as_str();
+                "{}", ticket.form_type
+            );
             let result = process_ticket_form(ticket).await;
             serde_json::to_vec(&result)
                 .map_err(actix_web::error::ErrorInternalServerError)?
@@ -80,20 +112,15 @@ pub async fn control_handler(
         }
     };
 
-    let (nonce_out, tag_out, ct_out) = encrypt(&key, &raw_response)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-
-    Ok(HttpResponse::Ok()
-        .append_header(("Nonce", hex::encode(nonce_out)))
-        .append_header(("Tag", hex::encode(tag_out)))
-        .body(ct_out))
+    seal_response(&key, encoding, &raw_response)
 }
 
 /// Handler for the `/mpi` endpoint.
 ///
 /// Receives raw binary AES-256 EAX ciphertext bodies, decrypts using the
 /// hex-encoded Nonce and Tag headers, routes, and returns an encrypted
-/// response in the same format.
+/// response in the same format.  A body compressed under the `Encoding`
+/// header is decompressed first, and the response is compressed to match.
 ///
 /// Mirrors Python's `/mpi` endpoint.
 pub async fn mpi_handler(
@@ -102,19 +129,14 @@ pub async fn mpi_handler(
     request: HttpRequest,
 ) -> ActixResult<HttpResponse> {
     let key = config_data.key();
-
-    let nonce = extract_header_hex(&request, "Nonce")?;
-    let tag   = extract_header_hex(&request, "Tag")?;
-    let ct    = body.to_vec();
-
-    let plaintext = decrypt(&key, &nonce, &tag, &ct)
-        .map_err(actix_web::error::ErrorBadRequest)?;
+    let (encoding, plaintext) = open_request(&key, &request, &body)?;
 
     let mut message: NetworkMessage = serde_json::from_slice(&plaintext)
         .map_err(actix_web::error::ErrorBadRequest)?;
 
     if let Some(isrc) = isrc_of(&message) {
-        touch_peer(&isrc).unwrap_or_else(|e| log::error!("touch_peer error: {e}"));
+        metrics::peer_seen(&isrc);
+        touch_peer(&isrc).unwrap_or_else(|e| log::error!(src = isrc.as_str(); "touch_peer error: {e}"));
     }
 
     if dest_of(&message).is_empty() {
@@ -126,13 +148,7 @@ pub async fn mpi_handler(
     let raw_response = serde_json::to_vec(&response)
         .map_err(actix_web::error::ErrorInternalServerError)?;
 
-    let (nonce_out, tag_out, ct_out) = encrypt(&key, &raw_response)
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-
-    Ok(HttpResponse::Ok()
-        .append_header(("Nonce", hex::encode(nonce_out)))
-        .append_header(("Tag", hex::encode(tag_out)))
-        .body(ct_out))
+    seal_response(&key, encoding, &raw_response)
 }
 
 // ── Control form processing ───────────────────────────────────────────────────
@@ -144,6 +160,7 @@ pub async fn process_control_form(form: ControlForm) -> ControlForm {
     log::debug!("{}", form.form_type());
     match form {
         ControlForm::DiscoverPeer(mut f) => {
+            // Sent uncompressed: the peer has not said what it accepts yet.
             let client = AgentClient::with_credentials(
                 f.url.clone(),
                 config().key(),
@@ -163,7 +180,10 @@ pub async fn process_control_form(form: ControlForm) -> ControlForm {
                             Some(f.url.clone()),
                             f.ttl.map(|t| t as u32),
                             f.polling,
-                        ) {
+                        )
+                        .and_then(|_| name_peer(dest, ack.name.clone()))
+                        .and_then(|_| set_peer_encodings(dest, ack.encodings.clone()))
+                        {
                             f.error = Some(e.to_string());
                         }
                     }
@@ -217,31 +237,245 @@ pub async fn process_control_form(form: ControlForm) -> ControlForm {
             ControlForm::GetRoutes(f)
         }
 
-        ControlForm::SyncProcess(f) => {
-            match tokio::task::spawn_blocking(move || sync_process(f)).await {
-                Ok(result) => ControlForm::SyncProcess(result),
+        ControlForm::GetNames(mut f) => {
+            match agent_names() {
+                Ok(names) => f.names = names,
+                Err(e) => f.error = Some(e.to_string()),
+            }
+            ControlForm::GetNames(f)
+        }
+
+        ControlForm::SelectAgents(mut f) => {
+            match select_agents(&f.selector) {
+                Ok(agents) => f.agents = agents,
+                Err(e) => f.error = Some(e.to_string()),
+            }
+            ControlForm::SelectAgents(f)
+        }
+
+        ControlForm::GetTopology(f) => ControlForm::GetTopology(get_topology(f).await),
+
+        ControlForm::SyncProcess(f) => execute_sync_process(f, None).await,
+
+        ControlForm::StartProcess(f) => {
+            match tokio::task::spawn_blocking(move || start_process(f)).await {
+                Ok(result) => ControlForm::StartProcess(result),
+                Err(e) => {
+                    log::error!("start_process task error: {e}");
+                    ControlForm::StartProcess(StartProcess {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::ProcessStatus(f) => ControlForm::ProcessStatus(process_status(f)),
+
+        ControlForm::KillProcess(f) => {
+            match tokio::task::spawn_blocking(move || kill_process(f)).await {
+                Ok(result) => ControlForm::KillProcess(result),
                 Err(e) => {
-                    log::error!("sync_process task error: {e}");
-                    ControlForm::SyncProcess(SyncProcessForm {
-                        command:      CommandArg::default(),
-                        timeout:      15,
-                        stdout:       None,
-                        stderr:       None,
-                        status:       None,
-                        start_time:   None,
-                        elapsed_time: None,
-                        error:        Some(e.to_string()),
-                        objuuid:      None,
-                        coluuid:      None,
+                    log::error!("kill_process task error: {e}");
+                    ControlForm::KillProcess(KillProcess {
+                        error: Some(e.to_string()),
+                        ..Default::default()
                     })
                 }
             }
         }
 
+        ControlForm::ListProcesses(f) => ControlForm::ListProcesses(list_processes(f)),
+
         ControlForm::LoadFile(f) => ControlForm::LoadFile(load_file_to_form(f)),
 
         ControlForm::WriteFile(f) => ControlForm::WriteFile(write_file_from_form(f)),
 
+        ControlForm::HashFile(f) => {
+            match tokio::task::spawn_blocking(move || hash_file(f)).await {
+                Ok(result) => ControlForm::HashFile(result),
+                Err(e) => {
+                    log::error!("hash_file task error: {e}");
+                    ControlForm::HashFile(HashFile {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::ListDirectory(f) => {
+            match tokio::task::spawn_blocking(move || list_directory(f)).await {
+                Ok(result) => ControlForm::ListDirectory(result),
+                Err(e) => {
+                    log::error!("list_directory task error: {e}");
+                    ControlForm::ListDirectory(ListDirectory {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::StatFile(f) => {
+            match tokio::task::spawn_blocking(move || stat_file(f)).await {
+                Ok(result) => ControlForm::StatFile(result),
+                Err(e) => {
+                    log::error!("stat_file task error: {e}");
+                    ControlForm::StatFile(StatFile {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::TailFile(f) => {
+            match tokio::task::spawn_blocking(move || tail_file(f)).await {
+                Ok(result) => ControlForm::TailFile(result),
+                Err(e) => {
+                    log::error!("tail_file task error: {e}");
+                    ControlForm::TailFile(TailFile {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::PackArchive(f) => {
+            match tokio::task::spawn_blocking(move || pack_archive(f)).await {
+                Ok(result) => ControlForm::PackArchive(result),
+                Err(e) => {
+                    log::error!("pack_archive task error: {e}");
+                    ControlForm::PackArchive(PackArchive {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::UnpackArchive(f) => {
+            match tokio::task::spawn_blocking(move || unpack_archive(f)).await {
+                Ok(result) => ControlForm::UnpackArchive(result),
+                Err(e) => {
+                    log::error!("unpack_archive task error: {e}");
+                    ControlForm::UnpackArchive(UnpackArchive {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::DiscardArchive(f) => ControlForm::DiscardArchive(discard_archive(f)),
+
+        ControlForm::DeletePath(f) => {
+            match tokio::task::spawn_blocking(move || delete_path(f)).await {
+                Ok(result) => ControlForm::DeletePath(result),
+                Err(e) => {
+                    log::error!("delete_path task error: {e}");
+                    ControlForm::DeletePath(DeletePath {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::MovePath(f) => {
+            match tokio::task::spawn_blocking(move || move_path(f)).await {
+                Ok(result) => ControlForm::MovePath(result),
+                Err(e) => {
+                    log::error!("move_path task error: {e}");
+                    ControlForm::MovePath(MovePath {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::MakeDirectory(f) => {
+            match tokio::task::spawn_blocking(move || make_directory(f)).await {
+                Ok(result) => ControlForm::MakeDirectory(result),
+                Err(e) => {
+                    log::error!("make_directory task error: {e}");
+                    ControlForm::MakeDirectory(MakeDirectory {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::SetPermissions(f) => {
+            match tokio::task::spawn_blocking(move || set_permissions(f)).await {
+                Ok(result) => ControlForm::SetPermissions(result),
+                Err(e) => {
+                    log::error!("set_permissions task error: {e}");
+                    ControlForm::SetPermissions(SetPermissions {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::ReadChunk(f) => {
+            match tokio::task::spawn_blocking(move || read_chunk(f)).await {
+                Ok(result) => ControlForm::ReadChunk(result),
+                Err(e) => {
+                    log::error!("read_chunk task error: {e}");
+                    ControlForm::ReadChunk(ReadChunk {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::WriteChunk(f) => {
+            match tokio::task::spawn_blocking(move || write_chunk(f)).await {
+                Ok(result) => ControlForm::WriteChunk(result),
+                Err(e) => {
+                    log::error!("write_chunk task error: {e}");
+                    ControlForm::WriteChunk(WriteChunk {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        ControlForm::TransferStatus(f) => {
+            match tokio::task::spawn_blocking(move || transfer_status(f)).await {
+                Ok(result) => ControlForm::TransferStatus(result),
+                Err(e) => {
+                    log::error!("transfer_status task error: {e}");
+                    ControlForm::TransferStatus(TransferStatus {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        // Assembling a multi-gigabyte file takes a while; keep it off the runtime.
+        ControlForm::CommitTransfer(f) => {
+            match tokio::task::spawn_blocking(move || commit_transfer(f)).await {
+                Ok(result) => ControlForm::CommitTransfer(result),
+                Err(e) => {
+                    log::error!("commit_transfer task error: {e}");
+                    ControlForm::CommitTransfer(CommitTransfer {
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
         ControlForm::Benchmark(mut f) => {
             f.payload = f.inbound_size.map(|size| "0".repeat(size as usize));
             ControlForm::Benchmark(f)
@@ -252,6 +486,11 @@ pub async fn process_control_form(form: ControlForm) -> ControlForm {
             ControlForm::GetConfig(f)
         }
 
+        ControlForm::SetConfig(f) => ControlForm::SetConfig(set_config(f)),
+        ControlForm::SetLogLevel(f) => ControlForm::SetLogLevel(set_log_level(f)),
+        ControlForm::GetLogs(f) => ControlForm::GetLogs(get_logs(f)),
+        ControlForm::GetStats(f) => ControlForm::GetStats(metrics::get_stats(f)),
+
         ControlForm::CheckTicket(f) => {
             match check_ticket(f) {
                 Ok(updated) => ControlForm::CheckTicket(updated),
@@ -259,18 +498,258 @@ pub async fn process_control_form(form: ControlForm) -> ControlForm {
             }
         }
 
+        ControlForm::WaitTickets(f) => {
+            let tckuuids = f.tckuuids.clone();
+            match wait_tickets(f).await {
+                Ok(updated) => ControlForm::WaitTickets(updated),
+                Err(e) => ControlForm::WaitTickets(WaitTickets {
+                    tckuuids,
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }),
+            }
+        }
+
         ControlForm::CloseTicket(f) => {
             close_ticket(&f).unwrap_or_else(|e| log::error!("close_ticket error: {e}"));
             ControlForm::CloseTicket(f)
         }
+
+        ControlForm::CancelTicket(mut f) => {
+            match open_tickets().and_then(|tickets| tickets.find(&[("tckuuid", f.tckuuid.as_str())])) {
+                Ok(found) => match found.first().map(|t| &t.object) {
+                    Some(ticket) if ticket.service_time.is_none() => {
+                        // A multicast ticket is cancelled through its outstanding per-agent tickets.
+                        let targets: Vec<(String, String)> = if ticket.is_multicast() {
+                            open_tickets()
+                                .and_then(|tickets| tickets.find(&[("parent", f.tckuuid.as_str())]))
+                                .unwrap_or_default()
+                                .into_iter()
+                                .filter(|t| t.object.service_time.is_none())
+                                .map(|t| (t.object.tckuuid, t.object.dst))
+                                .collect()
+                        } else {
+                            vec![(f.tckuuid.clone(), ticket.dst.clone())]
+                        };
+                        for (tckuuid, dst) in targets {
+                            route_network_message(NetworkMessage::TicketCancel(TicketCancel {
+                                tckuuid,
+                                src:  config().agtuuid.clone(),
+                                dest: Some(dst),
+                                ..Default::default()
+                            }))
+                            .await;
+                        }
+                    }
+                    Some(_) => f.error = Some("ticket already serviced".to_string()),
+                    None    => f.error = Some("unknown ticket".to_string()),
+                },
+                Err(e) => f.error = Some(e.to_string()),
+            }
+            ControlForm::CancelTicket(f)
+        }
+    }
+}
+
+/// Run a `SyncProcess` within the agent's resource limits and process slots.
+///
+/// When the form arrived in a `ticket`, the originator is told its queue
+/// position while the form waits for a slot, and receives the output as it is
+/// produced if the form asks for streaming.
+async fn execute_sync_process(
+    mut form: SyncProcessForm,
+    ticket: Option<&NetworkTicket>,
+) -> ControlForm {
+    apply_limits(&mut form, &config());
+
+    let _slot = match process_slots() {
+        Some(slots) => Some(
+            slots
+                .acquire(|position| async move {
+                    if let Some(ticket) = ticket {
+                        send_ticket_progress(TicketProgress {
+                            tckuuid:        ticket.tckuuid.clone(),
+                            queue_position: Some(position),
+                            dest:           Some(ticket.src.clone()),
+                            ..Default::default()
+                        })
+                        .await;
+                    }
+                })
+                .await,
+        ),
+        None => None,
+    };
+
+    if let Some(ticket) = ticket.filter(|_| form.stream) {
+        return stream_sync_process(ticket, form).await;
+    }
+
+    match tokio::task::spawn_blocking(move || sync_process(form)).await {
+        Ok(result) => ControlForm::SyncProcess(result),
+        Err(e) => {
+            log::error!("sync_process task error: {e}");
+            ControlForm::SyncProcess(SyncProcessForm {
+                error: Some(e.to_string()),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Route a `TicketProgress` from this agent to the ticket's originator.
+async fn send_ticket_progress(progress: TicketProgress) {
+    route_network_message(NetworkMessage::TicketProgress(TicketProgress {
+        src: config().agtuuid.clone(),
+        ..progress
+    }))
+    .await;
+}
+
+/// Run a streaming `SyncProcess` ticket and relay its output as it is produced.
+///
+/// Output read from the process is batched every [`PROGRESS_INTERVAL`] and
+/// routed back to the ticket's originator as `TicketProgress` messages, each
+/// with the offsets of its text in the whole output.  The completed form is
+/// returned for the usual `TicketResponse`.
+async fn stream_sync_process(ticket: &NetworkTicket, form: SyncProcessForm) -> ControlForm {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let task = tokio::task::spawn_blocking(move || sync_process_streaming(form, tx));
+
+    let mut seq = 0;
+    let mut stdout_offset = 0;
+    let mut stderr_offset = 0;
+    let mut open = true;
+    while open {
+        tokio::time::sleep(PROGRESS_INTERVAL).await;
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        loop {
+            match rx.try_recv() {
+                Ok(OutputChunk::Stdout(s)) => stdout.push_str(&s),
+                Ok(OutputChunk::Stderr(s)) => stderr.push_str(&s),
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+        if stdout.is_empty() && stderr.is_empty() {
+            continue;
+        }
+
+        let progress = TicketProgress {
+            tckuuid:       ticket.tckuuid.clone(),
+            seq,
+            stdout_offset: (!stdout.is_empty()).then_some(stdout_offset),
+            stderr_offset: (!stderr.is_empty()).then_some(stderr_offset),
+            dest:          Some(ticket.src.clone()),
+            ..Default::default()
+        };
+        stdout_offset += stdout.len() as u64;
+        stderr_offset += stderr.len() as u64;
+        send_ticket_progress(TicketProgress {
+            stdout: (!stdout.is_empty()).then_some(stdout),
+            stderr: (!stderr.is_empty()).then_some(stderr),
+            ..progress
+        })
+        .await;
+        seq += 1;
+    }
+
+    match task.await {
+        Ok(result) => ControlForm::SyncProcess(result),
+        Err(e) => {
+            log::error!("sync_process task error: {e}");
+            ControlForm::SyncProcess(SyncProcessForm {
+                error: Some(e.to_string()),
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Follow a `TailFile` ticket, relaying text appended to the file.
+///
+/// The current tail goes out as the first `TicketProgress`, then the file is
+/// polled every [`PROGRESS_INTERVAL`] until the form's `timeout` passes, the
+/// originator cancels the ticket or the file can no longer be read.  The
+/// returned form carries the offset to resume from in a later ticket.
+async fn follow_tail_file(ticket: &NetworkTicket, form: TailFile) -> ControlForm {
+    let watch    = CancelWatch::new(&ticket.tckuuid);
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(form.timeout);
+
+    let mut form = match tokio::task::spawn_blocking(move || tail_file(form)).await {
+        Ok(result) => result,
+        Err(e) => {
+            log::error!("tail_file task error: {e}");
+            return ControlForm::TailFile(TailFile {
+                error: Some(e.to_string()),
+                ..Default::default()
+            });
+        }
+    };
+    let (Some(mut offset), None) = (form.next_offset, &form.error) else {
+        return ControlForm::TailFile(form);
+    };
+
+    let mut seq = 0;
+    let mut progress = |stdout: String, stderr: String| {
+        let message = TicketProgress {
+            tckuuid: ticket.tckuuid.clone(),
+            seq,
+            stdout:  (!stdout.is_empty()).then_some(stdout),
+            stderr:  (!stderr.is_empty()).then_some(stderr),
+            dest:    Some(ticket.src.clone()),
+            ..Default::default()
+        };
+        seq += 1;
+        send_ticket_progress(message)
+    };
+
+    if let Some(content) = form.content.take().filter(|c| !c.is_empty()) {
+        progress(content, String::new()).await;
+    }
+
+    while tokio::time::Instant::now() < deadline && !watch.is_cancelled() {
+        tokio::time::sleep(PROGRESS_INTERVAL).await;
+
+        let path = std::path::PathBuf::from(&form.path);
+        let appended = match tokio::task::spawn_blocking(move || read_appended(&path, offset)).await {
+            Ok(Ok(appended)) => appended,
+            Ok(Err(e)) => {
+                form.error = Some(e.to_string());
+                break;
+            }
+            Err(e) => {
+                log::error!("read_appended task error: {e}");
+                form.error = Some(e.to_string());
+                break;
+            }
+        };
+        offset = appended.next_offset;
+
+        let stderr = if appended.truncated {
+            format!("tail: {}: file truncated\n", form.path)
+        } else {
+            String::new()
+        };
+        if !appended.text.is_empty() || !stderr.is_empty() {
+            progress(appended.text, stderr).await;
+        }
     }
+
+    form.next_offset = Some(offset);
+    ControlForm::TailFile(form)
 }
 
 /// Handle ticket-type control forms received at the `/control` endpoint.
 ///
 /// Dispatches `create_ticket` and `read_ticket` operations.
 async fn process_ticket_form(mut ticket: ControlFormTicket) -> ControlFormTicket {
-    log::debug!("{}", ticket.form_type);
+    log::debug!(tckuuid = ticket.tckuuid.as_str(); "{}", ticket.form_type);
     match ticket.form_type.as_str() {
         "create_ticket" => create_form_ticket(ticket).await,
         "read_ticket" => match read_ticket(&ticket) {
@@ -285,55 +764,152 @@ async fn process_ticket_form(mut ticket: ControlFormTicket) -> ControlFormTicket
     }
 }
 
-/// Create a network ticket from a control form ticket and route it to the destination.
+/// Create a network ticket from a control form ticket and route it to the
+/// destination, or to each destination of a multicast ticket.
 ///
 /// Mirrors Python's `create_form_ticket(control_form_ticket)`.
-pub async fn create_form_ticket(mut control_form_ticket: ControlFormTicket) -> ControlFormTicket {
-    // Fill benchmark outbound payload before routing
-    if let ControlForm::Benchmark(ref mut f) = control_form_ticket.form {
-        f.payload = f.outbound_size.map(|size| "0".repeat(size as usize));
+pub async fn create_form_ticket(control_form_ticket: ControlFormTicket) -> ControlFormTicket {
+    if control_form_ticket.is_multicast() {
+        create_multicast_ticket(control_form_ticket).await
+    } else {
+        send_form_ticket(control_form_ticket).await
     }
+}
 
-    let network_ticket = NetworkTicket {
-        tckuuid:      control_form_ticket.tckuuid.clone(),
-        form:         control_form_ticket.form.clone(),
-        tracing:      control_form_ticket.tracing,
-        src:          config().agtuuid.clone(),
-        dest:         Some(control_form_ticket.dst.clone()),
-        isrc:         None,
-        timestamp:    None,
-        create_time:  Some(control_form_ticket.create_time),
-        service_time: None,
-        error:        None,
-        objuuid:      None,
-        coluuid:      None,
+/// Store a multicast ticket and send its form to each agent in a per-agent
+/// ticket of its own.
+///
+/// The `selector`, if any, is resolved into `dsts` first.  A ticket whose
+/// selector cannot be resolved, or that picks no agents, is stored already
+/// serviced so that reading it reports why.
+async fn create_multicast_ticket(mut multicast: ControlFormTicket) -> ControlFormTicket {
+    if let Some(ref selector) = multicast.selector {
+        match select_agents(selector) {
+            Ok(agents) => multicast.dsts.extend(agents),
+            Err(e) => multicast.error = Some(e.to_string()),
+        }
+    }
+    let mut seen = BTreeSet::new();
+    multicast.dsts.retain(|dst| seen.insert(dst.clone()));
+    if multicast.error.is_some() || multicast.dsts.is_empty() {
+        multicast.service_time = Some(unix_now());
+    }
+
+    let stored = match open_tickets().and_then(|tickets| tickets.upsert_object(multicast.clone())) {
+        Ok(obj) => obj.object,
+        Err(e) => {
+            log::error!(
+                tckuuid = multicast.tckuuid.as_str();
+                "create_multicast_ticket: failed to store ticket: {e}"
+            );
+            multicast.error = Some(e.to_string());
+            return multicast;
+        }
     };
+    if stored.service_time.is_some() {
+        return stored;
+    }
+
+    // Each per-agent ticket is stored before its request is routed, and the
+    // routing is left to run in the background: a request to this agent may
+    // be serviced inline, and must not hold up the multicast's creation.
+    for dst in &stored.dsts {
+        let mut child = ControlFormTicket {
+            dst:         dst.clone(),
+            parent:      Some(stored.tckuuid.clone()),
+            tracing:     stored.tracing,
+            create_time: stored.create_time,
+            form:        stored.form.clone(),
+            ..ControlFormTicket::default()
+        };
+        let request = form_ticket_request(&mut child);
+        match open_tickets().and_then(|tickets| tickets.upsert_object(child.clone())) {
+            Ok(_) => {
+                metrics::inc("stembot_tickets_created_total", &[]);
+                tokio::spawn(route_network_message(NetworkMessage::TicketRequest(request)));
+            }
+            Err(e) => {
+                log::error!(
+                    tckuuid = child.tckuuid.as_str(), dest = dst.as_str();
+                    "create_multicast_ticket: failed to store ticket: {e}"
+                );
+                clear_ticket_payload(&mut child);
+                if let Err(e) = fail_ticket(child, format!("failed to store ticket: {e}")) {
+                    log::error!(tckuuid = stored.tckuuid.as_str(); "fail_ticket error: {e}");
+                }
+            }
+        }
+    }
+
+    let mut returned = stored;
+    clear_ticket_payload(&mut returned);
+    returned
+}
+
+/// Drop bulky request payloads from a ticket that is returned to the client.
+fn clear_ticket_payload(ticket: &mut ControlFormTicket) {
+    // Clear file content from the ticket before returning to avoid unnecessary data transfer
+    if let ControlForm::WriteFile(ref mut f) = ticket.form {
+        f.b64zlib = "".to_string();
+    }
+    if let ControlForm::WriteChunk(ref mut f) = ticket.form {
+        f.b64zlib = "".to_string();
+    }
+    // Clear benchmark payload from the ticket before returning
+    if let ControlForm::Benchmark(ref mut f) = ticket.form {
+        f.payload = None;
+    }
+}
+
+/// Route a single-destination ticket and store it until it is serviced.
+async fn send_form_ticket(mut control_form_ticket: ControlFormTicket) -> ControlFormTicket {
+    let network_ticket = form_ticket_request(&mut control_form_ticket);
 
     let stored = open_tickets()
         .and_then(|tickets| tickets.upsert_object(control_form_ticket.clone()))
         .map(|obj| obj.object);
+    metrics::inc("stembot_tickets_created_total", &[]);
 
     route_network_message(NetworkMessage::TicketRequest(network_ticket)).await;
 
     match stored {
         Ok(mut ticket) => {
-            // Clear file content from the ticket before returning to avoid unnecessary data transfer
-            if let ControlForm::WriteFile(ref mut f) = ticket.form {
-                f.b64zlib = "".to_string();
-            }
-            // Clear benchmark payload from the ticket before returning
-            if let ControlForm::Benchmark(ref mut f) = ticket.form {
-                f.payload = None;
-            }
+            clear_ticket_payload(&mut ticket);
             ticket
         },
         Err(e) => {
-            log::error!("create_form_ticket: failed to store ticket: {e}");
+            log::error!(
+                tckuuid = control_form_ticket.tckuuid.as_str();
+                "create_form_ticket: failed to store ticket: {e}"
+            );
             control_form_ticket
         }
     }
 }
 
+/// Build the network request for a single-destination ticket, filling in a
+/// benchmark's outbound payload first.
+fn form_ticket_request(control_form_ticket: &mut ControlFormTicket) -> NetworkTicket {
+    if let ControlForm::Benchmark(ref mut f) = control_form_ticket.form {
+        f.payload = f.outbound_size.map(|size| "0".repeat(size as usize));
+    }
+
+    NetworkTicket {
+        tckuuid:      control_form_ticket.tckuuid.clone(),
+        form:         control_form_ticket.form.clone(),
+        tracing:      control_form_ticket.tracing,
+        src:          config().agtuuid.clone(),
+        dest:         Some(control_form_ticket.dst.clone()),
+        isrc:         None,
+        timestamp:    None,
+        create_time:  Some(control_form_ticket.create_time),
+        service_time: None,
+        error:        None,
+        objuuid:      None,
+        coluuid:      None,
+    }
+}
+
 // ── Network message routing ───────────────────────────────────────────────────
 
 /// Route a network message to its destination or forward it to an intermediate peer.
@@ -365,13 +941,20 @@ pub fn route_network_message(
                 } else {
                     tokio::spawn(async move {
                         if let Err(e) = forward_network_message(trace_msg).await {
-                            log::error!("forward trace error: {e}");
+                            log::error!(
+                                message_type = "ticket_trace_response", tckuuid = ticket.tckuuid.as_str(),
+                                dest = dest.as_str();
+                                "forward trace error: {e}"
+                            );
                         }
                     });
                 }
             }
             Ok(None) => {}
-            Err(e) => log::error!("dedup_trace error: {e}"),
+            Err(e) => log::error!(
+                message_type = ttype, tckuuid = ticket.tckuuid.as_str();
+                "dedup_trace error: {e}"
+            ),
         }
     }
 
@@ -391,8 +974,17 @@ pub fn route_network_message(
 
     let msg = message_in.clone();
     tokio::spawn(async move {
-        if let Err(e) = forward_network_message(msg).await {
-            log::error!("forward_network_message error: {e}");
+        log::debug!(
+            message_type = msg.message_type(), tckuuid = msg.tckuuid(),
+            src = src_of(&msg).as_str(), dest = dest_of(&msg).as_str();
+            "forwarding"
+        );
+        if let Err(e) = forward_network_message(msg.clone()).await {
+            log::error!(
+                message_type = msg.message_type(), tckuuid = msg.tckuuid(),
+                src = src_of(&msg).as_str(), dest = dest_of(&msg).as_str();
+                "forward_network_message error: {e}"
+            );
         }
     });
 
@@ -411,38 +1003,71 @@ pub fn route_network_message(
 fn process_network_message(
     message: NetworkMessage,
 ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Option<NetworkMessage>> + Send>> {
-    log::debug!("{}", message.message_type());
+    log::debug!(
+        message_type = message.message_type(), tckuuid = message.tckuuid(),
+        src = src_of(&message).as_str(), dest = dest_of(&message).as_str();
+        "{}", message.message_type()
+    );
     Box::pin(async move {
     match message {
-        NetworkMessage::Ping(_) => None,
+        NetworkMessage::Ping(m) => Some(NetworkMessage::Acknowledgement(Acknowledgement {
+            ack_type:  "ping".to_string(),
+            src:       m.src,
+            dest:      Some(config().agtuuid.clone()),
+            name:      config().name.clone(),
+            encodings: Encoding::ALL.iter().map(|e| e.as_str().to_string()).collect(),
+            ..Default::default()
+        })),
 
         NetworkMessage::Advertisement(adv) => {
+            metrics::advertisement_received();
             if let Err(e) = process_route_advertisement(&adv) {
                 log::error!("process_route_advertisement error: {e}");
             }
             None
         }
 
-        NetworkMessage::TicketRequest(mut ticket) => {
-            ticket.form = process_control_form(ticket.form).await;
-            let src  = ticket.src.clone();
-            let dest = ticket.dest.clone().unwrap_or_default();
-            ticket.src  = dest;
-            ticket.dest = Some(src);
-            route_network_message(NetworkMessage::TicketResponse(ticket)).await;
+        // Long-running work is serviced in the background so the sender, which
+        // is this agent's own control handler for local tickets, isn't held up
+        // and the originator sees the ticket's progress while it runs.
+        NetworkMessage::TicketRequest(ticket) if services_in_background(&ticket.form) => {
+            tokio::spawn(service_ticket_request(ticket));
+            None
+        }
+
+        NetworkMessage::TicketRequest(ticket) => {
+            service_ticket_request(ticket).await;
             None
         }
 
         NetworkMessage::TicketResponse(ticket) => {
             if let Err(e) = service_ticket(&ticket) {
-                log::error!("service_ticket error: {e}");
+                log::error!(
+                    tckuuid = ticket.tckuuid.as_str(), src = ticket.src.as_str();
+                    "service_ticket error: {e}"
+                );
+            }
+            None
+        }
+
+        NetworkMessage::TicketProgress(progress) => {
+            if let Err(e) = progress_ticket(&progress) {
+                log::error!(tckuuid = progress.tckuuid.as_str(); "progress_ticket error: {e}");
+            }
+            None
+        }
+
+        NetworkMessage::TicketCancel(cancel) => {
+            if !cancel_ticket(&cancel.tckuuid) {
+                log::debug!(tckuuid = cancel.tckuuid.as_str(); "no cancellable work for ticket");
             }
             None
         }
 
         NetworkMessage::TicketTraceResponse(trace) => {
+            let tckuuid = trace.tckuuid.clone();
             if let Err(e) = service_trace(trace) {
-                log::error!("service_trace error: {e}");
+                log::error!(tckuuid = tckuuid.as_str(); "service_trace error: {e}");
             }
             None
         }
@@ -467,6 +1092,36 @@ fn process_network_message(
     }) // end Box::pin
 }
 
+/// Whether a ticket's form may run long enough that it should not be
+/// serviced inline: a followed tail, a topology walk, a streaming process, or
+/// a process that may wait in the process queue.
+fn services_in_background(form: &ControlForm) -> bool {
+    match form {
+        ControlForm::TailFile(f)    => f.follow,
+        ControlForm::GetTopology(_) => true,
+        ControlForm::SyncProcess(f) => f.stream || process_slots().is_some(),
+        _ => false,
+    }
+}
+
+/// Process the form in a ticket and route the response back to its originator.
+async fn service_ticket_request(mut ticket: NetworkTicket) {
+    log::debug!(
+        message_type = "ticket_request", tckuuid = ticket.tckuuid.as_str(), src = ticket.src.as_str();
+        "servicing {}", ticket.form.form_type()
+    );
+    ticket.form = match std::mem::take(&mut ticket.form) {
+        ControlForm::SyncProcess(f) => execute_sync_process(f, Some(&ticket)).await,
+        ControlForm::TailFile(f) if f.follow => follow_tail_file(&ticket, f).await,
+        form => process_control_form(form).await,
+    };
+    let src  = ticket.src.clone();
+    let dest = ticket.dest.clone().unwrap_or_default();
+    ticket.src  = dest;
+    ticket.dest = Some(src);
+    route_network_message(NetworkMessage::TicketResponse(ticket)).await;
+}
+
 // ── Scheduled background functions ───────────────────────────────────────────
 
 /// Replay pending network messages stored without a specific destination.
@@ -501,6 +1156,7 @@ pub async fn polling() {
     for peer_obj in peers {
         let peer = peer_obj.object.clone();
         tokio::spawn(async move {
+            let encoding = peer_encoding(&peer);
             let url = match peer.url {
                 Some(u) => u,
                 None    => return,
@@ -509,7 +1165,8 @@ pub async fn polling() {
                 url.clone(),
                 config().key(),
                 config().agtuuid.clone(),
-            );
+            )
+            .with_encoding(encoding);
             match client
                 .send_network_message(NetworkMessage::MessagesRequest(
                     NetworkMessagesRequest::default(),
@@ -517,6 +1174,9 @@ pub async fn polling() {
                 .await
             {
                 Ok(NetworkMessage::MessagesResponse(resp)) => {
+                    if let Some(ref agtuuid) = peer.agtuuid {
+                        metrics::peer_seen(agtuuid);
+                    }
                     for msg in resp.messages {
                         tokio::spawn(async move {
                             route_network_message(msg).await;
@@ -561,6 +1221,7 @@ pub async fn advertizing() {
             match create_route_advertisement() {
                 Ok(mut adv) => {
                     adv.dest = Some(agtuuid);
+                    metrics::advertisement_sent();
                     route_network_message(NetworkMessage::Advertisement(adv)).await;
                 }
                 Err(e) => log::error!("create_route_advertisement error: {e}"),
@@ -571,6 +1232,45 @@ pub async fn advertizing() {
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// Decrypt a request body and undo any compression its `Encoding` header names.
+fn open_request(
+    key: &[u8; 32],
+    request: &HttpRequest,
+    body: &[u8],
+) -> ActixResult<(Encoding, Vec<u8>)> {
+    let nonce = extract_header_hex(request, "Nonce")?;
+    let tag   = extract_header_hex(request, "Tag")?;
+
+    let header = request
+        .headers()
+        .get("Encoding")
+        .map(|v| v.to_str())
+        .transpose()
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    let encoding = body_encoding(header).map_err(actix_web::error::ErrorBadRequest)?;
+
+    let plaintext = decrypt(key, &nonce, &tag, body)
+        .and_then(|compressed| decompress(encoding, &compressed))
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    Ok((encoding, plaintext))
+}
+
+/// Compress a response body like the request was and encrypt it.
+fn seal_response(key: &[u8; 32], encoding: Encoding, raw: &[u8]) -> ActixResult<HttpResponse> {
+    let (nonce, tag, ct) = compress(encoding, raw)
+        .and_then(|body| encrypt(key, &body))
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let mut response = HttpResponse::Ok();
+    response
+        .append_header(("Nonce", hex::encode(nonce)))
+        .append_header(("Tag", hex::encode(tag)));
+    if encoding != Encoding::None {
+        response.append_header(("Encoding", encoding.as_str()));
+    }
+    Ok(response.body(ct))
+}
+
 fn extract_header_hex(req: &HttpRequest, name: &str) -> ActixResult<Vec<u8>> {
     let value = req
         .headers()
@@ -598,6 +1298,19 @@ fn config_to_json() -> Value {
         "max_weight":          c.max_weight,
         "ticket_timeout_secs": c.ticket_timeout_secs,
         "message_timeout_secs": c.message_timeout_secs,
+        "max_processes":       c.max_processes,
+        "rlimit_cpu_secs":     c.rlimit_cpu_secs,
+        "rlimit_as_bytes":     c.rlimit_as_bytes,
+        "rlimit_nofile":       c.rlimit_nofile,
+        "max_output_bytes":    c.max_output_bytes,
+        "body_encoding":       c.body_encoding.as_str(),
+        "log_file":            c.log_file,
+        "log_file_max_bytes":  c.log_file_max_bytes,
+        "log_file_count":      c.log_file_count,
+        "log_format":          c.log_format.as_str(),
+        "metrics_address":     c.metrics_address,
+        "labels":              format_labels(&c.labels),
+        "name":                c.name,
     })
 }
 
@@ -611,6 +1324,8 @@ fn dest_of(msg: &NetworkMessage) -> String {
         NetworkMessage::TicketTraceResponse(m) => m.dest.clone().unwrap_or_default(),
         NetworkMessage::TicketRequest(m)       => m.dest.clone().unwrap_or_default(),
         NetworkMessage::TicketResponse(m)      => m.dest.clone().unwrap_or_default(),
+        NetworkMessage::TicketProgress(m)      => m.dest.clone().unwrap_or_default(),
+        NetworkMessage::TicketCancel(m)        => m.dest.clone().unwrap_or_default(),
     }
 }
 
@@ -624,6 +1339,8 @@ fn src_of(msg: &NetworkMessage) -> String {
         NetworkMessage::TicketTraceResponse(m) => m.src.clone(),
         NetworkMessage::TicketRequest(m)       => m.src.clone(),
         NetworkMessage::TicketResponse(m)      => m.src.clone(),
+        NetworkMessage::TicketProgress(m)      => m.src.clone(),
+        NetworkMessage::TicketCancel(m)        => m.src.clone(),
     }
 }
 
@@ -637,6 +1354,8 @@ fn isrc_of(msg: &NetworkMessage) -> Option<String> {
         NetworkMessage::TicketTraceResponse(m) => m.isrc.clone(),
         NetworkMessage::TicketRequest(m)       => m.isrc.clone(),
         NetworkMessage::TicketResponse(m)      => m.isrc.clone(),
+        NetworkMessage::TicketProgress(m)      => m.isrc.clone(),
+        NetworkMessage::TicketCancel(m)        => m.isrc.clone(),
     }
 }
 
@@ -651,6 +1370,8 @@ fn set_dest(msg: &mut NetworkMessage, dest: String) {
         NetworkMessage::TicketTraceResponse(m) => m.dest = d,
         NetworkMessage::TicketRequest(m)       => m.dest = d,
         NetworkMessage::TicketResponse(m)      => m.dest = d,
+        NetworkMessage::TicketProgress(m)      => m.dest = d,
+        NetworkMessage::TicketCancel(m)        => m.dest = d,
     }
 }
 
@@ -3,22 +3,47 @@
 //! Mirrors Python's `stembot/ticketing.py`.
 //!
 //! Manages the lifecycle of control form tickets routed through the network:
-//! - `read_ticket` — look up a ticket and populate hops if tracing is enabled
-//! - `close_ticket` — delete a ticket by UUID
-//! - `service_ticket` — record the response form and service time for a ticket
+//! - `read_ticket` — look up a ticket and populate hops if tracing is enabled,
+//!   and the per-agent results and completion counts of a multicast ticket
+//! - `close_ticket` — delete a ticket (and a multicast ticket's per-agent tickets) by UUID
+//! - `service_ticket` — record the response form and service time for a ticket,
+//!   servicing its multicast ticket once every per-agent ticket is serviced
+//! - `fail_ticket` — store a ticket that could not be sent as serviced with an error
+//! - `progress_ticket` — append partial output to a ticket still being serviced
+//! - `wait_tickets` — long-poll until a set of tickets is serviced or moves on
+//! - `CancelWatch` / `cancel_ticket` — let work on a ticket being serviced
+//!   here be stopped early by its originator
 //! - `service_trace` — upsert a hop trace into the traces collection
 //! - `dedup_trace` — deduplicate trace messages to prevent infinite loops
 //! - `expire_tickets` — remove stale tickets and traces
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{timeout_at, Instant};
 
 use crate::collections::{open_tickets, open_traces};
-use crate::models::control::{CheckTicket, CloseTicket, ControlFormTicket};
-use crate::models::network::{NetworkTicket, TicketTraceResponse};
+use crate::dao::Collection;
+use crate::metrics;
+use crate::models::control::{
+    CheckTicket, CloseTicket, ControlFormTicket, Progress, TicketCounts, WaitTickets,
+};
+use crate::models::network::{NetworkTicket, TicketProgress, TicketTraceResponse};
 use crate::config::config;
 
+/// How long a `WaitTickets` blocks when it does not say.
+pub const DEFAULT_WAIT_SECS: f64 = 30.0;
+
+/// The longest a `WaitTickets` may block before it is answered.
+pub const MAX_WAIT_SECS: f64 = 60.0;
+
+/// Bytes of partial output kept on a ticket; older chunks lose their text.
+pub const MAX_PROGRESS_BYTES: usize = 1024 * 1024;
+
 fn unix_now() -> f64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -26,10 +51,69 @@ fn unix_now() -> f64 {
         .as_secs_f64()
 }
 
+/// Carries the UUID of each stored ticket as it is serviced, progressed,
+/// closed or expired.
+fn ticket_events() -> &'static broadcast::Sender<String> {
+    static TICKET_EVENTS: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+    TICKET_EVENTS.get_or_init(|| broadcast::Sender::new(1024))
+}
+
+fn ticket_changed(tckuuid: &str) {
+    // No waiters is not an error.
+    let _ = ticket_events().send(tckuuid.to_string());
+}
+
+/// The status of each listed ticket still stored here.
+fn ticket_statuses(tckuuids: &[String]) -> Result<Vec<CheckTicket>> {
+    let tickets = open_tickets()?;
+    let mut statuses = Vec::new();
+    for tckuuid in tckuuids {
+        if let Some(ticket) = tickets.find(&[("tckuuid", tckuuid.as_str())])?.first() {
+            statuses.push(CheckTicket {
+                tckuuid:        tckuuid.clone(),
+                create_time:    Some(ticket.object.create_time),
+                service_time:   ticket.object.service_time,
+                queue_position: ticket.object.queue_position,
+                last_seq:       last_seq(&ticket.object),
+                ..Default::default()
+            });
+        }
+    }
+    Ok(statuses)
+}
+
+/// `seq` of the latest partial output recorded on `ticket`.
+fn last_seq(ticket: &ControlFormTicket) -> Option<u64> {
+    ticket.progress.iter().map(|p| p.seq).max()
+}
+
+/// `ticket_statuses` off the async runtime.
+async fn read_ticket_statuses(tckuuids: &[String]) -> Result<Vec<CheckTicket>> {
+    let tckuuids = tckuuids.to_vec();
+    tokio::task::spawn_blocking(move || ticket_statuses(&tckuuids)).await?
+}
+
+/// Whether a wait that began at `before` can end at `now`: every ticket is
+/// serviced (or gone), or one still pending has a new queue position or new
+/// partial output.
+fn wait_over(before: &[CheckTicket], now: &[CheckTicket]) -> bool {
+    let moved = |t: &CheckTicket| {
+        t.service_time.is_none()
+            && before
+                .iter()
+                .find(|b| b.tckuuid == t.tckuuid)
+                .is_some_and(|b| b.queue_position != t.queue_position || b.last_seq != t.last_seq)
+    };
+    now.iter().all(|t| t.service_time.is_some()) || now.iter().any(moved)
+}
+
 // ── Public API ────────────────────────────────────────────────────────────────
 
 /// Retrieve a ticket by UUID, populating hops if tracing is enabled.
 ///
+/// For a multicast ticket, also populates `results` with its per-agent
+/// tickets, in `dsts` order, and `counts` with how many are serviced.
+///
 /// Returns `None` if no ticket with the given UUID exists.
 /// Sets the returned ticket's `form_type` to `ReadTicket`.
 ///
@@ -38,56 +122,260 @@ pub fn read_ticket(control_form_ticket: &ControlFormTicket) -> Result<Option<Con
     let tickets = open_tickets()?;
     let traces  = open_traces()?;
 
-    if let Some(ticket) = tickets.find(&[("tckuuid", control_form_ticket.tckuuid.as_str())])?.first_mut() {
-        if ticket.object.tracing {
-            ticket.object.hops = traces
-                .find(&[("tckuuid", ticket.object.tckuuid.as_str())])?
+    let with_hops = |mut ticket: ControlFormTicket| -> Result<ControlFormTicket> {
+        if ticket.tracing {
+            ticket.hops = traces
+                .find(&[("tckuuid", ticket.tckuuid.as_str())])?
                 .into_iter()
                 .map(|t| t.object.hop())
                 .collect();
         }
-        ticket.object.form_type = "read_ticket".to_string();
-        return Ok(Some(ticket.object.clone()));
+        Ok(ticket)
+    };
+
+    if let Some(ticket) = tickets.find(&[("tckuuid", control_form_ticket.tckuuid.as_str())])?.pop() {
+        let mut ticket = with_hops(ticket.object)?;
+        if ticket.is_multicast() {
+            let mut results = tickets
+                .find(&[("parent", ticket.tckuuid.as_str())])?
+                .into_iter()
+                .map(|t| with_hops(t.object))
+                .collect::<Result<Vec<_>>>()?;
+            results.sort_by_key(|t| ticket.dsts.iter().position(|d| *d == t.dst));
+            ticket.counts  = Some(multicast_counts(&ticket, &results));
+            ticket.results = results;
+        }
+        ticket.form_type = "read_ticket".to_string();
+        return Ok(Some(ticket));
     }
     Ok(None)
 }
 
-/// Delete a ticket by UUID from the in-memory ticket collection.
+/// Completion counts of a multicast ticket from its per-agent tickets.
+fn multicast_counts(ticket: &ControlFormTicket, results: &[ControlFormTicket]) -> TicketCounts {
+    let total    = ticket.dsts.len() as u64;
+    let serviced = results.iter().filter(|t| t.service_time.is_some()).count() as u64;
+    TicketCounts { total, serviced, pending: total.saturating_sub(serviced) }
+}
+
+/// Delete a ticket by UUID from the in-memory ticket collection, along with
+/// the per-agent tickets of a multicast ticket.
 ///
 /// Mirrors `close_ticket(form)` in Python.
 pub fn close_ticket(form: &CloseTicket) -> Result<()> {
-    open_tickets()?.pop(&[("tckuuid", form.tckuuid.as_str())])?;
+    let tickets = open_tickets()?;
+    tickets.pop(&[("tckuuid", form.tckuuid.as_str())])?;
+    ticket_changed(&form.tckuuid);
+    for child in tickets.pop(&[("parent", form.tckuuid.as_str())])? {
+        ticket_changed(&child.object.tckuuid);
+    }
     Ok(())
 }
 
 /// Check the status of a ticket by UUID.
 ///
-/// Populates `create_time` and `service_time` from the stored ticket.
+/// Populates `create_time`, `service_time`, `queue_position` and `last_seq`
+/// from the stored ticket.
 /// Mirrors `check_ticket(form)` in Python.
 pub fn check_ticket(form: CheckTicket) -> Result<CheckTicket> {
     let mut form = form;
     let tickets = open_tickets()?;
     if let Some(ticket) = tickets.find(&[("tckuuid", form.tckuuid.as_str())])?.first() {
-        form.create_time  = Some(ticket.object.create_time);
-        form.service_time = ticket.object.service_time;
+        form.create_time    = Some(ticket.object.create_time);
+        form.service_time   = ticket.object.service_time;
+        form.queue_position = ticket.object.queue_position;
+        form.last_seq       = last_seq(&ticket.object);
     }
     Ok(form)
 }
 
+/// Block until every ticket in `form.tckuuids` is serviced, one has a new
+/// queue position or new partial output, or the form's timeout passes, then
+/// fill in `form.tickets` with the status of those still stored here.
+///
+/// Statuses passed in `form.tickets` are what the caller last saw, and are
+/// waited on instead of the ones stored when the wait begins.
+pub async fn wait_tickets(mut form: WaitTickets) -> Result<WaitTickets> {
+    let wait = form.timeout.unwrap_or(DEFAULT_WAIT_SECS).clamp(0.0, MAX_WAIT_SECS);
+    let deadline = Instant::now() + Duration::from_secs_f64(wait);
+
+    // Subscribe before the first read so that no change in between is missed.
+    let mut events = ticket_events().subscribe();
+    let mut now = read_ticket_statuses(&form.tckuuids).await?;
+    let before = match std::mem::take(&mut form.tickets) {
+        seen if seen.is_empty() => now.clone(),
+        seen => seen,
+    };
+    while !wait_over(&before, &now) {
+        match timeout_at(deadline, events.recv()).await {
+            Ok(Ok(tckuuid)) if !form.tckuuids.contains(&tckuuid) => continue,
+            // A lagging waiter may have missed one of its tickets.
+            Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => now = read_ticket_statuses(&form.tckuuids).await?,
+            _ => break,
+        }
+    }
+    form.tickets = now;
+    Ok(form)
+}
+
 /// Update a ticket with the serviced control form and service time.
 ///
+/// Servicing the last outstanding per-agent ticket of a multicast ticket
+/// services the multicast ticket too.
+///
 /// Mirrors `service_ticket(network_ticket)`.
 pub fn service_ticket(network_ticket: &NetworkTicket) -> Result<()> {
     let tickets = open_tickets()?;
     for mut ticket in tickets.find(&[("tckuuid", network_ticket.tckuuid.as_str())])? {
+        if ticket.object.service_time.is_none() {
+            metrics::inc("stembot_tickets_serviced_total", &[]);
+            metrics::observe("stembot_ticket_latency_seconds", &[], unix_now() - ticket.object.create_time);
+        }
         ticket.object.form         = network_ticket.form.clone();
         ticket.object.error        = network_ticket.error.clone();
         ticket.object.service_time = Some(unix_now());
         ticket.commit()?;
+
+        ticket_changed(&ticket.object.tckuuid);
+
+        if let Some(ref parent) = ticket.object.parent {
+            complete_multicast(&tickets, parent)?;
+            ticket_changed(parent);
+        }
+    }
+    Ok(())
+}
+
+/// Store `ticket` as serviced with `error`, for a per-agent ticket whose
+/// request could not be stored or sent, so its multicast ticket can still
+/// complete.
+pub fn fail_ticket(mut ticket: ControlFormTicket, error: String) -> Result<()> {
+    let tickets = open_tickets()?;
+    ticket.error        = Some(error);
+    ticket.service_time = Some(unix_now());
+    let parent = ticket.parent.clone();
+    let tckuuid = ticket.tckuuid.clone();
+    tickets.upsert_object(ticket)?;
+    ticket_changed(&tckuuid);
+    if let Some(ref parent) = parent {
+        complete_multicast(&tickets, parent)?;
+        ticket_changed(parent);
     }
     Ok(())
 }
 
+/// Service the multicast ticket `parent` once none of its per-agent tickets
+/// is pending.
+fn complete_multicast(tickets: &Collection<ControlFormTicket>, parent: &str) -> Result<()> {
+    for mut multicast in tickets.find(&[("tckuuid", parent)])? {
+        if multicast.object.service_time.is_some() {
+            continue;
+        }
+        let results: Vec<ControlFormTicket> = tickets
+            .find(&[("parent", parent)])?
+            .into_iter()
+            .map(|t| t.object)
+            .collect();
+        if multicast_counts(&multicast.object, &results).pending == 0 {
+            multicast.object.service_time = Some(unix_now());
+            multicast.commit()?;
+        }
+    }
+    Ok(())
+}
+
+/// Record partial output or a queue position on a ticket that has not been
+/// serviced yet.
+///
+/// Output chunks whose `seq` is already recorded, and anything that arrives
+/// after the final response, are ignored.  Chunks are kept in `seq` order and
+/// trimmed to [`MAX_PROGRESS_BYTES`] by [`trim_progress`].
+pub fn progress_ticket(ticket_progress: &TicketProgress) -> Result<()> {
+    let has_output = ticket_progress.stdout.is_some() || ticket_progress.stderr.is_some();
+    let tickets = open_tickets()?;
+    for mut ticket in tickets.find(&[("tckuuid", ticket_progress.tckuuid.as_str())])? {
+        if ticket.object.service_time.is_some() {
+            continue;
+        }
+        if ticket_progress.queue_position.is_some() {
+            ticket.object.queue_position = ticket_progress.queue_position;
+        }
+        if has_output && !ticket.object.progress.iter().any(|p| p.seq == ticket_progress.seq) {
+            ticket.object.progress.push(ticket_progress.progress());
+            trim_progress(&mut ticket.object.progress);
+        }
+        ticket.commit()?;
+        ticket_changed(&ticket.object.tckuuid);
+    }
+    Ok(())
+}
+
+/// Sort `progress` by `seq` and drop the text of the oldest chunks once the
+/// rest hold [`MAX_PROGRESS_BYTES`].  The emptied chunks keep their `seq` and
+/// offsets, so a reader following the output sees where text was dropped.
+fn trim_progress(progress: &mut [Progress]) {
+    progress.sort_by_key(|p| p.seq);
+    let mut kept = 0;
+    for p in progress.iter_mut().rev() {
+        let len = p.stdout.as_ref().map_or(0, String::len) + p.stderr.as_ref().map_or(0, String::len);
+        if kept + len > MAX_PROGRESS_BYTES {
+            p.stdout = None;
+            p.stderr = None;
+        } else {
+            kept += len;
+        }
+    }
+}
+
+/// Cancellation flags of the tickets being serviced here, by `tckuuid`.
+fn cancellations() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    static CANCELLATIONS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+    CANCELLATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A ticket being serviced here that its originator may cancel.  The ticket
+/// stops being cancellable when the watch is dropped.
+pub struct CancelWatch {
+    tckuuid: String,
+    flag:    Arc<AtomicBool>,
+}
+
+impl CancelWatch {
+    pub fn new(tckuuid: &str) -> Self {
+        let flag = Arc::new(AtomicBool::new(false));
+        cancellations()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tckuuid.to_string(), Arc::clone(&flag));
+        Self { tckuuid: tckuuid.to_string(), flag }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for CancelWatch {
+    fn drop(&mut self) {
+        cancellations()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.tckuuid);
+    }
+}
+
+/// Cancel a ticket being serviced here.  Returns `false` if it is not (or no
+/// longer) being serviced here.
+pub fn cancel_ticket(tckuuid: &str) -> bool {
+    match cancellations().lock().unwrap_or_else(|e| e.into_inner()).get(tckuuid) {
+        Some(flag) => {
+            flag.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
 /// Add hop information to a ticket's trace for route tracking.
 ///
 /// Mirrors `service_trace(ticket_trace)`.
@@ -156,8 +444,12 @@ pub fn expire_tickets() -> Result<()> {
     let cutoff = unix_now() - config().ticket_timeout_secs as f64;
     let cutoff_str = format!("$lt:{}", cutoff);
 
-    for obj in open_tickets()?.pop(&[("create_time", cutoff_str.as_str())])? {
+    let expired = open_tickets()?.pop(&[("create_time", cutoff_str.as_str())])?;
+    for obj in expired {
+        ticket_changed(&obj.object.tckuuid);
+        metrics::inc("stembot_tickets_expired_total", &[]);
         log::warn!(
+            tckuuid = obj.object.tckuuid.as_str(), dest = obj.object.dst.as_str();
             "Expiring ticket {}:{}",
             obj.object.form_type,
             obj.object.tckuuid
@@ -165,8 +457,153 @@ pub fn expire_tickets() -> Result<()> {
     }
 
     for obj in open_traces()?.pop(&[("hop_time", cutoff_str.as_str())])? {
-        log::debug!("Expiring trace {}", obj.object.tckuuid);
+        log::debug!(tckuuid = obj.object.tckuuid.as_str(); "Expiring trace {}", obj.object.tckuuid);
     }
 
     Ok(())
 }
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(tckuuid: &str, service_time: Option<f64>, queue_position: Option<u64>) -> CheckTicket {
+        CheckTicket { tckuuid: tckuuid.into(), service_time, queue_position, ..Default::default() }
+    }
+
+    #[test]
+    fn test_wait_over() {
+        let pending = [status("t1", None, Some(2)), status("t2", None, None)];
+        assert!(!wait_over(&pending, &pending));
+
+        let one_serviced = [status("t1", Some(1.0), None), status("t2", None, None)];
+        assert!(!wait_over(&pending, &one_serviced));
+
+        let moved = [status("t1", None, Some(1)), status("t2", None, None)];
+        assert!(wait_over(&pending, &moved));
+
+        let progressed = [status("t1", None, Some(2)), CheckTicket { last_seq: Some(0), ..status("t2", None, None) }];
+        assert!(wait_over(&pending, &progressed));
+
+        let all_serviced = [status("t1", Some(1.0), None), status("t2", Some(2.0), None)];
+        assert!(wait_over(&pending, &all_serviced));
+
+        // Tickets that are no longer stored have nothing left to wait for.
+        assert!(wait_over(&pending, &[]));
+    }
+
+    #[test]
+    fn test_trim_progress_empties_oldest_chunks() {
+        let chunk = |seq, len| Progress { seq, stdout: Some("x".repeat(len)), ..Default::default() };
+        let mut progress = vec![
+            chunk(2, MAX_PROGRESS_BYTES / 2),
+            chunk(0, 10),
+            chunk(1, MAX_PROGRESS_BYTES / 2),
+        ];
+        trim_progress(&mut progress);
+        assert_eq!(progress.iter().map(|p| p.seq).collect::<Vec<_>>(), [0, 1, 2]);
+        assert!(progress[0].stdout.is_none());
+        assert!(progress[1].stdout.is_some() && progress[2].stdout.is_some());
+    }
+
+    #[test]
+    fn test_multicast_counts() {
+        let multicast = ControlFormTicket {
+            dsts: vec!["a1".into(), "a2".into(), "a3".into()],
+            ..ControlFormTicket::default()
+        };
+        let results = [
+            ControlFormTicket { dst: "a1".into(), service_time: Some(1.0), ..ControlFormTicket::default() },
+            ControlFormTicket { dst: "a2".into(), ..ControlFormTicket::default() },
+        ];
+        assert_eq!(
+            multicast_counts(&multicast, &results),
+            TicketCounts { total: 3, serviced: 1, pending: 2 }
+        );
+    }
+
+    #[test]
+    fn test_fail_ticket_completes_multicast() {
+        let multicast = ControlFormTicket { dsts: vec!["a1".into()], ..ControlFormTicket::default() };
+        open_tickets().unwrap().upsert_object(multicast.clone()).unwrap();
+        let child = ControlFormTicket {
+            dst:    "a1".into(),
+            parent: Some(multicast.tckuuid.clone()),
+            ..ControlFormTicket::default()
+        };
+        fail_ticket(child, "failed to store ticket".into()).unwrap();
+
+        let read = read_ticket(&multicast).unwrap().unwrap();
+        assert!(read.service_time.is_some());
+        assert_eq!(read.results.len(), 1);
+        assert_eq!(read.results[0].error.as_deref(), Some("failed to store ticket"));
+        close_ticket(&CloseTicket { tckuuid: multicast.tckuuid, ..Default::default() }).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_tickets_ends_on_waited_ticket_only() {
+        let waited = ControlFormTicket::default();
+        let other  = ControlFormTicket::default();
+        open_tickets().unwrap().upsert_object(waited.clone()).unwrap();
+        open_tickets().unwrap().upsert_object(other.clone()).unwrap();
+
+        let wait = tokio::spawn(wait_tickets(WaitTickets {
+            tckuuids: vec![waited.tckuuid.clone()],
+            timeout:  Some(5.0),
+            ..Default::default()
+        }));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let serviced = |ticket: &ControlFormTicket| NetworkTicket {
+            tckuuid:      ticket.tckuuid.clone(),
+            form:         ticket.form.clone(),
+            tracing:      false,
+            src:          String::new(),
+            dest:         None,
+            isrc:         None,
+            timestamp:    None,
+            create_time:  None,
+            service_time: None,
+            error:        None,
+            objuuid:      None,
+            coluuid:      None,
+        };
+        service_ticket(&serviced(&other)).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!wait.is_finished());
+
+        service_ticket(&serviced(&waited)).unwrap();
+        let form = wait.await.unwrap().unwrap();
+        assert_eq!(form.tickets.len(), 1);
+        assert!(form.tickets[0].service_time.is_some());
+
+        for ticket in [waited, other] {
+            close_ticket(&CloseTicket { tckuuid: ticket.tckuuid, ..Default::default() }).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_tickets_ends_at_once_when_caller_is_behind() {
+        let ticket = ControlFormTicket::default();
+        open_tickets().unwrap().upsert_object(ticket.clone()).unwrap();
+        progress_ticket(&TicketProgress {
+            tckuuid: ticket.tckuuid.clone(),
+            seq:     0,
+            stdout:  Some("line\n".into()),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let wait = wait_tickets(WaitTickets {
+            tckuuids: vec![ticket.tckuuid.clone()],
+            timeout:  Some(5.0),
+            tickets:  vec![status(&ticket.tckuuid, None, None)],
+            ..Default::default()
+        });
+        let form = tokio::time::timeout(Duration::from_secs(1), wait).await.unwrap().unwrap();
+        assert_eq!(form.tickets[0].last_seq, Some(0));
+
+        close_ticket(&CloseTicket { tckuuid: ticket.tckuuid, ..Default::default() }).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+//! Whole-mesh topology discovery for the `GetTopology` control form.
+//!
+//! The walk starts from this agent's own peer and route tables, asks every
+//! agent it knows of for its peers with a multicast `GetPeers` ticket, and
+//! repeats for agents first seen in the answers until none are left or the
+//! timeout passes.  `topology_dot` renders the result for Graphviz.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::time::Instant;
+
+use crate::config::config;
+use crate::models::control::{
+    CloseTicket, ControlForm, ControlFormTicket, GetPeers, GetTopology, TopologyLink, TopologyNode,
+    WaitTickets,
+};
+use crate::models::routing::{Labels, Peer};
+use crate::peering::{get_peers, get_routes};
+use crate::processor::create_form_ticket;
+use crate::ticketing::{close_ticket, read_ticket, wait_tickets};
+
+/// How long a walk runs when the form gives no timeout.
+pub const DEFAULT_TOPOLOGY_SECS: f64 = 15.0;
+/// The longest a walk may run.
+pub const MAX_TOPOLOGY_SECS: f64 = 120.0;
+
+/// What an agent's peer-table entry says about its link to the peer.
+fn link(peer: &Peer) -> Option<TopologyLink> {
+    Some(TopologyLink {
+        agtuuid: peer.agtuuid.clone()?,
+        polling: peer.polling,
+        url:     peer.url.clone(),
+    })
+}
+
+/// Walk the mesh and fill in `form.agents`, sorted by UUID.
+pub async fn get_topology(mut form: GetTopology) -> GetTopology {
+    let timeout = form.timeout.unwrap_or(DEFAULT_TOPOLOGY_SECS).clamp(0.0, MAX_TOPOLOGY_SECS);
+    match walk(Instant::now() + Duration::from_secs_f64(timeout)).await {
+        Ok(agents) => form.agents = agents,
+        Err(e) => form.error = Some(e.to_string()),
+    }
+    form
+}
+
+async fn walk(deadline: Instant) -> Result<Vec<TopologyNode>> {
+    let routes = get_routes()?;
+    let peers  = get_peers()?;
+    let gateways: BTreeSet<String> = routes.iter().map(|r| r.gtwuuid.clone()).collect();
+
+    // Names and labels as last heard from advertisements and peer tables.
+    let mut known: BTreeMap<String, (Option<String>, Labels)> = BTreeMap::new();
+    for route in &routes {
+        known.insert(route.agtuuid.clone(), (route.name.clone(), route.labels.clone()));
+    }
+    for peer in &peers {
+        if let Some(ref agtuuid) = peer.agtuuid {
+            known.insert(agtuuid.clone(), (peer.name.clone(), peer.labels.clone()));
+        }
+    }
+
+    let mut nodes: BTreeMap<String, TopologyNode> = BTreeMap::new();
+    nodes.insert(config().agtuuid.clone(), TopologyNode {
+        agtuuid:   config().agtuuid.clone(),
+        name:      config().name.clone(),
+        labels:    config().labels.clone(),
+        reachable: true,
+        peers:     peers.iter().filter_map(link).collect(),
+        ..Default::default()
+    });
+
+    let mut pending: BTreeSet<String> = known.keys().cloned().collect();
+    while !pending.is_empty() && Instant::now() < deadline {
+        for (agtuuid, answer) in ask_peers(pending.iter().cloned().collect(), deadline).await? {
+            let node = match answer {
+                Ok(peers) => {
+                    for peer in &peers {
+                        if let Some(ref a) = peer.agtuuid {
+                            known.entry(a.clone()).or_insert((peer.name.clone(), peer.labels.clone()));
+                        }
+                    }
+                    TopologyNode {
+                        agtuuid:   agtuuid.clone(),
+                        reachable: true,
+                        peers:     peers.iter().filter_map(link).collect(),
+                        ..Default::default()
+                    }
+                }
+                Err(e) => TopologyNode { agtuuid: agtuuid.clone(), error: Some(e), ..Default::default() },
+            };
+            nodes.insert(agtuuid, node);
+        }
+        pending = nodes
+            .values()
+            .flat_map(|n| n.peers.iter().map(|l| l.agtuuid.clone()))
+            .chain(known.keys().cloned())
+            .filter(|a| !nodes.contains_key(a))
+            .collect();
+    }
+    for agtuuid in pending {
+        nodes.insert(agtuuid.clone(), TopologyNode {
+            agtuuid,
+            error: Some("walk timed out".to_string()),
+            ..Default::default()
+        });
+    }
+
+    for node in nodes.values_mut() {
+        if node.agtuuid != config().agtuuid {
+            if let Some((name, labels)) = known.get(&node.agtuuid) {
+                node.name   = name.clone();
+                node.labels = labels.clone();
+            }
+        }
+        node.gateway = gateways.contains(&node.agtuuid);
+    }
+    Ok(nodes.into_values().collect())
+}
+
+/// Ask each of `dsts` for its peers with one multicast ticket and wait for
+/// the answers until `deadline`, returning each agent's peers or why it gave
+/// none.
+async fn ask_peers(
+    dsts: Vec<String>,
+    deadline: Instant,
+) -> Result<BTreeMap<String, Result<Vec<Peer>, String>>> {
+    let ticket = create_form_ticket(ControlFormTicket {
+        dsts,
+        form: ControlForm::GetPeers(GetPeers::default()),
+        ..ControlFormTicket::default()
+    })
+    .await;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now()).as_secs_f64();
+        let wait = wait_tickets(WaitTickets {
+            tckuuids: vec![ticket.tckuuid.clone()],
+            timeout:  Some(remaining),
+            ..Default::default()
+        })
+        .await?;
+        if remaining <= 0.0 || wait.tickets.iter().all(|t| t.service_time.is_some()) {
+            break;
+        }
+    }
+
+    let results = read_ticket(&ticket)?.map(|t| t.results).unwrap_or_default();
+    close_ticket(&CloseTicket { tckuuid: ticket.tckuuid.clone(), ..Default::default() })?;
+
+    Ok(ticket
+        .dsts
+        .iter()
+        .map(|dst| {
+            let answer = match results.iter().find(|r| r.dst == *dst) {
+                Some(r) if r.service_time.is_some() => match (&r.error, &r.form) {
+                    (Some(e), _) => Err(e.clone()),
+                    (None, ControlForm::GetPeers(f)) => match f.error {
+                        Some(ref e) => Err(e.clone()),
+                        None        => Ok(f.peers.clone()),
+                    },
+                    _ => Err("unexpected response form".to_string()),
+                },
+                _ => Err("no response".to_string()),
+            };
+            (dst.clone(), answer)
+        })
+        .collect())
+}
+
+// ── Graphviz ──────────────────────────────────────────────────────────────────
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+/// Render a topology as a Graphviz DOT digraph.
+///
+/// A link both agents list without polling is drawn once with arrows both
+/// ways.  A polling link is dashed and points from the polling agent to the
+/// peer it polls; links without a URL are left out, as the agent at the other
+/// end draws them.  Unreachable agents are red and dashed, and an unreachable
+/// gateway is labelled as one.
+pub fn topology_dot(agents: &[TopologyNode]) -> String {
+    let mut dot = String::from("digraph stembot {\n    node [shape=box];\n");
+
+    for node in agents {
+        let mut lines = vec![escape(&node.agtuuid)];
+        lines.extend(node.name.as_deref().map(escape));
+        let attrs = match (node.reachable, node.gateway) {
+            (true, _)      => "",
+            (false, true)  => {
+                lines.push("unreachable gateway".to_string());
+                ", color=red, fontcolor=red, style=\"dashed,bold\""
+            }
+            (false, false) => {
+                lines.push("unreachable".to_string());
+                ", color=red, fontcolor=red, style=dashed"
+            }
+        };
+        dot.push_str(&format!(
+            "    {} [label=\"{}\"{attrs}];\n",
+            quote(&node.agtuuid),
+            lines.join("\\n"),
+        ));
+    }
+
+    let direct: BTreeSet<(&str, &str)> = agents
+        .iter()
+        .flat_map(|n| n.peers.iter().map(move |l| (n, l)))
+        .filter(|(_, l)| !l.polling && l.url.is_some())
+        .map(|(n, l)| (n.agtuuid.as_str(), l.agtuuid.as_str()))
+        .collect();
+    for &(a, b) in &direct {
+        if direct.contains(&(b, a)) {
+            if a < b {
+                dot.push_str(&format!("    {} -> {} [dir=both];\n", quote(a), quote(b)));
+            }
+        } else {
+            dot.push_str(&format!("    {} -> {};\n", quote(a), quote(b)));
+        }
+    }
+    for node in agents {
+        for l in node.peers.iter().filter(|l| l.polling && l.url.is_some()) {
+            dot.push_str(&format!(
+                "    {} -> {} [style=dashed, color=blue, label=\"polling\"];\n",
+                quote(&node.agtuuid),
+                quote(&l.agtuuid),
+            ));
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(agtuuid: &str, polling: bool, url: Option<&str>) -> TopologyLink {
+        TopologyLink { agtuuid: agtuuid.into(), polling, url: url.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_topology_dot() {
+        let agents = vec![
+            TopologyNode {
+                agtuuid:   "a1".into(),
+                name:      Some("web".into()),
+                reachable: true,
+                peers:     vec![peer("a2", false, Some("http://a2")), peer("a3", false, None)],
+                ..Default::default()
+            },
+            TopologyNode {
+                agtuuid:   "a2".into(),
+                reachable: true,
+                peers:     vec![peer("a1", false, Some("http://a1")), peer("a4", false, Some("http://a4"))],
+                ..Default::default()
+            },
+            TopologyNode {
+                agtuuid:   "a3".into(),
+                reachable: true,
+                peers:     vec![peer("a1", true, Some("http://a1"))],
+                ..Default::default()
+            },
+            TopologyNode { agtuuid: "a4".into(), gateway: true, ..Default::default() },
+            TopologyNode { agtuuid: "a\"5".into(), ..Default::default() },
+        ];
+        let dot = topology_dot(&agents);
+        assert!(dot.starts_with("digraph stembot {\n"));
+        assert!(dot.contains(r#""a1" [label="a1\nweb"];"#));
+        assert!(dot.contains("\"a1\" -> \"a2\" [dir=both];"));
+        assert!(!dot.contains("\"a2\" -> \"a1\""));
+        assert!(dot.contains("\"a2\" -> \"a4\";"));
+        assert!(dot.contains("\"a3\" -> \"a1\" [style=dashed, color=blue, label=\"polling\"];"));
+        assert!(!dot.contains("\"a1\" -> \"a3\""));
+        assert!(dot.contains("unreachable gateway\", color=red, fontcolor=red, style=\"dashed,bold\""));
+        assert!(dot.contains(r#""a\"5" [label="a\"5\nunreachable", color=red"#));
+        assert!(dot.ends_with("}\n"));
+    }
+}